@@ -0,0 +1,46 @@
+//! Times `eval_set` formulas on large universes with the HashSet path, the compiled
+//! formula and the dense backends: `cargo bench --bench set_backends`.
+//! Every run starts from the same `Vec<i32>` sets, conversions included.
+
+use {
+    itertools::Itertools,
+    rand::Rng,
+    ready_set_boole::{DenseBackend, eval_hash_sets, eval_set, eval_set_dense},
+    std::{collections::HashSet, hint::black_box, time::Instant},
+};
+
+const RUNS: u32 = 5;
+
+fn time<R>(name: &str, mut f: impl FnMut() -> R) {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    println!("  {name:<10} {:>12.2?}", start.elapsed() / RUNS);
+}
+
+fn main() {
+    let mut rng = rand::rng();
+    for (size, density) in [(1_000_000, 0.5), (4_000_000, 0.5), (4_000_000, 0.01)] {
+        let sets = (0..3)
+            .map(|_| (0..size).filter(|_| rng.random_bool(density)).collect_vec())
+            .collect_vec();
+        for formula in ["AB|C&", "AB=C>!"] {
+            println!("{formula} on {size} elements, density {density}");
+            time("HashSet", || {
+                let sets = sets
+                    .iter()
+                    .map(|set| set.iter().copied().collect::<HashSet<i32>>())
+                    .collect_vec();
+                eval_hash_sets(formula, &sets)
+            });
+            time("compiled", || eval_set(formula, sets.clone()));
+            time("bitset", || {
+                eval_set_dense(formula, sets.clone(), DenseBackend::BitSet)
+            });
+            time("roaring", || {
+                eval_set_dense(formula, sets.clone(), DenseBackend::Roaring)
+            });
+        }
+    }
+}
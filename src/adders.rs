@@ -0,0 +1,414 @@
+//! Adder topologies built from two-input gates, each reporting its gate count
+//! and logic depth along with the sum
+
+use crate::numbers::Word;
+
+/// A wire of the circuit: its value and the number of gates on its longest input path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Signal {
+    pub value: bool,
+    pub depth: usize,
+}
+
+/// Counts the gates as they are instantiated. `mux` is a 2:1 multiplexer
+/// and counts as a single gate, inputs and constants are free.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Circuit {
+    pub gates: usize,
+}
+
+impl Circuit {
+    fn gate(&mut self, value: bool, inputs: &[Signal]) -> Signal {
+        self.gates += 1;
+        Signal {
+            value,
+            depth: inputs.iter().map(|signal| signal.depth).max().unwrap_or(0) + 1,
+        }
+    }
+
+    pub fn input(value: bool) -> Signal {
+        Signal { value, depth: 0 }
+    }
+
+    pub fn not(&mut self, a: Signal) -> Signal {
+        self.gate(!a.value, &[a])
+    }
+
+    pub fn and(&mut self, a: Signal, b: Signal) -> Signal {
+        self.gate(a.value && b.value, &[a, b])
+    }
+
+    pub fn or(&mut self, a: Signal, b: Signal) -> Signal {
+        self.gate(a.value || b.value, &[a, b])
+    }
+
+    pub fn xor(&mut self, a: Signal, b: Signal) -> Signal {
+        self.gate(a.value ^ b.value, &[a, b])
+    }
+
+    /// `if select { a } else { b }`
+    pub fn mux(&mut self, select: Signal, a: Signal, b: Signal) -> Signal {
+        self.gate(
+            if select.value { a.value } else { b.value },
+            &[select, a, b],
+        )
+    }
+
+    /// balanced tree of two-input gates, `None` without operands
+    pub fn reduce(
+        &mut self,
+        signals: &[Signal],
+        gate: fn(&mut Self, Signal, Signal) -> Signal,
+    ) -> Option<Signal> {
+        match signals {
+            [] => None,
+            [signal] => Some(*signal),
+            _ => {
+                let (left, right) = signals.split_at(signals.len() / 2);
+                let left = self.reduce(left, gate).unwrap();
+                let right = self.reduce(right, gate).unwrap();
+                Some(gate(self, left, right))
+            }
+        }
+    }
+
+    /// returns the sum and the carry-out
+    pub fn half_adder(&mut self, a: Signal, b: Signal) -> (Signal, Signal) {
+        (self.xor(a, b), self.and(a, b))
+    }
+
+    /// returns the sum and the carry-out
+    pub fn full_adder(&mut self, a: Signal, b: Signal, carry: Signal) -> (Signal, Signal) {
+        let propagate = self.xor(a, b);
+        let sum = self.xor(propagate, carry);
+        let generate = self.and(a, b);
+        let carried = self.and(propagate, carry);
+        (sum, self.or(generate, carried))
+    }
+
+    /// returns the sum bits and the carry-out
+    pub fn ripple_carry(
+        &mut self,
+        a: &[Signal],
+        b: &[Signal],
+        mut carry: Signal,
+    ) -> (Vec<Signal>, Signal) {
+        let mut sum = vec![];
+        for (&ai, &bi) in a.iter().zip(b) {
+            let (s, c) = self.full_adder(ai, bi, carry);
+            sum.push(s);
+            carry = c;
+        }
+        (sum, carry)
+    }
+}
+
+pub(crate) fn to_signals<T: Word>(x: T) -> Vec<Signal> {
+    (0..T::BITS)
+        .map(|i| Circuit::input((x >> i) & T::ONE == T::ONE))
+        .collect()
+}
+
+pub(crate) fn from_signals<T: Word>(signals: &[Signal]) -> T {
+    signals.iter().enumerate().fold(T::ZERO, |x, (i, signal)| {
+        if signal.value {
+            x | T::ONE << i as u32
+        } else {
+            x
+        }
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdderArchitecture {
+    /// one full adder per bit, the carry goes through every bit
+    RippleCarry,
+    /// carries of each block computed from its generate and propagate signals,
+    /// blocks are chained
+    CarryLookahead(usize),
+    /// every block after the first is computed for both carry-ins,
+    /// the incoming carry selects the right one
+    CarrySelect(usize),
+    /// ripple-carry blocks whose carry-in bypasses the block when every bit propagates.
+    /// The bypass only shortens the paths that are actually sensitized,
+    /// the reported depth is structural and stays the one of the ripple-carry chain.
+    CarrySkip(usize),
+    /// parallel prefix with minimal depth and `n log n` prefix nodes
+    KoggeStone,
+    /// parallel prefix with about `2n` prefix nodes and twice the depth of Kogge-Stone
+    BrentKung,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AdderReport<T> {
+    pub sum: T,
+    pub carry: bool,
+    pub gates: usize,
+    pub depth: usize,
+}
+
+/// `(generate, propagate)` of the bits `i..=j` from those of `i..=k` (high) and `k-1..=j` (low)
+fn prefix(
+    circuit: &mut Circuit,
+    high: (Signal, Signal),
+    low: (Signal, Signal),
+) -> (Signal, Signal) {
+    let carried = circuit.and(high.1, low.0);
+    let generate = circuit.or(high.0, carried);
+    let propagate = circuit.and(high.1, low.1);
+    (generate, propagate)
+}
+
+fn carry_lookahead(
+    circuit: &mut Circuit,
+    a: &[Signal],
+    b: &[Signal],
+    block_size: usize,
+) -> (Vec<Signal>, Signal) {
+    let mut sum = vec![];
+    let mut carry = Circuit::input(false);
+    for (a, b) in a.chunks(block_size).zip(b.chunks(block_size)) {
+        let generates = a
+            .iter()
+            .zip(b)
+            .map(|(&ai, &bi)| circuit.and(ai, bi))
+            .collect::<Vec<_>>();
+        let propagates = a
+            .iter()
+            .zip(b)
+            .map(|(&ai, &bi)| circuit.xor(ai, bi))
+            .collect::<Vec<_>>();
+        // c[i + 1] = g[i] | p[i] g[i - 1] | ... | p[i] ... p[0] c[0], as two levels of gate trees
+        let mut carries = vec![carry];
+        for i in 0..a.len() {
+            let mut terms = vec![];
+            for j in 0..=i + 1 {
+                let mut factors = propagates[j..=i].to_vec();
+                factors.push(if j == 0 { carry } else { generates[j - 1] });
+                terms.push(circuit.reduce(&factors, Circuit::and).unwrap());
+            }
+            carries.push(circuit.reduce(&terms, Circuit::or).unwrap());
+        }
+        for (&p, &c) in propagates.iter().zip(&carries) {
+            sum.push(circuit.xor(p, c));
+        }
+        carry = carries[a.len()];
+    }
+    (sum, carry)
+}
+
+fn carry_select(
+    circuit: &mut Circuit,
+    a: &[Signal],
+    b: &[Signal],
+    block_size: usize,
+) -> (Vec<Signal>, Signal) {
+    let (mut sum, mut carry) =
+        circuit.ripple_carry(&a[..block_size], &b[..block_size], Circuit::input(false));
+    for (a, b) in a[block_size..]
+        .chunks(block_size)
+        .zip(b[block_size..].chunks(block_size))
+    {
+        let (sum0, carry0) = circuit.ripple_carry(a, b, Circuit::input(false));
+        let (sum1, carry1) = circuit.ripple_carry(a, b, Circuit::input(true));
+        for (s0, s1) in sum0.into_iter().zip(sum1) {
+            sum.push(circuit.mux(carry, s1, s0));
+        }
+        carry = circuit.mux(carry, carry1, carry0);
+    }
+    (sum, carry)
+}
+
+fn carry_skip(
+    circuit: &mut Circuit,
+    a: &[Signal],
+    b: &[Signal],
+    block_size: usize,
+) -> (Vec<Signal>, Signal) {
+    let mut sum = vec![];
+    let mut carry = Circuit::input(false);
+    for (a, b) in a.chunks(block_size).zip(b.chunks(block_size)) {
+        let propagates = a
+            .iter()
+            .zip(b)
+            .map(|(&ai, &bi)| circuit.xor(ai, bi))
+            .collect::<Vec<_>>();
+        let skip = circuit.reduce(&propagates, Circuit::and).unwrap();
+        let (block_sum, block_carry) = circuit.ripple_carry(a, b, carry);
+        sum.extend(block_sum);
+        carry = circuit.mux(skip, carry, block_carry);
+    }
+    (sum, carry)
+}
+
+/// `nodes[i]` becomes the `(generate, propagate)` of the bits `0..=i`
+fn kogge_stone(circuit: &mut Circuit, nodes: &mut [(Signal, Signal)]) {
+    let mut distance = 1;
+    while distance < nodes.len() {
+        for i in (distance..nodes.len()).rev() {
+            nodes[i] = prefix(circuit, nodes[i], nodes[i - distance]);
+        }
+        distance *= 2;
+    }
+}
+
+/// `nodes[i]` becomes the `(generate, propagate)` of the bits `0..=i`
+fn brent_kung(circuit: &mut Circuit, nodes: &mut [(Signal, Signal)]) {
+    let n = nodes.len();
+    // up-sweep: nodes[i] covers the 2^k bits ending at i when i + 1 is a multiple of 2^k
+    let mut distance = 1;
+    while distance < n {
+        for i in (2 * distance - 1..n).step_by(2 * distance) {
+            nodes[i] = prefix(circuit, nodes[i], nodes[i - distance]);
+        }
+        distance *= 2;
+    }
+    // down-sweep: fill the prefixes in between
+    while distance > 1 {
+        distance /= 2;
+        for i in (3 * distance - 1..n).step_by(2 * distance) {
+            nodes[i] = prefix(circuit, nodes[i], nodes[i - distance]);
+        }
+    }
+}
+
+fn parallel_prefix(
+    circuit: &mut Circuit,
+    a: &[Signal],
+    b: &[Signal],
+    network: fn(&mut Circuit, &mut [(Signal, Signal)]),
+) -> (Vec<Signal>, Signal) {
+    let propagates = a
+        .iter()
+        .zip(b)
+        .map(|(&ai, &bi)| circuit.xor(ai, bi))
+        .collect::<Vec<_>>();
+    let mut nodes = a
+        .iter()
+        .zip(b)
+        .zip(&propagates)
+        .map(|((&ai, &bi), &p)| (circuit.and(ai, bi), p))
+        .collect::<Vec<_>>();
+    network(circuit, &mut nodes);
+    let mut sum = vec![propagates[0]];
+    for i in 1..a.len() {
+        sum.push(circuit.xor(propagates[i], nodes[i - 1].0));
+    }
+    (sum, nodes[a.len() - 1].0)
+}
+
+/// Builds the adder with the given architecture and simulates it on `a` and `b`
+pub fn adder_circuit<T: Word>(a: T, b: T, architecture: AdderArchitecture) -> AdderReport<T> {
+    let (a, b) = (to_signals(a), to_signals(b));
+    let mut circuit = Circuit::default();
+    let (sum, carry) = match architecture {
+        AdderArchitecture::RippleCarry => circuit.ripple_carry(&a, &b, Circuit::input(false)),
+        AdderArchitecture::CarryLookahead(block_size)
+        | AdderArchitecture::CarrySelect(block_size)
+        | AdderArchitecture::CarrySkip(block_size) => {
+            assert!(block_size > 0, "block size must be positive");
+            let block_size = block_size.min(a.len());
+            match architecture {
+                AdderArchitecture::CarryLookahead(_) => {
+                    carry_lookahead(&mut circuit, &a, &b, block_size)
+                }
+                AdderArchitecture::CarrySelect(_) => carry_select(&mut circuit, &a, &b, block_size),
+                _ => carry_skip(&mut circuit, &a, &b, block_size),
+            }
+        }
+        AdderArchitecture::KoggeStone => parallel_prefix(&mut circuit, &a, &b, kogge_stone),
+        AdderArchitecture::BrentKung => parallel_prefix(&mut circuit, &a, &b, brent_kung),
+    };
+    AdderReport {
+        sum: from_signals(&sum),
+        carry: carry.value,
+        gates: circuit.gates,
+        depth: sum
+            .iter()
+            .chain([&carry])
+            .map(|signal| signal.depth)
+            .max()
+            .unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::numbers::adder_with_flags,
+        rand::{Rng, distr::StandardUniform, prelude::Distribution},
+    };
+
+    const ARCHITECTURES: [AdderArchitecture; 10] = [
+        AdderArchitecture::RippleCarry,
+        AdderArchitecture::CarryLookahead(1),
+        AdderArchitecture::CarryLookahead(4),
+        AdderArchitecture::CarrySelect(3),
+        AdderArchitecture::CarrySelect(4),
+        AdderArchitecture::CarrySkip(4),
+        AdderArchitecture::CarrySkip(5),
+        AdderArchitecture::KoggeStone,
+        AdderArchitecture::BrentKung,
+        AdderArchitecture::CarryLookahead(16),
+    ];
+
+    fn check_width<T: Word>()
+    where
+        StandardUniform: Distribution<T>,
+    {
+        let mut rng = rand::rng();
+        let edges = [T::ZERO, T::ONE, !T::ZERO, !T::ZERO >> 1];
+        let pairs = edges
+            .iter()
+            .flat_map(|&a| edges.iter().map(move |&b| (a, b)))
+            .chain((0..100).map(|_| (rng.random(), rng.random())));
+        for (a, b) in pairs {
+            let expected = adder_with_flags(a, b);
+            for architecture in ARCHITECTURES {
+                let report = adder_circuit(a, b, architecture);
+                assert_eq!(
+                    (report.sum, report.carry),
+                    (expected.value, expected.carry),
+                    "{a:?} + {b:?} with {architecture:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_agrees_with_adder() {
+        check_width::<u8>();
+        check_width::<u16>();
+        check_width::<u32>();
+        check_width::<u64>();
+        check_width::<u128>();
+    }
+
+    #[test]
+    fn test_costs() {
+        let report = |architecture| adder_circuit(0u32, 0, architecture);
+        let ripple = report(AdderArchitecture::RippleCarry);
+        assert_eq!(ripple.gates, 5 * 32);
+        assert_eq!(ripple.depth, 2 * 32 + 1);
+
+        let kogge_stone = report(AdderArchitecture::KoggeStone);
+        let brent_kung = report(AdderArchitecture::BrentKung);
+        // generate/propagate, at most 2 gates for each of the log2(32) prefix levels, final xor
+        assert!(kogge_stone.depth <= 1 + 2 * 5 + 1);
+        for architecture in ARCHITECTURES {
+            assert!(report(architecture).depth >= kogge_stone.depth);
+        }
+        assert!(brent_kung.depth > kogge_stone.depth);
+        assert!(brent_kung.depth < ripple.depth);
+        assert!(brent_kung.gates < kogge_stone.gates);
+
+        assert!(report(AdderArchitecture::CarryLookahead(4)).depth < ripple.depth);
+        assert!(report(AdderArchitecture::CarrySelect(4)).depth < ripple.depth);
+        assert!(report(AdderArchitecture::CarrySelect(4)).gates > ripple.gates);
+        assert_eq!(
+            report(AdderArchitecture::CarryLookahead(32)).depth,
+            report(AdderArchitecture::CarryLookahead(1000)).depth
+        );
+    }
+}
@@ -0,0 +1,670 @@
+//! And-Inverter Graphs: two-input AND nodes with complemented edges, hashed structurally
+//! so that equal nodes are shared, read and written in the AIGER formats.
+//! Literals follow AIGER: `2 * v` is the variable `v`, `2 * v + 1` its complement,
+//! `0` is false and `1` is true.
+
+use {crate::BooleanTree, std::collections::HashMap};
+
+pub type AigLiteral = u32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Node {
+    Constant,
+    Input,
+    And(AigLiteral, AigLiteral),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Aig {
+    /// indexed by variable, the constant is variable 0
+    nodes: Vec<Node>,
+    strash: HashMap<(AigLiteral, AigLiteral), AigLiteral>,
+    inputs: Vec<AigLiteral>,
+    outputs: Vec<AigLiteral>,
+    input_names: Vec<Option<String>>,
+    output_names: Vec<Option<String>>,
+}
+
+impl Default for Aig {
+    fn default() -> Self {
+        Self {
+            nodes: vec![Node::Constant],
+            strash: HashMap::new(),
+            inputs: vec![],
+            outputs: vec![],
+            input_names: vec![],
+            output_names: vec![],
+        }
+    }
+}
+
+impl Aig {
+    pub const FALSE: AigLiteral = 0;
+    pub const TRUE: AigLiteral = 1;
+
+    pub fn negate(literal: AigLiteral) -> AigLiteral {
+        literal ^ 1
+    }
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn num_inputs(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn num_ands(&self) -> usize {
+        self.nodes.len() - 1 - self.inputs.len()
+    }
+
+    pub fn inputs(&self) -> &[AigLiteral] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[AigLiteral] {
+        &self.outputs
+    }
+
+    pub fn input_name(&self, i: usize) -> Option<&str> {
+        self.input_names[i].as_deref()
+    }
+
+    pub fn output_name(&self, i: usize) -> Option<&str> {
+        self.output_names[i].as_deref()
+    }
+
+    pub fn name_input(&mut self, i: usize, name: &str) {
+        self.input_names[i] = Some(name.to_string());
+    }
+
+    pub fn name_output(&mut self, i: usize, name: &str) {
+        self.output_names[i] = Some(name.to_string());
+    }
+
+    pub fn add_input(&mut self) -> AigLiteral {
+        let literal = 2 * self.nodes.len() as AigLiteral;
+        self.nodes.push(Node::Input);
+        self.inputs.push(literal);
+        self.input_names.push(None);
+        literal
+    }
+
+    pub fn add_output(&mut self, literal: AigLiteral) {
+        assert!(
+            (literal as usize / 2) < self.nodes.len(),
+            "no variable for literal {literal}"
+        );
+        self.outputs.push(literal);
+        self.output_names.push(None);
+    }
+
+    /// Constants are propagated and `a & a`, `a & !a` simplified,
+    /// an existing node with the same fanins is returned instead of a new one
+    pub fn and(&mut self, a: AigLiteral, b: AigLiteral) -> AigLiteral {
+        let (a, b) = (a.max(b), a.min(b));
+        if b == Self::FALSE || a == Self::negate(b) {
+            return Self::FALSE;
+        }
+        if b == Self::TRUE || a == b {
+            return a;
+        }
+        if let Some(&literal) = self.strash.get(&(a, b)) {
+            return literal;
+        }
+        let literal = 2 * self.nodes.len() as AigLiteral;
+        self.nodes.push(Node::And(a, b));
+        self.strash.insert((a, b), literal);
+        literal
+    }
+
+    pub fn or(&mut self, a: AigLiteral, b: AigLiteral) -> AigLiteral {
+        Self::negate(self.and(Self::negate(a), Self::negate(b)))
+    }
+
+    pub fn xor(&mut self, a: AigLiteral, b: AigLiteral) -> AigLiteral {
+        let both = self.and(a, b);
+        let neither = self.and(Self::negate(a), Self::negate(b));
+        self.and(Self::negate(both), Self::negate(neither))
+    }
+
+    /// `if c { t } else { e }`
+    pub fn ite(&mut self, c: AigLiteral, t: AigLiteral, e: AigLiteral) -> AigLiteral {
+        let then = self.and(c, t);
+        let otherwise = self.and(Self::negate(c), e);
+        self.or(then, otherwise)
+    }
+
+    pub fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        assert_eq!(inputs.len(), self.inputs.len(), "wrong number of inputs");
+        let mut values = vec![false; self.nodes.len()];
+        for (&literal, &value) in self.inputs.iter().zip(inputs) {
+            values[literal as usize / 2] = value;
+        }
+        let value = |values: &[bool], literal: AigLiteral| {
+            values[literal as usize / 2] ^ (literal & 1 == 1)
+        };
+        // fanins are always created before the node
+        for v in 0..self.nodes.len() {
+            if let Node::And(a, b) = self.nodes[v] {
+                values[v] = value(&values, a) && value(&values, b);
+            }
+        }
+        self.outputs
+            .iter()
+            .map(|&literal| value(&values, literal))
+            .collect()
+    }
+
+    /// One input per free variable, in alphabetical order, and a single output.
+    /// Quantifiers are expanded first.
+    pub fn from_tree(tree: &BooleanTree) -> Self {
+        fn build(aig: &mut Aig, tree: &BooleanTree, variables: &[char]) -> AigLiteral {
+            let mut operands = |nodes: &[&BooleanTree]| {
+                nodes
+                    .iter()
+                    .map(|node| build(aig, node, variables))
+                    .collect::<Vec<_>>()
+            };
+            match tree {
+                BooleanTree::Value(b) => *b as AigLiteral,
+                BooleanTree::Variable(c) => aig.inputs[variables.binary_search(c).unwrap()],
+                BooleanTree::Not(node) => Aig::negate(build(aig, node, variables)),
+                BooleanTree::Ite(node1, node2, node3) => {
+                    let [c, t, e] = operands(&[node1, node2, node3])[..] else {
+                        unreachable!()
+                    };
+                    aig.ite(c, t, e)
+                }
+                BooleanTree::Or(node1, node2)
+                | BooleanTree::And(node1, node2)
+                | BooleanTree::Xor(node1, node2)
+                | BooleanTree::Implication(node1, node2)
+                | BooleanTree::Equivalence(node1, node2)
+                | BooleanTree::Nand(node1, node2)
+                | BooleanTree::Nor(node1, node2)
+                | BooleanTree::ConverseImplication(node1, node2)
+                | BooleanTree::NonImplication(node1, node2) => {
+                    let [a, b] = operands(&[node1, node2])[..] else {
+                        unreachable!()
+                    };
+                    match tree {
+                        BooleanTree::Or(..) => aig.or(a, b),
+                        BooleanTree::And(..) => aig.and(a, b),
+                        BooleanTree::Xor(..) => aig.xor(a, b),
+                        BooleanTree::Implication(..) => aig.or(Aig::negate(a), b),
+                        BooleanTree::Equivalence(..) => Aig::negate(aig.xor(a, b)),
+                        BooleanTree::Nand(..) => Aig::negate(aig.and(a, b)),
+                        BooleanTree::Nor(..) => Aig::negate(aig.or(a, b)),
+                        BooleanTree::ConverseImplication(..) => aig.or(a, Aig::negate(b)),
+                        _ => aig.and(a, Aig::negate(b)),
+                    }
+                }
+                BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) | BooleanTree::XorN(nodes) => {
+                    let (identity, gate): (_, fn(&mut Aig, AigLiteral, AigLiteral) -> AigLiteral) =
+                        match tree {
+                            BooleanTree::AndN(_) => (Aig::TRUE, Aig::and),
+                            BooleanTree::OrN(_) => (Aig::FALSE, Aig::or),
+                            _ => (Aig::FALSE, Aig::xor),
+                        };
+                    nodes.iter().fold(identity, |literal, node| {
+                        let operand = build(aig, node, variables);
+                        gate(aig, literal, operand)
+                    })
+                }
+                BooleanTree::Forall(..) | BooleanTree::Exists(..) => {
+                    unreachable!("quantifiers are expanded first")
+                }
+            }
+        }
+
+        let mut tree = tree.clone();
+        tree.expand_quantifiers();
+        let variables = tree.get_variables();
+        let mut aig = Aig::new();
+        for (i, c) in variables.iter().enumerate() {
+            aig.add_input();
+            aig.name_input(i, &c.to_string());
+        }
+        let output = build(&mut aig, &tree, &variables);
+        aig.add_output(output);
+        aig
+    }
+
+    // =====================================================
+    // ======================= AIGER =======================
+    // =====================================================
+
+    /// AIGER numbering: inputs first, then the AND nodes, each after its fanins
+    fn renumbering(&self) -> Vec<AigLiteral> {
+        let mut variables = vec![0; self.nodes.len()];
+        for (i, &literal) in self.inputs.iter().enumerate() {
+            variables[literal as usize / 2] = i as AigLiteral + 1;
+        }
+        let mut next = self.inputs.len() as AigLiteral + 1;
+        for (v, node) in self.nodes.iter().enumerate() {
+            if let Node::And(..) = node {
+                variables[v] = next;
+                next += 1;
+            }
+        }
+        variables
+    }
+
+    fn header_and_symbols(&self, format: &str) -> (String, String) {
+        let header = format!(
+            "{format} {} {} 0 {} {}\n",
+            self.nodes.len() - 1,
+            self.inputs.len(),
+            self.outputs.len(),
+            self.num_ands()
+        );
+        let mut symbols = String::new();
+        for (i, name) in self.input_names.iter().enumerate() {
+            if let Some(name) = name {
+                symbols += &format!("i{i} {name}\n");
+            }
+        }
+        for (i, name) in self.output_names.iter().enumerate() {
+            if let Some(name) = name {
+                symbols += &format!("o{i} {name}\n");
+            }
+        }
+        (header, symbols)
+    }
+
+    /// ASCII AIGER (`aag`)
+    pub fn to_aag(&self) -> String {
+        let variables = self.renumbering();
+        let renumber = |literal: AigLiteral| 2 * variables[literal as usize / 2] + (literal & 1);
+        let (mut aag, symbols) = self.header_and_symbols("aag");
+        for &literal in &self.inputs {
+            aag += &format!("{}\n", renumber(literal));
+        }
+        for &literal in &self.outputs {
+            aag += &format!("{}\n", renumber(literal));
+        }
+        for (v, node) in self.nodes.iter().enumerate() {
+            if let &Node::And(a, b) = node {
+                let lhs = renumber(2 * v as AigLiteral);
+                aag += &format!("{lhs} {} {}\n", renumber(a), renumber(b));
+            }
+        }
+        aag + &symbols
+    }
+
+    /// Binary AIGER (`aig`): inputs are implicit and each AND node is stored
+    /// as the two differences `lhs - rhs0` and `rhs0 - rhs1`, 7 bits per byte
+    pub fn to_aig(&self) -> Vec<u8> {
+        fn encode(bytes: &mut Vec<u8>, mut x: AigLiteral) {
+            while x >= 0x80 {
+                bytes.push((x & 0x7f) as u8 | 0x80);
+                x >>= 7;
+            }
+            bytes.push(x as u8);
+        }
+
+        let variables = self.renumbering();
+        let renumber = |literal: AigLiteral| 2 * variables[literal as usize / 2] + (literal & 1);
+        let (mut text, symbols) = self.header_and_symbols("aig");
+        for &literal in &self.outputs {
+            text += &format!("{}\n", renumber(literal));
+        }
+        let mut bytes = text.into_bytes();
+        for (v, node) in self.nodes.iter().enumerate() {
+            if let &Node::And(a, b) = node {
+                let lhs = renumber(2 * v as AigLiteral);
+                let (rhs0, rhs1) = (renumber(a).max(renumber(b)), renumber(a).min(renumber(b)));
+                encode(&mut bytes, lhs - rhs0);
+                encode(&mut bytes, rhs0 - rhs1);
+            }
+        }
+        bytes.extend(symbols.into_bytes());
+        bytes
+    }
+
+    /// Parses `aag M I L O A`, latches are not supported
+    fn parse_header(line: Option<&str>, format: &str) -> Result<[usize; 4], &'static str> {
+        let mut tokens = line.ok_or("missing header")?.split_whitespace();
+        if tokens.next() != Some(format) {
+            return Err("invalid header");
+        }
+        let numbers = tokens
+            .map(|token| token.parse::<usize>().map_err(|_| "invalid header"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let &[max_variable, num_inputs, num_latches, num_outputs, num_ands] = &numbers[..] else {
+            return Err("invalid header");
+        };
+        if num_latches != 0 {
+            return Err("latches are not supported");
+        }
+        // literals `2 * M + 1` must fit, and the binary format numbers the variables
+        // without gaps
+        let defined = num_inputs.checked_add(num_ands).ok_or("invalid header")?;
+        if max_variable < defined
+            || max_variable > (AigLiteral::MAX / 2) as usize
+            || (format == "aig" && max_variable != defined)
+        {
+            return Err("invalid header");
+        }
+        Ok([max_variable, num_inputs, num_outputs, num_ands])
+    }
+
+    fn parse_literal(token: Option<&str>, max_variable: usize) -> Result<AigLiteral, &'static str> {
+        let literal = token
+            .ok_or("missing literal")?
+            .parse::<AigLiteral>()
+            .map_err(|_| "invalid literal")?;
+        if literal as usize / 2 > max_variable {
+            return Err("literal out of range");
+        }
+        Ok(literal)
+    }
+
+    /// Builds the graph from the AIGER definitions, in any order,
+    /// then reads the symbol table from the remaining lines
+    fn build(
+        inputs: &[AigLiteral],
+        outputs: &[AigLiteral],
+        ands: &[(AigLiteral, AigLiteral, AigLiteral)],
+        symbols: &str,
+    ) -> Result<Self, &'static str> {
+        // sized from the literals rather than from the header, which may overstate M
+        let num_variables = inputs
+            .iter()
+            .chain(outputs)
+            .chain(ands.iter().flat_map(|(lhs, rhs0, rhs1)| [lhs, rhs0, rhs1]))
+            .map(|&literal| literal as usize / 2 + 1)
+            .max()
+            .unwrap_or(1);
+        let mut definitions = vec![None; num_variables];
+        let mut aig = Aig::new();
+        let mut literals: Vec<Option<AigLiteral>> = vec![None; num_variables];
+        literals[0] = Some(Self::FALSE);
+        for &input in inputs {
+            if input & 1 == 1 || input == 0 || literals[input as usize / 2].is_some() {
+                return Err("invalid input literal");
+            }
+            literals[input as usize / 2] = Some(aig.add_input());
+        }
+        for &(lhs, rhs0, rhs1) in ands {
+            let v = lhs as usize / 2;
+            if lhs & 1 == 1 || v == 0 || literals[v].is_some() || definitions[v].is_some() {
+                return Err("invalid AND literal");
+            }
+            definitions[v] = Some((rhs0, rhs1));
+        }
+
+        // depth-first, a variable is resolved once both fanins are
+        let mut visiting = vec![false; num_variables];
+        for root in outputs.iter().chain(ands.iter().map(|(lhs, _, _)| lhs)) {
+            let mut stack = vec![*root as usize / 2];
+            while let Some(&v) = stack.last() {
+                if literals[v].is_some() {
+                    stack.pop();
+                    continue;
+                }
+                let (rhs0, rhs1) = definitions[v].ok_or("undefined literal")?;
+                let pending = [rhs0, rhs1]
+                    .into_iter()
+                    .map(|rhs| rhs as usize / 2)
+                    .filter(|&w| literals[w].is_none())
+                    .collect::<Vec<_>>();
+                if pending.is_empty() {
+                    let fanin = |rhs: AigLiteral| literals[rhs as usize / 2].unwrap() ^ (rhs & 1);
+                    literals[v] = Some(aig.and(fanin(rhs0), fanin(rhs1)));
+                    stack.pop();
+                } else if visiting[v] {
+                    return Err("cyclic AND definitions");
+                } else {
+                    visiting[v] = true;
+                    stack.extend(pending);
+                }
+            }
+        }
+        for &output in outputs {
+            aig.add_output(
+                literals[output as usize / 2].ok_or("undefined literal")? ^ (output & 1),
+            );
+        }
+
+        for line in symbols.lines() {
+            if line.starts_with('c') {
+                break;
+            }
+            let (kind, name) = line.split_once(' ').ok_or("invalid symbol")?;
+            let parse = |index: &str| index.parse::<usize>().map_err(|_| "invalid symbol");
+            if let Some(index) = kind.strip_prefix('i') {
+                let index = parse(index)?;
+                if index >= aig.num_inputs() {
+                    return Err("invalid symbol");
+                }
+                aig.name_input(index, name);
+            } else if let Some(index) = kind.strip_prefix('o') {
+                let index = parse(index)?;
+                if index >= aig.outputs.len() {
+                    return Err("invalid symbol");
+                }
+                aig.name_output(index, name);
+            } else {
+                return Err("invalid symbol");
+            }
+        }
+        Ok(aig)
+    }
+
+    pub fn from_aag(aag: &str) -> Result<Self, &'static str> {
+        let mut lines = aag.lines();
+        let [max_variable, num_inputs, num_outputs, num_ands] =
+            Self::parse_header(lines.next(), "aag")?;
+        let mut next = |count: usize, width: usize| {
+            (0..count)
+                .map(|_| {
+                    let mut tokens = lines.next().ok_or("missing line")?.split_whitespace();
+                    let literals = (0..width)
+                        .map(|_| Self::parse_literal(tokens.next(), max_variable))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    match tokens.next() {
+                        None => Ok(literals),
+                        Some(_) => Err("unexpected token"),
+                    }
+                })
+                .collect::<Result<Vec<_>, &'static str>>()
+        };
+        let inputs = next(num_inputs, 1)?.concat();
+        let outputs = next(num_outputs, 1)?.concat();
+        let ands = next(num_ands, 3)?
+            .into_iter()
+            .map(|and| (and[0], and[1], and[2]))
+            .collect::<Vec<_>>();
+        let symbols = lines.collect::<Vec<_>>().join("\n");
+        Self::build(&inputs, &outputs, &ands, &symbols)
+    }
+
+    pub fn from_aig(aig: &[u8]) -> Result<Self, &'static str> {
+        let mut position = 0;
+        let mut line = || {
+            let start = position;
+            let length = aig[start..].iter().position(|&byte| byte == b'\n')?;
+            position = start + length + 1;
+            std::str::from_utf8(&aig[start..start + length]).ok()
+        };
+        let [max_variable, num_inputs, num_outputs, num_ands] = Self::parse_header(line(), "aig")?;
+        let outputs = (0..num_outputs)
+            .map(|_| Self::parse_literal(line(), max_variable))
+            .collect::<Result<Vec<_>, _>>()?;
+        let inputs = (1..=num_inputs as AigLiteral)
+            .map(|v| 2 * v)
+            .collect::<Vec<_>>();
+
+        let mut decode = || {
+            let mut x: AigLiteral = 0;
+            for shift in (0..).step_by(7) {
+                let &byte = aig.get(position).ok_or("truncated AND section")?;
+                position += 1;
+                if shift >= AigLiteral::BITS {
+                    return Err("invalid delta");
+                }
+                x |= ((byte & 0x7f) as AigLiteral) << shift;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            Ok(x)
+        };
+        let mut ands = vec![];
+        for i in 0..num_ands {
+            let lhs = 2 * (num_inputs + i + 1) as AigLiteral;
+            let rhs0 = lhs.checked_sub(decode()?).ok_or("invalid delta")?;
+            let rhs1 = rhs0.checked_sub(decode()?).ok_or("invalid delta")?;
+            ands.push((lhs, rhs0, rhs1));
+        }
+        let symbols = std::str::from_utf8(&aig[position..]).map_err(|_| "invalid symbols")?;
+        Self::build(&inputs, &outputs, &ands, symbols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_tree(formula: &str) {
+        let tree = BooleanTree::new(formula, true).unwrap();
+        let aig = Aig::from_tree(&tree);
+        let (variables, inputs, outputs) = tree.compute_truth_table();
+        assert_eq!(aig.num_inputs(), variables.len());
+        for (input, output) in inputs.iter().zip(outputs) {
+            assert_eq!(aig.evaluate(input), [output], "{formula}");
+        }
+    }
+
+    #[test]
+    fn test_structural_hashing() {
+        let mut aig = Aig::new();
+        let (a, b) = (aig.add_input(), aig.add_input());
+        let ab = aig.and(a, b);
+        assert_eq!(aig.and(b, a), ab);
+        assert_eq!(aig.and(a, Aig::negate(a)), Aig::FALSE);
+        assert_eq!(aig.and(a, a), a);
+        assert_eq!(aig.and(a, Aig::TRUE), a);
+        assert_eq!(aig.and(Aig::FALSE, b), Aig::FALSE);
+        assert_eq!(aig.or(a, Aig::TRUE), Aig::TRUE);
+        assert_eq!(aig.num_ands(), 1);
+        let x1 = aig.xor(a, b);
+        let x2 = aig.xor(b, a);
+        assert_eq!(x1, x2);
+        assert_eq!(aig.num_ands(), 3);
+    }
+
+    #[test]
+    fn test_from_tree() {
+        for formula in [
+            "AB&C|",
+            "AB^C=",
+            "AB>!",
+            "A!A&",
+            "1A|",
+            "0",
+            "AB↑C↓",
+            "AB<C↛",
+            "ABC?",
+            "AB|A∃",
+            "AB^AB^^",
+        ] {
+            check_tree(formula);
+        }
+        let mut tree = BooleanTree::new("AB&C&DA&|", true).unwrap();
+        tree.normalize();
+        let aig = Aig::from_tree(&tree);
+        assert_eq!(aig.num_ands(), 4);
+        // A & !A is folded away, and so is the whole disjunction with 1
+        assert_eq!(
+            Aig::from_tree(&BooleanTree::new("AA!&B|1|", true).unwrap()).num_ands(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_aag() {
+        // half adder from the AIGER documentation, AND nodes out of order
+        let aag = "aag 7 2 0 2 3\n2\n4\n6\n12\n6 13 15\n12 2 4\n14 3 5\ni0 x\ni1 y\no0 s\no1 c\nc\nhalf adder\n";
+        let aig = Aig::from_aag(aag).unwrap();
+        for (x, y) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(aig.evaluate(&[x, y]), [x ^ y, x && y]);
+        }
+        assert_eq!(aig.input_name(1), Some("y"));
+        assert_eq!(aig.output_name(0), Some("s"));
+        let written = aig.to_aag();
+        assert_eq!(Aig::from_aag(&written).unwrap(), aig);
+        assert_eq!(Aig::from_aag(&written).unwrap().to_aag(), written);
+    }
+
+    #[test]
+    fn test_aig() {
+        // a single AND gate: deltas 6 - 4 and 4 - 2
+        let aig = Aig::from_aig(b"aig 3 2 0 1 1\n6\n\x02\x02").unwrap();
+        assert_eq!(aig.evaluate(&[true, true]), [true]);
+        assert_eq!(aig.evaluate(&[true, false]), [false]);
+        assert_eq!(aig.to_aig(), b"aig 3 2 0 1 1\n6\n\x02\x02");
+
+        let tree = BooleanTree::new("ABCDEFGH&|^>?↑", true).unwrap();
+        let aig = Aig::from_tree(&tree);
+        let binary = aig.to_aig();
+        let read = Aig::from_aig(&binary).unwrap();
+        assert_eq!(read, Aig::from_aag(&aig.to_aag()).unwrap());
+        assert_eq!(read.input_name(7), Some("H"));
+        for mask in 0..1 << 8 {
+            let inputs = (0..8).map(|i| mask >> i & 1 == 1).collect::<Vec<_>>();
+            assert_eq!(read.evaluate(&inputs), aig.evaluate(&inputs));
+        }
+
+        // multi-byte deltas
+        let mut aig = Aig::new();
+        let inputs = (0..100).map(|_| aig.add_input()).collect::<Vec<_>>();
+        let conjunction = inputs.iter().fold(Aig::TRUE, |x, &y| aig.and(x, y));
+        aig.add_output(Aig::negate(conjunction));
+        assert_eq!(Aig::from_aig(&aig.to_aig()).unwrap(), aig);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Aig::from_aag("aag 1 0 1 0 0\n2 3\n").is_err());
+        assert!(Aig::from_aag("aig 1 1 0 0 0\n2\n").is_err());
+        assert!(Aig::from_aag("aag 1 1 0 1 0\n2\n4\n").is_err());
+        assert!(Aig::from_aag("aag 2 1 0 1 0\n2\n4\n").is_err());
+        assert!(Aig::from_aag("aag 3 1 0 1 2\n2\n4\n4 6 2\n6 4 2\n").is_err());
+        assert!(Aig::from_aag("aag 2 1 0 1 1\n2\n4\n4 2\n").is_err());
+        assert!(Aig::from_aag("aag 2 1 0 1 1\n2\n4\n4 2 2\nx0 a\n").is_err());
+        // malformed symbols
+        let valid = "aag 2 1 0 1 1\n2\n4\n4 2 2\n";
+        for symbol in ["x0 a", " i0 a", "é0 a", "i a", "ix a", "i1 a", "o1 a", "i0"] {
+            assert_eq!(
+                Aig::from_aag(&format!("{valid}{symbol}\n")),
+                Err("invalid symbol"),
+                "{symbol:?}"
+            );
+        }
+        assert!(Aig::from_aag(&format!("{valid}i0 a\no0 b\n")).is_ok());
+        // headers out of range
+        for header in [
+            "aag 5 18446744073709551615 0 0 1",
+            "aag 18446744073709551615 0 0 0 0",
+            "aag 100000000000 0 0 0 0",
+            "aag 4294967296 0 0 0 0",
+        ] {
+            assert_eq!(
+                Aig::from_aag(&format!("{header}\n")),
+                Err("invalid header"),
+                "{header}"
+            );
+        }
+        assert_eq!(Aig::from_aag("aag 1000 0 0 0 0\n"), Ok(Aig::new()));
+        assert_eq!(Aig::from_aig(b"aig 1000 0 0 0 0\n"), Err("invalid header"));
+        assert_eq!(
+            Aig::from_aig(b"aig 18446744073709551615 18446744073709551615 0 0 1\n"),
+            Err("invalid header")
+        );
+        assert!(Aig::from_aig(b"aig 3 2 0 1 1\n6\n\x02").is_err());
+        assert!(Aig::from_aig(b"aig 3 2 0 1 1\n6\n\x08\x02").is_err());
+    }
+}
@@ -0,0 +1,34 @@
+use {
+    itertools::Itertools,
+    ready_set_boole::{eq, lt, signed_lt},
+};
+
+fn main() {
+    let args = std::env::args().skip(1).collect_vec();
+    assert!(args.len() == 2, "Usage: cargo run -q --bin comparator a b");
+    let nums: Vec<i32> = args
+        .iter()
+        .map(|arg| {
+            arg.parse()
+                .unwrap_or_else(|_| panic!("failed to parse \"{}\" as i32", arg))
+        })
+        .collect();
+    let (a, b) = (nums[0], nums[1]);
+
+    let order = |less: bool| {
+        if eq(a as u32, b as u32) {
+            '='
+        } else if less {
+            '<'
+        } else {
+            '>'
+        }
+    };
+    println!("signed:   {a} {} {b}", order(signed_lt(a as u32, b as u32)));
+    println!(
+        "unsigned: {} {} {}",
+        a as u32,
+        order(lt(a as u32, b as u32)),
+        b as u32
+    );
+}
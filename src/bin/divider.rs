@@ -0,0 +1,32 @@
+use {
+    itertools::Itertools,
+    ready_set_boole::{non_restoring_divider, restoring_divider, signed_divider},
+};
+
+fn main() {
+    let args = std::env::args().skip(1).collect_vec();
+    assert!(
+        args.len() == 2,
+        "Usage: cargo run -q --bin divider dividend divisor"
+    );
+    let nums: Vec<i32> = args
+        .iter()
+        .map(|arg| {
+            arg.parse()
+                .unwrap_or_else(|_| panic!("failed to parse \"{}\" as i32", arg))
+        })
+        .collect();
+    let (a, b) = (nums[0], nums[1]);
+
+    let Some((quotient, remainder)) = signed_divider(a as u32, b as u32) else {
+        println!("{a} / {b}: division by zero");
+        return;
+    };
+    println!("{a} = {} * {b} + {}", quotient as i32, remainder as i32);
+    if a >= 0 && b > 0 {
+        let restoring = restoring_divider(a as u32, b as u32);
+        let non_restoring = non_restoring_divider(a as u32, b as u32);
+        println!("restoring:     {:?}", restoring.unwrap());
+        println!("non-restoring: {:?}", non_restoring.unwrap());
+    }
+}
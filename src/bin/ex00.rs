@@ -3,14 +3,14 @@ use {itertools::Itertools, ready_set_boole::adder};
 fn main() {
     let args = std::env::args().skip(1).collect_vec();
     assert!(
-        args.len() >= 1,
+        !args.is_empty(),
         "Usage: cargo run -q --bin ex00 n1 n2 n3 ..."
     );
     let nums: Vec<u32> = args
         .iter()
         .map(|arg| {
             arg.parse()
-                .expect(&format!("failed to parse \"{}\" as u32", arg))
+                .unwrap_or_else(|_| panic!("failed to parse \"{}\" as u32", arg))
         })
         .collect();
 
@@ -1,8 +1,15 @@
-use ready_set_boole::gray_code;
+use ready_set_boole::{gray_code, gray_decode};
 
 fn main() {
-    for i in 0..=42 {
+    let last = match std::env::args().nth(1) {
+        None => 42,
+        Some(arg) => arg
+            .parse()
+            .unwrap_or_else(|_| panic!("failed to parse \"{}\" as u32", arg)),
+    };
+    for i in 0..=last {
         let gc = gray_code(i);
+        assert_eq!(gray_decode(gc), i);
         println!("{:2} | {:2} | {:06b}", i, gc, gc);
     }
 }
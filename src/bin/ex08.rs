@@ -1,4 +1,7 @@
-use {itertools::Itertools, ready_set_boole::powerset};
+use {
+    itertools::Itertools,
+    ready_set_boole::{Powerset, PowersetOrder},
+};
 
 fn main() {
     let args = std::env::args().skip(1).collect_vec();
@@ -6,11 +9,12 @@ fn main() {
         .iter()
         .map(|arg| {
             arg.parse()
-                .expect(&format!("failed to parse \"{}\" as i32", arg))
+                .unwrap_or_else(|_| panic!("failed to parse \"{}\" as i32", arg))
         })
         .collect();
 
-    for subset in powerset(nums) {
+    // one subset at a time: the 2^n subsets are never all in memory
+    for subset in Powerset::new(nums, PowersetOrder::Binary) {
         println!("{:?}", subset);
     }
 }
@@ -10,7 +10,7 @@ fn main() {
             set.split_whitespace()
                 .map(|x| {
                     x.parse::<i32>()
-                        .expect(&format!("failed to parse \"{}\" as i32", x))
+                        .unwrap_or_else(|_| panic!("failed to parse \"{}\" as i32", x))
                 })
                 .collect()
         })
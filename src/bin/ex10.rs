@@ -5,7 +5,7 @@ fn main() {
         .skip(1)
         .map(|arg| {
             arg.parse::<u16>()
-                .expect(&format!("failed to parse \"{}\" as u16", arg))
+                .unwrap_or_else(|_| panic!("failed to parse \"{}\" as u16", arg))
         })
         .collect_tuple()
         .expect("Usage: cargo run -q --bin ex10 x y");
@@ -8,7 +8,7 @@ fn main() {
     );
     let z = args[0]
         .parse::<f64>()
-        .expect(&format!("failed to parse \"{}\" as u16", args[0]));
+        .unwrap_or_else(|_| panic!("failed to parse \"{}\" as u16", args[0]));
 
     println!("{} -> {:?}", z, reverse_map(z));
 }
@@ -0,0 +1,23 @@
+use {itertools::Itertools, ready_set_boole::subtractor};
+
+fn main() {
+    let args = std::env::args().skip(1).collect_vec();
+    assert!(
+        !args.is_empty(),
+        "Usage: cargo run -q --bin subtractor n1 n2 n3 ..."
+    );
+    let nums: Vec<i32> = args
+        .iter()
+        .map(|arg| {
+            arg.parse()
+                .unwrap_or_else(|_| panic!("failed to parse \"{}\" as i32", arg))
+        })
+        .collect();
+
+    let lhs = nums.iter().map(i32::to_string).join(" - ");
+    // two's complement: the unsigned gates compute the signed difference
+    let rhs = nums[1..].iter().fold(nums[0] as u32, |difference, &n| {
+        subtractor(difference, n as u32)
+    });
+    println!("{lhs} = {}", rhs as i32);
+}
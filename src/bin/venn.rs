@@ -0,0 +1,49 @@
+use {itertools::Itertools, ready_set_boole::VennCounts};
+
+const USAGE: &str = "Usage: cargo run -q --bin venn 1,2,3 2,3,4 [--formula AB&] ...";
+
+fn main() {
+    let args = std::env::args().skip(1).collect_vec();
+    let mut sets: Vec<Vec<i32>> = vec![];
+    let mut formulas = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--formula" {
+            formulas.push(args.next().expect(USAGE));
+            continue;
+        }
+        let set = arg
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.parse()
+                    .unwrap_or_else(|_| panic!("failed to parse \"{}\" as i32", x))
+            })
+            .collect();
+        sets.push(set);
+    }
+    assert!(!sets.is_empty(), "{USAGE}");
+    assert!(sets.len() <= 26, "at most 26 sets, from A to Z");
+
+    let venn = VennCounts::new(sets.iter().cloned());
+    let names = ('A'..='Z').take(sets.len()).collect_vec();
+    println!("| {} | count |", names.iter().join(" | "));
+    println!("{}", ["|"].repeat(names.len() + 2).join("---"));
+    for (region, count) in venn.counts().iter().enumerate() {
+        let members = (0..names.len()).map(|i| region >> i & 1).join(" | ");
+        println!("| {members} | {count} |");
+    }
+
+    let all = (1 << sets.len()) - 1;
+    println!(
+        "union: {} (inclusion-exclusion: {})",
+        venn.union(all),
+        venn.union_by_inclusion_exclusion(all)
+    );
+    for formula in formulas {
+        match venn.count_formula(formula) {
+            Ok(count) => println!("{formula}: {count}"),
+            Err(error) => println!("{formula}: {error}"),
+        }
+    }
+}
@@ -0,0 +1,545 @@
+//! Sets of indices in `0..capacity` for dense universes: a plain bitset, and a
+//! roaring-style set splitting the indices in chunks of 2^16, each one stored
+//! as a sorted array when sparse and as a bitmap when dense.
+//! Operations work a word (or a sorted run) at a time instead of an element at a time.
+
+use std::collections::BTreeMap;
+
+/// Set algebra on indices below a common capacity, which complements are relative to
+pub trait DenseSet: Clone {
+    fn from_indices(capacity: usize, indices: impl IntoIterator<Item = usize>) -> Self;
+    fn capacity(&self) -> usize;
+    fn contains(&self, i: usize) -> bool;
+    fn len(&self) -> usize;
+    /// indices in increasing order
+    fn indices(&self) -> Vec<usize>;
+    fn union(&self, other: &Self) -> Self;
+    fn intersection(&self, other: &Self) -> Self;
+    fn symmetric_difference(&self, other: &Self) -> Self;
+    fn complement(&self) -> Self;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn equivalence(&self, other: &Self) -> Self {
+        self.symmetric_difference(other).complement()
+    }
+
+    fn material_condition(&self, other: &Self) -> Self {
+        self.complement().union(other)
+    }
+}
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+fn words_for(bits: usize) -> usize {
+    bits.div_ceil(WORD_BITS)
+}
+
+/// mask of the valid bits of the last word
+fn last_word_mask(bits: usize) -> u64 {
+    match bits % WORD_BITS {
+        0 => !0,
+        rest => (1 << rest) - 1,
+    }
+}
+
+fn word_indices(words: &[u64], offset: usize) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(move |(k, &word)| {
+        let mut word = word;
+        std::iter::from_fn(move || {
+            (word != 0).then(|| {
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                offset + k * WORD_BITS + bit
+            })
+        })
+    })
+}
+
+// =====================================================
+// ====================== BITSET =======================
+// =====================================================
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+    capacity: usize,
+}
+
+impl BitSet {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            words: vec![0; words_for(capacity)],
+            capacity,
+        }
+    }
+
+    /// returns whether `i` was not in the set yet
+    pub fn insert(&mut self, i: usize) -> bool {
+        assert!(
+            i < self.capacity,
+            "{i} is out of the capacity {}",
+            self.capacity
+        );
+        let (word, bit) = (i / WORD_BITS, i % WORD_BITS);
+        let absent = self.words[word] >> bit & 1 == 0;
+        self.words[word] |= 1 << bit;
+        absent
+    }
+
+    /// returns whether `i` was in the set
+    pub fn remove(&mut self, i: usize) -> bool {
+        let present = self.contains(i);
+        if present {
+            self.words[i / WORD_BITS] &= !(1 << (i % WORD_BITS));
+        }
+        present
+    }
+
+    /// `union` in place
+    pub fn union_with(&mut self, other: &Self) {
+        assert_eq!(self.capacity, other.capacity, "different capacities");
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a |= b;
+        }
+    }
+
+    fn zip(&self, other: &Self, op: impl Fn(u64, u64) -> u64) -> Self {
+        assert_eq!(self.capacity, other.capacity, "different capacities");
+        let words = self
+            .words
+            .iter()
+            .zip(&other.words)
+            .map(|(&a, &b)| op(a, b))
+            .collect();
+        let mut set = Self {
+            words,
+            capacity: self.capacity,
+        };
+        set.clear_padding();
+        set
+    }
+
+    /// bits past the capacity stay unset, whatever the operation
+    fn clear_padding(&mut self) {
+        if let Some(last) = self.words.last_mut() {
+            *last &= last_word_mask(self.capacity);
+        }
+    }
+}
+
+impl DenseSet for BitSet {
+    fn from_indices(capacity: usize, indices: impl IntoIterator<Item = usize>) -> Self {
+        let mut set = Self::new(capacity);
+        for i in indices {
+            set.insert(i);
+        }
+        set
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        i < self.capacity && self.words[i / WORD_BITS] >> (i % WORD_BITS) & 1 == 1
+    }
+
+    fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    fn indices(&self) -> Vec<usize> {
+        word_indices(&self.words, 0).collect()
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a | b)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a & b)
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| a ^ b)
+    }
+
+    fn complement(&self) -> Self {
+        self.zip(self, |a, _| !a)
+    }
+
+    fn equivalence(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| !(a ^ b))
+    }
+
+    fn material_condition(&self, other: &Self) -> Self {
+        self.zip(other, |a, b| !a | b)
+    }
+}
+
+// =====================================================
+// ====================== ROARING ======================
+// =====================================================
+
+const CHUNK_BITS: usize = 1 << 16;
+const BITMAP_WORDS: usize = CHUNK_BITS / WORD_BITS;
+/// an array of more than 4096 `u16` takes more room than a bitmap
+const ARRAY_LIMIT: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Container {
+    /// sorted, at most `ARRAY_LIMIT` values
+    Array(Vec<u16>),
+    /// more than `ARRAY_LIMIT` bits set
+    Bitmap(Box<[u64; BITMAP_WORDS]>),
+}
+
+impl Container {
+    fn len(&self) -> usize {
+        match self {
+            Container::Array(values) => values.len(),
+            Container::Bitmap(words) => words.iter().map(|word| word.count_ones() as usize).sum(),
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&low).is_ok(),
+            Container::Bitmap(words) => {
+                words[low as usize / WORD_BITS] >> (low as usize % WORD_BITS) & 1 == 1
+            }
+        }
+    }
+
+    fn to_bitmap(&self) -> Box<[u64; BITMAP_WORDS]> {
+        match self {
+            Container::Array(values) => {
+                let mut words = Box::new([0; BITMAP_WORDS]);
+                for &low in values {
+                    words[low as usize / WORD_BITS] |= 1 << (low as usize % WORD_BITS);
+                }
+                words
+            }
+            Container::Bitmap(words) => words.clone(),
+        }
+    }
+
+    /// smallest representation, `None` when empty
+    fn from_bitmap(words: Box<[u64; BITMAP_WORDS]>) -> Option<Self> {
+        let container = Container::Bitmap(words);
+        match container.len() {
+            0 => None,
+            len if len <= ARRAY_LIMIT => Some(Container::Array(container.lows().collect())),
+            _ => Some(container),
+        }
+    }
+
+    fn from_sorted(values: Vec<u16>) -> Option<Self> {
+        if values.is_empty() {
+            None
+        } else if values.len() <= ARRAY_LIMIT {
+            Some(Container::Array(values))
+        } else {
+            Container::from_bitmap(Container::Array(values).to_bitmap())
+        }
+    }
+
+    fn lows(&self) -> Box<dyn Iterator<Item = u16> + '_> {
+        match self {
+            Container::Array(values) => Box::new(values.iter().copied()),
+            Container::Bitmap(words) => Box::new(word_indices(&words[..], 0).map(|i| i as u16)),
+        }
+    }
+
+    /// `keep` tells from the membership in `self` and `other` whether a value is kept,
+    /// `word` is the same operation on 64 values at once
+    fn combine(
+        &self,
+        other: &Self,
+        keep: fn(bool, bool) -> bool,
+        word: fn(u64, u64) -> u64,
+    ) -> Option<Self> {
+        match (self, other) {
+            (Container::Array(a), Container::Array(b)) => {
+                // merge of the two sorted arrays
+                let mut values = vec![];
+                let (mut i, mut j) = (0, 0);
+                while i < a.len() || j < b.len() {
+                    let x = match (a.get(i), b.get(j)) {
+                        (Some(&x), Some(&y)) => x.min(y),
+                        (Some(&x), None) => x,
+                        (None, Some(&y)) => y,
+                        (None, None) => unreachable!(),
+                    };
+                    let in_a = a.get(i) == Some(&x);
+                    let in_b = b.get(j) == Some(&x);
+                    i += in_a as usize;
+                    j += in_b as usize;
+                    if keep(in_a, in_b) {
+                        values.push(x);
+                    }
+                }
+                Container::from_sorted(values)
+            }
+            _ => {
+                let mut words = self.to_bitmap();
+                for (x, y) in words.iter_mut().zip(other.to_bitmap().iter()) {
+                    *x = word(*x, *y);
+                }
+                Container::from_bitmap(words)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoaringSet {
+    /// by the high bits of the indices, no empty container
+    containers: BTreeMap<usize, Container>,
+    capacity: usize,
+}
+
+impl RoaringSet {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            containers: BTreeMap::new(),
+            capacity,
+        }
+    }
+
+    /// numbers of containers holding sorted arrays and bitmaps
+    pub fn container_counts(&self) -> (usize, usize) {
+        let arrays = self
+            .containers
+            .values()
+            .filter(|container| matches!(container, Container::Array(_)))
+            .count();
+        (arrays, self.containers.len() - arrays)
+    }
+
+    /// returns whether `i` was not in the set yet
+    pub fn insert(&mut self, i: usize) -> bool {
+        assert!(
+            i < self.capacity,
+            "{i} is out of the capacity {}",
+            self.capacity
+        );
+        let (high, low) = (i / CHUNK_BITS, (i % CHUNK_BITS) as u16);
+        let container = self
+            .containers
+            .entry(high)
+            .or_insert_with(|| Container::Array(vec![]));
+        match container {
+            Container::Array(values) => match values.binary_search(&low) {
+                Ok(_) => false,
+                Err(position) => {
+                    values.insert(position, low);
+                    if values.len() > ARRAY_LIMIT {
+                        *container = Container::Bitmap(container.to_bitmap());
+                    }
+                    true
+                }
+            },
+            Container::Bitmap(words) => {
+                let (word, bit) = (low as usize / WORD_BITS, low as usize % WORD_BITS);
+                let absent = words[word] >> bit & 1 == 0;
+                words[word] |= 1 << bit;
+                absent
+            }
+        }
+    }
+
+    fn combine(
+        &self,
+        other: &Self,
+        keep: fn(bool, bool) -> bool,
+        word: fn(u64, u64) -> u64,
+    ) -> Self {
+        assert_eq!(self.capacity, other.capacity, "different capacities");
+        // chunks absent from both sets stay empty: keep(false, false) is false
+        let mut containers = BTreeMap::new();
+        for &high in self.containers.keys().chain(other.containers.keys()) {
+            if containers.contains_key(&high) {
+                continue;
+            }
+            let combined = match (self.containers.get(&high), other.containers.get(&high)) {
+                (Some(a), Some(b)) => a.combine(b, keep, word),
+                (Some(a), None) => keep(true, false).then(|| a.clone()),
+                (None, Some(b)) => keep(false, true).then(|| b.clone()),
+                (None, None) => unreachable!(),
+            };
+            if let Some(container) = combined {
+                containers.insert(high, container);
+            }
+        }
+        Self {
+            containers,
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl DenseSet for RoaringSet {
+    fn from_indices(capacity: usize, indices: impl IntoIterator<Item = usize>) -> Self {
+        let mut chunks: BTreeMap<usize, Vec<u16>> = BTreeMap::new();
+        for i in indices {
+            assert!(i < capacity, "{i} is out of the capacity {capacity}");
+            chunks
+                .entry(i / CHUNK_BITS)
+                .or_default()
+                .push((i % CHUNK_BITS) as u16);
+        }
+        let containers = chunks
+            .into_iter()
+            .filter_map(|(high, mut values)| {
+                values.sort_unstable();
+                values.dedup();
+                Container::from_sorted(values).map(|container| (high, container))
+            })
+            .collect();
+        Self {
+            containers,
+            capacity,
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn contains(&self, i: usize) -> bool {
+        self.containers
+            .get(&(i / CHUNK_BITS))
+            .is_some_and(|container| container.contains((i % CHUNK_BITS) as u16))
+    }
+
+    fn len(&self) -> usize {
+        self.containers.values().map(Container::len).sum()
+    }
+
+    fn indices(&self) -> Vec<usize> {
+        self.containers
+            .iter()
+            .flat_map(|(&high, container)| {
+                container
+                    .lows()
+                    .map(move |low| high * CHUNK_BITS + low as usize)
+            })
+            .collect()
+    }
+
+    fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a || b, |a, b| a | b)
+    }
+
+    fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a && b, |a, b| a & b)
+    }
+
+    fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a != b, |a, b| a ^ b)
+    }
+
+    fn complement(&self) -> Self {
+        let mut containers = BTreeMap::new();
+        for high in 0..self.capacity.div_ceil(CHUNK_BITS) {
+            let mut words = match self.containers.get(&high) {
+                Some(container) => container.to_bitmap(),
+                None => Box::new([0; BITMAP_WORDS]),
+            };
+            for word in words.iter_mut() {
+                *word = !*word;
+            }
+            // indices past the capacity stay out of the last chunk
+            let valid = (self.capacity - high * CHUNK_BITS).min(CHUNK_BITS);
+            for (k, word) in words.iter_mut().enumerate() {
+                let start = k * WORD_BITS;
+                if start >= valid {
+                    *word = 0;
+                } else if valid - start < WORD_BITS {
+                    *word &= last_word_mask(valid - start);
+                }
+            }
+            if let Some(container) = Container::from_bitmap(words) {
+                containers.insert(high, container);
+            }
+        }
+        Self {
+            containers,
+            capacity: self.capacity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::Rng, std::collections::BTreeSet};
+
+    fn random_indices(capacity: usize, density: f64) -> BTreeSet<usize> {
+        let mut rng = rand::rng();
+        (0..capacity).filter(|_| rng.random_bool(density)).collect()
+    }
+
+    fn check_operations<S: DenseSet + std::fmt::Debug>(capacity: usize) {
+        for (da, db) in [(0.5, 0.5), (0.01, 0.7), (0.001, 0.002), (0.0, 0.3)] {
+            let (a, b) = (random_indices(capacity, da), random_indices(capacity, db));
+            let universe = (0..capacity).collect::<BTreeSet<_>>();
+            let (x, y) = (
+                S::from_indices(capacity, a.iter().copied()),
+                S::from_indices(capacity, b.iter().copied()),
+            );
+            assert_eq!(x.len(), a.len());
+            assert_eq!(x.indices(), a.iter().copied().collect::<Vec<_>>());
+            assert!(a.iter().all(|&i| x.contains(i)));
+            let expect = |set: S, expected: BTreeSet<usize>| {
+                assert_eq!(set.indices(), expected.into_iter().collect::<Vec<_>>());
+            };
+            expect(x.union(&y), &a | &b);
+            expect(x.intersection(&y), &a & &b);
+            expect(x.symmetric_difference(&y), &a ^ &b);
+            expect(x.complement(), &universe - &a);
+            expect(x.equivalence(&y), &universe - &(&a ^ &b));
+            expect(x.material_condition(&y), &(&universe - &a) | &b);
+        }
+    }
+
+    #[test]
+    fn test_bitset() {
+        for capacity in [0, 1, 63, 64, 65, 1000] {
+            check_operations::<BitSet>(capacity);
+        }
+        let mut set = BitSet::new(100);
+        assert!(set.insert(99));
+        assert!(!set.insert(99));
+        assert!(set.remove(99));
+        assert!(set.is_empty());
+        assert!(!set.contains(100));
+    }
+
+    #[test]
+    fn test_roaring_set() {
+        for capacity in [0, 1, 1000, CHUNK_BITS, 3 * CHUNK_BITS + 100] {
+            check_operations::<RoaringSet>(capacity);
+        }
+        // sparse and dense chunks
+        let indices = (0..CHUNK_BITS).step_by(2).chain([CHUNK_BITS + 7]);
+        let mut set = RoaringSet::from_indices(2 * CHUNK_BITS, indices);
+        assert_eq!(set.container_counts(), (1, 1));
+        assert_eq!(set.complement().container_counts(), (0, 2));
+        assert_eq!(set.complement().complement(), set);
+        for i in 0..ARRAY_LIMIT {
+            set.insert(CHUNK_BITS + 100 + i);
+        }
+        assert_eq!(set.container_counts(), (0, 2));
+        assert_eq!(set.len(), CHUNK_BITS / 2 + 1 + ARRAY_LIMIT);
+        let empty = set.intersection(&set.complement());
+        assert!(empty.is_empty());
+        assert_eq!(empty.container_counts(), (0, 0));
+    }
+}
@@ -7,8 +7,9 @@ use {
 };
 
 pub type BinaryNode = fn(Box<BooleanTree>, Box<BooleanTree>) -> BooleanTree;
+pub type QuantifierNode = fn(char, Box<BooleanTree>) -> BooleanTree;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum BooleanTree {
     Value(bool),
     Variable(char),
@@ -18,6 +19,29 @@ pub enum BooleanTree {
     Xor(Box<BooleanTree>, Box<BooleanTree>),
     Implication(Box<BooleanTree>, Box<BooleanTree>),
     Equivalence(Box<BooleanTree>, Box<BooleanTree>),
+    Nand(Box<BooleanTree>, Box<BooleanTree>),
+    Nor(Box<BooleanTree>, Box<BooleanTree>),
+    ConverseImplication(Box<BooleanTree>, Box<BooleanTree>),
+    NonImplication(Box<BooleanTree>, Box<BooleanTree>),
+    /// if-then-else: `Ite(c, t, e)` is `t` when `c` holds, `e` otherwise
+    Ite(Box<BooleanTree>, Box<BooleanTree>, Box<BooleanTree>),
+    Forall(char, Box<BooleanTree>),
+    Exists(char, Box<BooleanTree>),
+    /// n-ary conjunction, true when empty
+    AndN(Vec<BooleanTree>),
+    /// n-ary disjunction, false when empty
+    OrN(Vec<BooleanTree>),
+    /// n-ary exclusive or (parity), false when empty
+    XorN(Vec<BooleanTree>),
+}
+
+/// `ABC&&` for three operands, the identity when there is none
+fn chain_formula(nodes: &[BooleanTree], operator: char, identity: bool) -> String {
+    if nodes.is_empty() {
+        return (identity as u8).to_string();
+    }
+    let operands = nodes.iter().map(BooleanTree::to_formula).join("");
+    operands + &operator.to_string().repeat(nodes.len() - 1)
 }
 
 impl BooleanTree {
@@ -29,6 +53,16 @@ impl BooleanTree {
                 ('^', BooleanTree::Xor as BinaryNode),
                 ('>', BooleanTree::Implication as BinaryNode),
                 ('=', BooleanTree::Equivalence as BinaryNode),
+                ('↑', BooleanTree::Nand as BinaryNode),
+                ('↓', BooleanTree::Nor as BinaryNode),
+                ('<', BooleanTree::ConverseImplication as BinaryNode),
+                ('↛', BooleanTree::NonImplication as BinaryNode),
+            ])
+        });
+        static QUANTIFIER_NODES: LazyLock<HashMap<char, QuantifierNode>> = LazyLock::new(|| {
+            HashMap::from([
+                ('∀', BooleanTree::Forall as QuantifierNode),
+                ('∃', BooleanTree::Exists as QuantifierNode),
             ])
         });
 
@@ -53,6 +87,28 @@ impl BooleanTree {
                 let a = stack.pop().unwrap();
                 let b = stack.pop().unwrap();
                 stack.push(binary_node(Box::new(b), Box::new(a)));
+            } else if c == '?' {
+                if stack.len() < 3 {
+                    return Err("not enough operands for if-then-else");
+                }
+                let otherwise = stack.pop().unwrap();
+                let then = stack.pop().unwrap();
+                let condition = stack.pop().unwrap();
+                stack.push(BooleanTree::Ite(
+                    Box::new(condition),
+                    Box::new(then),
+                    Box::new(otherwise),
+                ));
+            } else if let Some(quantifier_node) = QUANTIFIER_NODES.get(&c) {
+                // the quantified variable is on top of the stack, its scope right below
+                if stack.len() < 2 {
+                    return Err("not enough operands for quantifier");
+                }
+                let BooleanTree::Variable(variable) = stack.pop().unwrap() else {
+                    return Err("quantifier expects a variable");
+                };
+                let body = stack.pop().unwrap();
+                stack.push(quantifier_node(variable, Box::new(body)));
             } else {
                 return Err("invalid character");
             }
@@ -86,6 +142,260 @@ impl BooleanTree {
             BooleanTree::Equivalence(node1, node2) => {
                 format!("{}{}=", node1.to_formula(), node2.to_formula())
             }
+            BooleanTree::Nand(node1, node2) => {
+                format!("{}{}↑", node1.to_formula(), node2.to_formula())
+            }
+            BooleanTree::Nor(node1, node2) => {
+                format!("{}{}↓", node1.to_formula(), node2.to_formula())
+            }
+            BooleanTree::ConverseImplication(node1, node2) => {
+                format!("{}{}<", node1.to_formula(), node2.to_formula())
+            }
+            BooleanTree::NonImplication(node1, node2) => {
+                format!("{}{}↛", node1.to_formula(), node2.to_formula())
+            }
+            BooleanTree::Ite(node1, node2, node3) => format!(
+                "{}{}{}?",
+                node1.to_formula(),
+                node2.to_formula(),
+                node3.to_formula()
+            ),
+            BooleanTree::Forall(variable, node) => format!("{}{variable}∀", node.to_formula()),
+            BooleanTree::Exists(variable, node) => format!("{}{variable}∃", node.to_formula()),
+            BooleanTree::AndN(nodes) => chain_formula(nodes, '&', true),
+            BooleanTree::OrN(nodes) => chain_formula(nodes, '|', false),
+            BooleanTree::XorN(nodes) => chain_formula(nodes, '^', false),
+        }
+    }
+
+    // =====================================================
+    // ======================= INFIX =======================
+    // =====================================================
+
+    /// Parses the usual notation, from loosest to tightest binding:
+    /// quantifiers (`∀A. body`, scope extends as far right as possible),
+    /// `c ? t : e` (right associative), `=`, `>` `<` `↛` (right associative),
+    /// `|` `↓`, `^`, `&` `↑`, then prefix `!`
+    pub fn from_infix(formula: &str) -> Result<Self, &'static str> {
+        struct Parser {
+            chars: Vec<char>,
+            position: usize,
+        }
+
+        impl Parser {
+            fn peek(&mut self) -> Option<char> {
+                while self.position < self.chars.len() && self.chars[self.position].is_whitespace()
+                {
+                    self.position += 1;
+                }
+                self.chars.get(self.position).copied()
+            }
+
+            fn next(&mut self) -> Option<char> {
+                let c = self.peek();
+                self.position += 1;
+                c
+            }
+
+            fn operator(&mut self, nodes: &[(char, BinaryNode)]) -> Option<BinaryNode> {
+                let c = self.peek()?;
+                let &(_, node) = nodes.iter().find(|(symbol, _)| *symbol == c)?;
+                self.next();
+                Some(node)
+            }
+
+            // left associative
+            fn binary(
+                &mut self,
+                nodes: &[(char, BinaryNode)],
+                operand: fn(&mut Self) -> Result<BooleanTree, &'static str>,
+            ) -> Result<BooleanTree, &'static str> {
+                let mut left = operand(self)?;
+                while let Some(node) = self.operator(nodes) {
+                    left = node(Box::new(left), Box::new(operand(self)?));
+                }
+                Ok(left)
+            }
+
+            fn ite(&mut self) -> Result<BooleanTree, &'static str> {
+                let condition = self.equivalence()?;
+                if self.peek() != Some('?') {
+                    return Ok(condition);
+                }
+                self.next();
+                let then = self.ite()?;
+                if self.next() != Some(':') {
+                    return Err("expected ':' in if-then-else");
+                }
+                let otherwise = self.ite()?;
+                Ok(BooleanTree::Ite(
+                    Box::new(condition),
+                    Box::new(then),
+                    Box::new(otherwise),
+                ))
+            }
+
+            fn equivalence(&mut self) -> Result<BooleanTree, &'static str> {
+                self.binary(&[('=', BooleanTree::Equivalence)], Self::implication)
+            }
+
+            // right associative
+            fn implication(&mut self) -> Result<BooleanTree, &'static str> {
+                let left = self.or()?;
+                let nodes: [(char, BinaryNode); 3] = [
+                    ('>', BooleanTree::Implication),
+                    ('<', BooleanTree::ConverseImplication),
+                    ('↛', BooleanTree::NonImplication),
+                ];
+                match self.operator(&nodes) {
+                    None => Ok(left),
+                    Some(node) => Ok(node(Box::new(left), Box::new(self.implication()?))),
+                }
+            }
+
+            fn or(&mut self) -> Result<BooleanTree, &'static str> {
+                self.binary(
+                    &[('|', BooleanTree::Or), ('↓', BooleanTree::Nor)],
+                    Self::xor,
+                )
+            }
+
+            fn xor(&mut self) -> Result<BooleanTree, &'static str> {
+                self.binary(&[('^', BooleanTree::Xor)], Self::and)
+            }
+
+            fn and(&mut self) -> Result<BooleanTree, &'static str> {
+                self.binary(
+                    &[('&', BooleanTree::And), ('↑', BooleanTree::Nand)],
+                    Self::unary,
+                )
+            }
+
+            fn unary(&mut self) -> Result<BooleanTree, &'static str> {
+                if self.peek() == Some('!') {
+                    self.next();
+                    return Ok(BooleanTree::Not(Box::new(self.unary()?)));
+                }
+                self.primary()
+            }
+
+            fn primary(&mut self) -> Result<BooleanTree, &'static str> {
+                match self.next() {
+                    None => Err("unexpected end of formula"),
+                    Some('0') => Ok(BooleanTree::Value(false)),
+                    Some('1') => Ok(BooleanTree::Value(true)),
+                    Some(c) if c.is_ascii_uppercase() => Ok(BooleanTree::Variable(c)),
+                    Some('(') => {
+                        let node = self.ite()?;
+                        match self.next() {
+                            Some(')') => Ok(node),
+                            _ => Err("expected closing parenthesis"),
+                        }
+                    }
+                    Some(quantifier @ ('∀' | '∃')) => {
+                        let variable = match self.next() {
+                            Some(c) if c.is_ascii_uppercase() => c,
+                            _ => return Err("quantifier expects a variable"),
+                        };
+                        if self.next() != Some('.') {
+                            return Err("expected '.' after quantified variable");
+                        }
+                        let body = Box::new(self.ite()?);
+                        Ok(if quantifier == '∀' {
+                            BooleanTree::Forall(variable, body)
+                        } else {
+                            BooleanTree::Exists(variable, body)
+                        })
+                    }
+                    Some(_) => Err("invalid character"),
+                }
+            }
+        }
+
+        let mut parser = Parser {
+            chars: formula.chars().collect(),
+            position: 0,
+        };
+        let tree = parser.ite()?;
+        match parser.peek() {
+            None => Ok(tree),
+            Some(')') => Err("unmatched closing parenthesis"),
+            Some(_) => Err("unexpected character"),
+        }
+    }
+
+    pub fn to_infix(&self) -> String {
+        fn precedence(tree: &BooleanTree) -> u8 {
+            match tree {
+                BooleanTree::Forall(..) | BooleanTree::Exists(..) => 0,
+                BooleanTree::Ite(..) => 1,
+                BooleanTree::Equivalence(..) => 2,
+                BooleanTree::Implication(..)
+                | BooleanTree::ConverseImplication(..)
+                | BooleanTree::NonImplication(..) => 3,
+                BooleanTree::Or(..) | BooleanTree::Nor(..) | BooleanTree::OrN(_) => 4,
+                BooleanTree::Xor(..) | BooleanTree::XorN(_) => 5,
+                BooleanTree::And(..) | BooleanTree::Nand(..) | BooleanTree::AndN(_) => 6,
+                BooleanTree::Not(..) => 7,
+                BooleanTree::Value(_) | BooleanTree::Variable(_) => 8,
+            }
+        }
+
+        fn wrap(tree: &BooleanTree, min_precedence: u8) -> String {
+            if precedence(tree) < min_precedence {
+                format!("({})", tree.to_infix())
+            } else {
+                tree.to_infix()
+            }
+        }
+
+        fn chain(nodes: &[BooleanTree], operator: &str, precedence: u8, identity: bool) -> String {
+            if nodes.is_empty() {
+                return (identity as u8).to_string();
+            }
+            nodes
+                .iter()
+                .enumerate()
+                .map(|(i, node)| wrap(node, if i == 0 { precedence } else { precedence + 1 }))
+                .join(operator)
+        }
+
+        match self {
+            BooleanTree::Value(false) => "0".to_string(),
+            BooleanTree::Value(true) => "1".to_string(),
+            BooleanTree::Variable(c) => c.to_string(),
+            BooleanTree::Not(node) => format!("!{}", wrap(node, 7)),
+            // left associative operators need parentheses around a right child of same precedence
+            BooleanTree::Or(node1, node2) => format!("{} | {}", wrap(node1, 4), wrap(node2, 5)),
+            BooleanTree::Nor(node1, node2) => format!("{} ↓ {}", wrap(node1, 4), wrap(node2, 5)),
+            BooleanTree::And(node1, node2) => format!("{} & {}", wrap(node1, 6), wrap(node2, 7)),
+            BooleanTree::Nand(node1, node2) => format!("{} ↑ {}", wrap(node1, 6), wrap(node2, 7)),
+            BooleanTree::Xor(node1, node2) => format!("{} ^ {}", wrap(node1, 5), wrap(node2, 6)),
+            BooleanTree::Implication(node1, node2) => {
+                format!("{} > {}", wrap(node1, 4), wrap(node2, 3))
+            }
+            BooleanTree::ConverseImplication(node1, node2) => {
+                format!("{} < {}", wrap(node1, 4), wrap(node2, 3))
+            }
+            BooleanTree::NonImplication(node1, node2) => {
+                format!("{} ↛ {}", wrap(node1, 4), wrap(node2, 3))
+            }
+            BooleanTree::Equivalence(node1, node2) => {
+                format!("{} = {}", wrap(node1, 2), wrap(node2, 3))
+            }
+            BooleanTree::Ite(node1, node2, node3) => {
+                format!(
+                    "{} ? {} : {}",
+                    wrap(node1, 2),
+                    wrap(node2, 1),
+                    wrap(node3, 1)
+                )
+            }
+            BooleanTree::Forall(variable, node) => format!("∀{variable}. {}", node.to_infix()),
+            BooleanTree::Exists(variable, node) => format!("∃{variable}. {}", node.to_infix()),
+            BooleanTree::AndN(nodes) => chain(nodes, " & ", 6, true),
+            BooleanTree::OrN(nodes) => chain(nodes, " | ", 4, false),
+            BooleanTree::XorN(nodes) => chain(nodes, " ^ ", 5, false),
         }
     }
 
@@ -97,7 +407,7 @@ impl BooleanTree {
         self.evaluate_with_variables(&HashMap::new())
     }
 
-    fn evaluate_with_variables(&self, values: &HashMap<char, bool>) -> bool {
+    pub(crate) fn evaluate_with_variables(&self, values: &HashMap<char, bool>) -> bool {
         match self {
             BooleanTree::Value(b) => *b,
             BooleanTree::Variable(c) => *values
@@ -119,32 +429,204 @@ impl BooleanTree {
             BooleanTree::Equivalence(node1, node2) => {
                 node1.evaluate_with_variables(values) == node2.evaluate_with_variables(values)
             }
+            BooleanTree::Nand(node1, node2) => {
+                !(node1.evaluate_with_variables(values) && node2.evaluate_with_variables(values))
+            }
+            BooleanTree::Nor(node1, node2) => {
+                !(node1.evaluate_with_variables(values) || node2.evaluate_with_variables(values))
+            }
+            BooleanTree::ConverseImplication(node1, node2) => {
+                node1.evaluate_with_variables(values) || !node2.evaluate_with_variables(values)
+            }
+            BooleanTree::NonImplication(node1, node2) => {
+                node1.evaluate_with_variables(values) && !node2.evaluate_with_variables(values)
+            }
+            BooleanTree::Ite(node1, node2, node3) => {
+                if node1.evaluate_with_variables(values) {
+                    node2.evaluate_with_variables(values)
+                } else {
+                    node3.evaluate_with_variables(values)
+                }
+            }
+            BooleanTree::Forall(variable, node) => [false, true].into_iter().all(|b| {
+                let mut values = values.clone();
+                values.insert(*variable, b);
+                node.evaluate_with_variables(&values)
+            }),
+            BooleanTree::Exists(variable, node) => [false, true].into_iter().any(|b| {
+                let mut values = values.clone();
+                values.insert(*variable, b);
+                node.evaluate_with_variables(&values)
+            }),
+            BooleanTree::AndN(nodes) => nodes
+                .iter()
+                .all(|node| node.evaluate_with_variables(values)),
+            BooleanTree::OrN(nodes) => nodes
+                .iter()
+                .any(|node| node.evaluate_with_variables(values)),
+            BooleanTree::XorN(nodes) => nodes.iter().fold(false, |parity, node| {
+                parity ^ node.evaluate_with_variables(values)
+            }),
         }
     }
 
-    fn get_variables(&self) -> Vec<char> {
-        fn _get_variables(tree: &BooleanTree, variables: &mut HashSet<char>) {
+    /// free variables, sorted
+    pub fn get_variables(&self) -> Vec<char> {
+        fn _get_variables(
+            tree: &BooleanTree,
+            bound: &mut Vec<char>,
+            variables: &mut HashSet<char>,
+        ) {
             match tree {
                 BooleanTree::Value(_) => {}
                 BooleanTree::Variable(c) => {
-                    variables.insert(*c);
+                    if !bound.contains(c) {
+                        variables.insert(*c);
+                    }
                 }
-                BooleanTree::Not(node) => _get_variables(node, variables),
+                BooleanTree::Not(node) => _get_variables(node, bound, variables),
                 BooleanTree::Or(node1, node2)
                 | BooleanTree::And(node1, node2)
                 | BooleanTree::Xor(node1, node2)
                 | BooleanTree::Implication(node1, node2)
-                | BooleanTree::Equivalence(node1, node2) => {
-                    _get_variables(node1, variables);
-                    _get_variables(node2, variables);
+                | BooleanTree::Equivalence(node1, node2)
+                | BooleanTree::Nand(node1, node2)
+                | BooleanTree::Nor(node1, node2)
+                | BooleanTree::ConverseImplication(node1, node2)
+                | BooleanTree::NonImplication(node1, node2) => {
+                    _get_variables(node1, bound, variables);
+                    _get_variables(node2, bound, variables);
+                }
+                BooleanTree::Ite(node1, node2, node3) => {
+                    _get_variables(node1, bound, variables);
+                    _get_variables(node2, bound, variables);
+                    _get_variables(node3, bound, variables);
+                }
+                BooleanTree::Forall(variable, node) | BooleanTree::Exists(variable, node) => {
+                    bound.push(*variable);
+                    _get_variables(node, bound, variables);
+                    bound.pop();
+                }
+                BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) | BooleanTree::XorN(nodes) => {
+                    for node in nodes {
+                        _get_variables(node, bound, variables);
+                    }
                 }
             }
         }
         let mut variables = HashSet::new();
-        _get_variables(self, &mut variables);
+        _get_variables(self, &mut vec![], &mut variables);
         variables.into_iter().sorted().collect_vec()
     }
 
+    pub fn is_quantified(&self) -> bool {
+        match self {
+            BooleanTree::Value(_) | BooleanTree::Variable(_) => false,
+            BooleanTree::Not(node) => node.is_quantified(),
+            BooleanTree::Or(node1, node2)
+            | BooleanTree::And(node1, node2)
+            | BooleanTree::Xor(node1, node2)
+            | BooleanTree::Implication(node1, node2)
+            | BooleanTree::Equivalence(node1, node2)
+            | BooleanTree::Nand(node1, node2)
+            | BooleanTree::Nor(node1, node2)
+            | BooleanTree::ConverseImplication(node1, node2)
+            | BooleanTree::NonImplication(node1, node2) => {
+                node1.is_quantified() || node2.is_quantified()
+            }
+            BooleanTree::Ite(node1, node2, node3) => {
+                node1.is_quantified() || node2.is_quantified() || node3.is_quantified()
+            }
+            BooleanTree::Forall(..) | BooleanTree::Exists(..) => true,
+            BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) | BooleanTree::XorN(nodes) => {
+                nodes.iter().any(BooleanTree::is_quantified)
+            }
+        }
+    }
+
+    /// replaces the free occurrences of `variable`
+    fn substitute(&mut self, variable: char, value: bool) {
+        match self {
+            BooleanTree::Value(_) => {}
+            BooleanTree::Variable(c) => {
+                if *c == variable {
+                    *self = BooleanTree::Value(value);
+                }
+            }
+            BooleanTree::Not(node) => node.substitute(variable, value),
+            BooleanTree::Or(node1, node2)
+            | BooleanTree::And(node1, node2)
+            | BooleanTree::Xor(node1, node2)
+            | BooleanTree::Implication(node1, node2)
+            | BooleanTree::Equivalence(node1, node2)
+            | BooleanTree::Nand(node1, node2)
+            | BooleanTree::Nor(node1, node2)
+            | BooleanTree::ConverseImplication(node1, node2)
+            | BooleanTree::NonImplication(node1, node2) => {
+                node1.substitute(variable, value);
+                node2.substitute(variable, value);
+            }
+            BooleanTree::Ite(node1, node2, node3) => {
+                node1.substitute(variable, value);
+                node2.substitute(variable, value);
+                node3.substitute(variable, value);
+            }
+            BooleanTree::Forall(c, node) | BooleanTree::Exists(c, node) => {
+                if *c != variable {
+                    node.substitute(variable, value);
+                }
+            }
+            BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) | BooleanTree::XorN(nodes) => {
+                for node in nodes {
+                    node.substitute(variable, value);
+                }
+            }
+        }
+    }
+
+    /// Shannon expansion: `∀A. f` becomes `f[A := 0] & f[A := 1]`
+    /// and `∃A. f` becomes `f[A := 0] | f[A := 1]`
+    pub fn expand_quantifiers(&mut self) {
+        match self {
+            BooleanTree::Value(_) | BooleanTree::Variable(_) => {}
+            BooleanTree::Not(node) => node.expand_quantifiers(),
+            BooleanTree::Or(node1, node2)
+            | BooleanTree::And(node1, node2)
+            | BooleanTree::Xor(node1, node2)
+            | BooleanTree::Implication(node1, node2)
+            | BooleanTree::Equivalence(node1, node2)
+            | BooleanTree::Nand(node1, node2)
+            | BooleanTree::Nor(node1, node2)
+            | BooleanTree::ConverseImplication(node1, node2)
+            | BooleanTree::NonImplication(node1, node2) => {
+                node1.expand_quantifiers();
+                node2.expand_quantifiers();
+            }
+            BooleanTree::Ite(node1, node2, node3) => {
+                node1.expand_quantifiers();
+                node2.expand_quantifiers();
+                node3.expand_quantifiers();
+            }
+            BooleanTree::Forall(variable, node) | BooleanTree::Exists(variable, node) => {
+                node.expand_quantifiers();
+                let mut if_false = node.clone();
+                let mut if_true = node.clone();
+                if_false.substitute(*variable, false);
+                if_true.substitute(*variable, true);
+                *self = if matches!(self, BooleanTree::Forall(..)) {
+                    BooleanTree::And(if_false, if_true)
+                } else {
+                    BooleanTree::Or(if_false, if_true)
+                };
+            }
+            BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) | BooleanTree::XorN(nodes) => {
+                for node in nodes {
+                    node.expand_quantifiers();
+                }
+            }
+        }
+    }
+
     pub fn compute_truth_table(&self) -> (Vec<char>, Vec<Vec<bool>>, Vec<bool>) {
         fn _compute_truth_table(
             tree: &BooleanTree,
@@ -213,7 +695,9 @@ impl BooleanTree {
     fn remove_forbidden_operations(&mut self) {
         match self {
             BooleanTree::Value(_) | BooleanTree::Variable(_) => {}
-            BooleanTree::Not(node) => node.remove_forbidden_operations(),
+            BooleanTree::Not(node)
+            | BooleanTree::Forall(_, node)
+            | BooleanTree::Exists(_, node) => node.remove_forbidden_operations(),
             BooleanTree::Or(node1, node2) | BooleanTree::And(node1, node2) => {
                 node1.remove_forbidden_operations();
                 node2.remove_forbidden_operations();
@@ -248,6 +732,52 @@ impl BooleanTree {
                     )),
                 );
             }
+            BooleanTree::Nand(node1, node2) => {
+                node1.remove_forbidden_operations();
+                node2.remove_forbidden_operations();
+                *self = BooleanTree::Not(Box::new(BooleanTree::And(node1.clone(), node2.clone())));
+            }
+            BooleanTree::Nor(node1, node2) => {
+                node1.remove_forbidden_operations();
+                node2.remove_forbidden_operations();
+                *self = BooleanTree::Not(Box::new(BooleanTree::Or(node1.clone(), node2.clone())));
+            }
+            BooleanTree::ConverseImplication(node1, node2) => {
+                node1.remove_forbidden_operations();
+                node2.remove_forbidden_operations();
+                *self = BooleanTree::Or(node1.clone(), Box::new(BooleanTree::Not(node2.clone())));
+            }
+            BooleanTree::NonImplication(node1, node2) => {
+                node1.remove_forbidden_operations();
+                node2.remove_forbidden_operations();
+                *self = BooleanTree::And(node1.clone(), Box::new(BooleanTree::Not(node2.clone())));
+            }
+            BooleanTree::Ite(node1, node2, node3) => {
+                node1.remove_forbidden_operations();
+                node2.remove_forbidden_operations();
+                node3.remove_forbidden_operations();
+                // (c > t) & (!c > e), already close to CNF
+                *self = BooleanTree::And(
+                    Box::new(BooleanTree::Or(
+                        Box::new(BooleanTree::Not(node1.clone())),
+                        node2.clone(),
+                    )),
+                    Box::new(BooleanTree::Or(node1.clone(), node3.clone())),
+                );
+            }
+            BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) => {
+                for node in nodes {
+                    node.remove_forbidden_operations();
+                }
+            }
+            BooleanTree::XorN(nodes) => {
+                *self = nodes
+                    .iter()
+                    .cloned()
+                    .reduce(|a, b| BooleanTree::Xor(Box::new(a), Box::new(b)))
+                    .unwrap_or(BooleanTree::Value(false));
+                self.remove_forbidden_operations();
+            }
         }
     }
 
@@ -273,6 +803,34 @@ impl BooleanTree {
                     *self = BooleanTree::Or(Box::new(left), Box::new(right));
                     true
                 }
+                BooleanTree::Forall(variable, grandchild) => {
+                    let mut scope = BooleanTree::Not(grandchild);
+                    scope.apply_de_morgan();
+                    *self = BooleanTree::Exists(variable, Box::new(scope));
+                    true
+                }
+                BooleanTree::Exists(variable, grandchild) => {
+                    let mut scope = BooleanTree::Not(grandchild);
+                    scope.apply_de_morgan();
+                    *self = BooleanTree::Forall(variable, Box::new(scope));
+                    true
+                }
+                BooleanTree::AndN(grandchildren) | BooleanTree::OrN(grandchildren) => {
+                    let negated = grandchildren
+                        .into_iter()
+                        .map(|grandchild| {
+                            let mut negated = BooleanTree::Not(Box::new(grandchild));
+                            negated.apply_de_morgan();
+                            negated
+                        })
+                        .collect();
+                    *self = if matches!(**child, BooleanTree::AndN(_)) {
+                        BooleanTree::OrN(negated)
+                    } else {
+                        BooleanTree::AndN(negated)
+                    };
+                    true
+                }
                 _ => unreachable!(),
             },
             BooleanTree::Or(child1, child2) | BooleanTree::And(child1, child2) => {
@@ -281,6 +839,16 @@ impl BooleanTree {
                 let b2 = child2.apply_de_morgan();
                 b1 || b2
             }
+            BooleanTree::Forall(_, child) | BooleanTree::Exists(_, child) => {
+                child.apply_de_morgan()
+            }
+            BooleanTree::AndN(children) | BooleanTree::OrN(children) => {
+                let mut changed = false;
+                for child in children {
+                    changed |= child.apply_de_morgan();
+                }
+                changed
+            }
             _ => unreachable!(),
         }
     }
@@ -300,6 +868,14 @@ impl BooleanTree {
                 child1.remove_double_negation();
                 child2.remove_double_negation();
             }
+            BooleanTree::Forall(_, child) | BooleanTree::Exists(_, child) => {
+                child.remove_double_negation();
+            }
+            BooleanTree::AndN(children) | BooleanTree::OrN(children) => {
+                for child in children {
+                    child.remove_double_negation();
+                }
+            }
             _ => unreachable!(),
         }
     }
@@ -329,6 +905,11 @@ impl BooleanTree {
                     new_child2.apply_distributivity();
                     *self = BooleanTree::And(Box::new(new_child1), Box::new(new_child2));
                     true
+                } else if matches!(**child1, BooleanTree::AndN(_))
+                    || matches!(**child2, BooleanTree::AndN(_))
+                {
+                    *self = BooleanTree::OrN(vec![*child1.clone(), *child2.clone()]);
+                    self.apply_distributivity()
                 } else {
                     // store in variables to avoid short-circuiting
                     let b1 = child1.apply_distributivity();
@@ -336,6 +917,44 @@ impl BooleanTree {
                     b1 || b2
                 }
             }
+            BooleanTree::AndN(children) => {
+                let mut changed = false;
+                for child in children {
+                    changed |= child.apply_distributivity();
+                }
+                changed
+            }
+            BooleanTree::OrN(children) => {
+                let conjunction = children
+                    .iter()
+                    .position(|child| matches!(child, BooleanTree::And(..) | BooleanTree::AndN(_)));
+                let Some(i) = conjunction else {
+                    let mut changed = false;
+                    for child in children {
+                        changed |= child.apply_distributivity();
+                    }
+                    return changed;
+                };
+                let mut others = children.clone();
+                let grandchildren = match others.remove(i) {
+                    BooleanTree::And(grandchild1, grandchild2) => vec![*grandchild1, *grandchild2],
+                    BooleanTree::AndN(grandchildren) => grandchildren,
+                    _ => unreachable!(),
+                };
+                *self = BooleanTree::AndN(
+                    grandchildren
+                        .into_iter()
+                        .map(|grandchild| {
+                            let mut disjunction = others.clone();
+                            disjunction.push(grandchild);
+                            let mut new_child = BooleanTree::OrN(disjunction);
+                            new_child.apply_distributivity();
+                            new_child
+                        })
+                        .collect(),
+                );
+                true
+            }
             _ => unreachable!(),
         }
     }
@@ -353,9 +972,17 @@ impl BooleanTree {
             BooleanTree::Or(node1, node2) | BooleanTree::And(node1, node2) => {
                 node1.is_nnf() && node2.is_nnf()
             }
-            BooleanTree::Xor(..) | BooleanTree::Implication(..) | BooleanTree::Equivalence(..) => {
-                false
-            }
+            BooleanTree::Forall(_, node) | BooleanTree::Exists(_, node) => node.is_nnf(),
+            BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) => nodes.iter().all(Self::is_nnf),
+            BooleanTree::Xor(..)
+            | BooleanTree::XorN(_)
+            | BooleanTree::Implication(..)
+            | BooleanTree::Equivalence(..)
+            | BooleanTree::Nand(..)
+            | BooleanTree::Nor(..)
+            | BooleanTree::ConverseImplication(..)
+            | BooleanTree::NonImplication(..)
+            | BooleanTree::Ite(..) => false,
         }
     }
 
@@ -366,6 +993,7 @@ impl BooleanTree {
                 matches!(**node, BooleanTree::Value(_) | BooleanTree::Variable(_))
             }
             BooleanTree::Or(node1, node2) => node1.is_cnf_term() && node2.is_cnf_term(),
+            BooleanTree::OrN(nodes) => nodes.iter().all(Self::is_cnf_term),
             _ => false,
         }
     }
@@ -378,6 +1006,8 @@ impl BooleanTree {
             }
             BooleanTree::Or(node1, node2) => node1.is_cnf_term() && node2.is_cnf_term(),
             BooleanTree::And(node1, node2) => node1.is_cnf() && node2.is_cnf(),
+            BooleanTree::OrN(nodes) => nodes.iter().all(Self::is_cnf_term),
+            BooleanTree::AndN(nodes) => nodes.iter().all(Self::is_cnf),
             _ => false,
         }
     }
@@ -388,10 +1018,93 @@ impl BooleanTree {
         self.remove_double_negation();
     }
 
+    /// quantifiers are expanded first, the result only depends on the free variables
     pub fn make_cnf(&mut self) {
+        self.expand_quantifiers();
         self.make_nnf();
         while self.apply_distributivity() {}
     }
+
+    /// Flattens nested `&`, `|` and `^` into n-ary nodes whose operands are sorted
+    /// and deduplicated (under `^`, equal operands cancel out in pairs)
+    pub fn normalize(&mut self) {
+        let (nodes, flat_node, identity): (_, fn(Vec<BooleanTree>) -> BooleanTree, _) = match self {
+            BooleanTree::Value(_) | BooleanTree::Variable(_) => return,
+            BooleanTree::Not(node)
+            | BooleanTree::Forall(_, node)
+            | BooleanTree::Exists(_, node) => return node.normalize(),
+            BooleanTree::Implication(node1, node2)
+            | BooleanTree::Equivalence(node1, node2)
+            | BooleanTree::Nand(node1, node2)
+            | BooleanTree::Nor(node1, node2)
+            | BooleanTree::ConverseImplication(node1, node2)
+            | BooleanTree::NonImplication(node1, node2) => {
+                node1.normalize();
+                node2.normalize();
+                return;
+            }
+            BooleanTree::Ite(node1, node2, node3) => {
+                node1.normalize();
+                node2.normalize();
+                node3.normalize();
+                return;
+            }
+            BooleanTree::And(node1, node2) => (
+                vec![*node1.clone(), *node2.clone()],
+                BooleanTree::AndN as _,
+                true,
+            ),
+            BooleanTree::Or(node1, node2) => (
+                vec![*node1.clone(), *node2.clone()],
+                BooleanTree::OrN as _,
+                false,
+            ),
+            BooleanTree::Xor(node1, node2) => (
+                vec![*node1.clone(), *node2.clone()],
+                BooleanTree::XorN as _,
+                false,
+            ),
+            BooleanTree::AndN(nodes) => (nodes.clone(), BooleanTree::AndN as _, true),
+            BooleanTree::OrN(nodes) => (nodes.clone(), BooleanTree::OrN as _, false),
+            BooleanTree::XorN(nodes) => (nodes.clone(), BooleanTree::XorN as _, false),
+        };
+
+        let kind = std::mem::discriminant(&flat_node(vec![]));
+        let mut operands = vec![];
+        for mut node in nodes {
+            node.normalize();
+            let is_nested = std::mem::discriminant(&node) == kind;
+            match node {
+                BooleanTree::AndN(nested)
+                | BooleanTree::OrN(nested)
+                | BooleanTree::XorN(nested)
+                    if is_nested =>
+                {
+                    operands.extend(nested)
+                }
+                node => operands.push(node),
+            }
+        }
+        operands.sort();
+        if kind != std::mem::discriminant(&BooleanTree::XorN(vec![])) {
+            operands.dedup();
+        } else {
+            let mut kept: Vec<BooleanTree> = vec![];
+            for operand in operands {
+                if kept.last() == Some(&operand) {
+                    kept.pop();
+                } else {
+                    kept.push(operand);
+                }
+            }
+            operands = kept;
+        }
+        *self = match operands.len() {
+            0 => BooleanTree::Value(identity),
+            1 => operands.pop().unwrap(),
+            _ => flat_node(operands),
+        };
+    }
 }
 
 #[cfg(test)]
@@ -468,6 +1181,11 @@ mod tests {
         check_nnf("A!B!!C!!!D!!!!E!!!!!>>>>");
         check_nnf("A!B!!C!!!D!!!!E!!!!!====");
         check_nnf("A!B!!C!!!D!!!!E!!!!!^^^^");
+        check_nnf("AB↑");
+        check_nnf("AB↓!");
+        check_nnf("AB<C↛");
+        check_nnf("ABC?!");
+        check_nnf("AB↑CD↓E?F<");
     }
 
     #[test]
@@ -550,5 +1268,169 @@ mod tests {
         check_cnf("AB&CD&|");
         check_cnf("AC>BCD&&!&");
         check_cnf("ABCDE>=&|ABCD|||ABC>>AB=&&&");
+        check_cnf("AB↑");
+        check_cnf("AB↓!");
+        check_cnf("AB<C↛");
+        check_cnf("ABC?!");
+        check_cnf("AB↑CD↓E?F<");
+        check_cnf("ABCD↛↛↛");
+    }
+
+    #[test]
+    fn test_quantifiers_rpn() {
+        let tree = BooleanTree::new("AB|A∀", true).unwrap();
+        assert!(matches!(tree, BooleanTree::Forall('A', _)));
+        assert_eq!(tree.to_formula(), "AB|A∀");
+        assert_eq!(tree.get_variables(), vec!['B']);
+        assert!(
+            BooleanTree::new("BB∃", true)
+                .unwrap()
+                .get_variables()
+                .is_empty()
+        );
+
+        assert!(BooleanTree::new("A∀", true).is_err());
+        assert!(BooleanTree::new("AB&1∀", true).is_err());
+        assert!(BooleanTree::new("11∃", false).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_quantifiers() {
+        let evaluate = |formula| BooleanTree::new(formula, true).unwrap().evaluate();
+        assert!(evaluate("AA!|A∀"));
+        assert!(!evaluate("AA!&A∃"));
+        assert!(evaluate("AB=B∃A∀"));
+        assert!(!evaluate("AB=A∀B∃"));
+        // the inner quantifiers shadow the outer ones
+        assert!(evaluate("AB=B∃A∀B∀"));
+        assert!(evaluate("AA∀!A∃"));
+    }
+
+    #[test]
+    fn test_infix() {
+        fn check(infix: &str, rpn: &str) {
+            let tree = BooleanTree::from_infix(infix).unwrap();
+            assert_eq!(tree.to_formula(), rpn, "{infix}");
+            let printed = tree.to_infix();
+            assert_eq!(
+                BooleanTree::from_infix(&printed).unwrap().to_formula(),
+                rpn,
+                "{infix} printed as {printed}"
+            );
+        }
+
+        check("A", "A");
+        check("!!0", "0!!");
+        check("A & B | C", "AB&C|");
+        check("A | B & C", "ABC&|");
+        check("(A | B) & C", "AB|C&");
+        check("A | B | C", "AB|C|");
+        check("A | (B | C)", "ABC||");
+        check("A > B > C", "ABC>>");
+        check("(A > B) > C", "AB>C>");
+        check("A = B > C ^ D", "ABCD^>=");
+        check("!(A & B) ^ !A", "AB&!A!^");
+        check("∀A. ∃B. A = B", "AB=B∃A∀");
+        check("!∀A. A & B", "AB&A∀!");
+        check("C & (∀A. A) | B", "CAA∀&B|");
+        check("(∃B. B) > A", "BB∃A>");
+        check("A ↑ B & C ↓ D", "AB↑C&D↓");
+        check("A ↑ (B ↑ C)", "ABC↑↑");
+        check("A < B ↛ C", "ABC↛<");
+        check("(A < B) ↛ C", "AB<C↛");
+        check("A ? B : C", "ABC?");
+        check("A = B ? C | D : E ? F : 0", "AB=CD|EF0??");
+        check("(A ? B : C) ? D : E", "ABC?DE?");
+        check("A ? B ? C : D : E", "ABCD?E?");
+        check("!(A ? B : C) & D", "ABC?!D&");
+
+        assert!(BooleanTree::from_infix("").is_err());
+        assert!(BooleanTree::from_infix("A &").is_err());
+        assert!(BooleanTree::from_infix("(A | B").is_err());
+        assert!(BooleanTree::from_infix("A | B)").is_err());
+        assert!(BooleanTree::from_infix("A B").is_err());
+        assert!(BooleanTree::from_infix("a").is_err());
+        assert!(BooleanTree::from_infix("∀1. A").is_err());
+        assert!(BooleanTree::from_infix("∀A A").is_err());
+        assert!(BooleanTree::from_infix("A ? B").is_err());
+        assert!(BooleanTree::from_infix("A ? B C").is_err());
+    }
+
+    #[test]
+    fn test_nnf_quantifiers() {
+        let mut tree = BooleanTree::from_infix("!∀A. ∃B. A > B").unwrap();
+        tree.make_nnf();
+        assert!(tree.is_nnf());
+        assert_eq!(tree.to_infix(), "∃A. ∀B. A & !B");
+    }
+
+    #[test]
+    fn test_cnf_quantifiers() {
+        let mut tree = BooleanTree::from_infix("∀A. A | B & C").unwrap();
+        let truth_table_before = tree.compute_truth_table();
+        tree.make_cnf();
+        assert!(tree.is_cnf());
+        assert!(!tree.is_quantified());
+        assert_eq!(tree.compute_truth_table(), truth_table_before);
+    }
+
+    #[test]
+    fn test_normalize() {
+        fn normalized(formula: &str) -> BooleanTree {
+            let initial = BooleanTree::new(formula, true).unwrap();
+            let mut tree = initial.clone();
+            tree.normalize();
+            // operands cancelling out under `^` may remove variables
+            let differ = BooleanTree::Xor(Box::new(initial), Box::new(tree.clone()));
+            assert!(!differ.is_satisfiable(), "{formula}");
+            tree
+        }
+
+        let variables = |chars: &str| chars.chars().map(BooleanTree::Variable).collect_vec();
+        assert_eq!(normalized("ABCD&&&"), BooleanTree::AndN(variables("ABCD")));
+        assert_eq!(normalized("DB&CA&&B&").to_formula(), "ABCD&&&");
+        assert_eq!(normalized("AB|CD||").to_infix(), "A | B | C | D");
+        assert_eq!(normalized("AA&A&"), BooleanTree::Variable('A'));
+        assert_eq!(normalized("AB^A^"), BooleanTree::Variable('B'));
+        assert_eq!(normalized("AB^BA^^"), BooleanTree::Value(false));
+        assert_eq!(normalized("AB^CA^^A^").to_formula(), "ABC^^");
+        assert_eq!(normalized("AB|C&!D!&").to_infix(), "!D & !(C & (A | B))");
+        assert_eq!(normalized("AB&CD&|").to_infix(), "A & B | C & D");
+        assert_eq!(normalized("AB&C>D&").to_formula(), "DAB&C>&");
+        assert_eq!(normalized("AB&BA&="), normalized("BA&AB&="));
+    }
+
+    #[test]
+    fn test_flat_cnf() {
+        let variables = |chars: &str| chars.chars().map(BooleanTree::Variable).collect_vec();
+        let clause = BooleanTree::OrN(vec![
+            BooleanTree::Not(Box::new(BooleanTree::Variable('A'))),
+            BooleanTree::Variable('B'),
+            BooleanTree::Variable('C'),
+        ]);
+        assert!(clause.is_cnf());
+        assert!(BooleanTree::AndN(vec![clause.clone(), BooleanTree::Variable('D')]).is_cnf());
+        assert!(!BooleanTree::OrN(vec![clause, BooleanTree::AndN(variables("AB"))]).is_cnf());
+        assert!(!BooleanTree::XorN(variables("AB")).is_cnf());
+        assert!(BooleanTree::AndN(vec![]).is_cnf());
+
+        for formula in [
+            "ABCD&&&!",
+            "AB&CD&EF&||",
+            "ABC^^D|",
+            "AB&C&D!E!&F&|!",
+            "ABCD||&EF&G|&",
+            "AB=CD=EF=&&",
+        ] {
+            let mut tree = BooleanTree::new(formula, true).unwrap();
+            tree.normalize();
+            let truth_table_before = tree.compute_truth_table();
+            tree.make_cnf();
+            assert!(tree.is_cnf(), "{} is not in CNF", tree.to_formula());
+            assert_eq!(tree.compute_truth_table(), truth_table_before, "{formula}");
+            tree.normalize();
+            assert!(tree.is_cnf(), "{} is not in CNF", tree.to_formula());
+            assert_eq!(tree.compute_truth_table(), truth_table_before, "{formula}");
+        }
     }
 }
@@ -0,0 +1,409 @@
+use crate::{BooleanTree, Solver, SolverResult};
+
+/// Non-zero integer, DIMACS style: `v` is the variable `v` and `-v` its negation
+pub type Literal = i32;
+pub type Clause = Vec<Literal>;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cnf {
+    pub num_variables: usize,
+    pub clauses: Vec<Clause>,
+}
+
+pub fn variable(literal: Literal) -> usize {
+    literal.unsigned_abs() as usize
+}
+
+/// Value of a literal under a model where `model[v - 1]` is the value of variable `v`
+pub fn literal_value(model: &[bool], literal: Literal) -> bool {
+    model[variable(literal) - 1] == (literal > 0)
+}
+
+impl Cnf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_variable(&mut self) -> Literal {
+        self.num_variables += 1;
+        self.num_variables as Literal
+    }
+
+    pub fn add_clause(&mut self, clause: impl IntoIterator<Item = Literal>) {
+        let clause: Clause = clause.into_iter().collect();
+        assert!(!clause.contains(&0), "0 is not a valid literal");
+        if let Some(max) = clause.iter().map(|&lit| variable(lit)).max() {
+            self.num_variables = self.num_variables.max(max);
+        }
+        self.clauses.push(clause);
+    }
+
+    pub fn from_dimacs(dimacs: &str) -> Result<Self, &'static str> {
+        let mut cnf = Cnf::new();
+        let mut num_clauses: Option<usize> = None;
+        let mut clause = vec![];
+        for line in dimacs.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('c') || line.starts_with('%') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('p') {
+                if num_clauses.is_some() {
+                    return Err("duplicate problem line");
+                }
+                let fields = header.split_whitespace().collect::<Vec<_>>();
+                let [format, variables, clauses] = fields[..] else {
+                    return Err("invalid problem line");
+                };
+                if format != "cnf" {
+                    return Err("problem line is not of type cnf");
+                }
+                cnf.num_variables = variables.parse().map_err(|_| "invalid variable count")?;
+                num_clauses = Some(clauses.parse().map_err(|_| "invalid clause count")?);
+                continue;
+            }
+            if num_clauses.is_none() {
+                return Err("clause before problem line");
+            }
+            for token in line.split_whitespace() {
+                let literal: Literal = token.parse().map_err(|_| "invalid literal")?;
+                if literal == 0 {
+                    cnf.add_clause(std::mem::take(&mut clause));
+                } else {
+                    clause.push(literal);
+                }
+            }
+        }
+
+        match num_clauses {
+            None => Err("missing problem line"),
+            Some(_) if !clause.is_empty() => Err("unterminated clause"),
+            Some(n) if n != cnf.clauses.len() => Err("clause count does not match problem line"),
+            Some(_) => Ok(cnf),
+        }
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        let mut dimacs = format!("p cnf {} {}\n", self.num_variables, self.clauses.len());
+        for clause in &self.clauses {
+            for literal in clause {
+                dimacs += &format!("{literal} ");
+            }
+            dimacs += "0\n";
+        }
+        dimacs
+    }
+
+    pub fn evaluate(&self, model: &[bool]) -> bool {
+        self.clauses
+            .iter()
+            .all(|clause| clause.iter().any(|&lit| literal_value(model, lit)))
+    }
+
+    /// Tseitin transformation: equisatisfiable CNF where variable `i + 1` is `variables[i]`
+    /// and every other variable stands for a subformula. Quantifiers are expanded first.
+    pub fn from_tree(tree: &BooleanTree) -> (Cnf, Vec<char>) {
+        fn tseitin(cnf: &mut Cnf, tree: &BooleanTree, variables: &[char]) -> Literal {
+            match tree {
+                BooleanTree::Value(b) => {
+                    let v = cnf.new_variable();
+                    cnf.add_clause([if *b { v } else { -v }]);
+                    v
+                }
+                BooleanTree::Variable(c) => variables.binary_search(c).unwrap() as Literal + 1,
+                BooleanTree::Not(node) => -tseitin(cnf, node, variables),
+                BooleanTree::Or(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    cnf.or_gate(a, b)
+                }
+                BooleanTree::And(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    cnf.and_gate(a, b)
+                }
+                BooleanTree::Xor(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    cnf.xor_gate(a, b)
+                }
+                BooleanTree::Implication(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    cnf.or_gate(-a, b)
+                }
+                BooleanTree::Equivalence(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    -cnf.xor_gate(a, b)
+                }
+                BooleanTree::Nand(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    -cnf.and_gate(a, b)
+                }
+                BooleanTree::Nor(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    -cnf.or_gate(a, b)
+                }
+                BooleanTree::ConverseImplication(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    cnf.or_gate(a, -b)
+                }
+                BooleanTree::NonImplication(node1, node2) => {
+                    let (a, b) = (
+                        tseitin(cnf, node1, variables),
+                        tseitin(cnf, node2, variables),
+                    );
+                    cnf.and_gate(a, -b)
+                }
+                BooleanTree::Ite(node1, node2, node3) => {
+                    let c = tseitin(cnf, node1, variables);
+                    let (t, e) = (
+                        tseitin(cnf, node2, variables),
+                        tseitin(cnf, node3, variables),
+                    );
+                    cnf.ite_gate(c, t, e)
+                }
+                BooleanTree::AndN(nodes) => {
+                    let literals = nodes
+                        .iter()
+                        .map(|node| tseitin(cnf, node, variables))
+                        .collect::<Vec<_>>();
+                    cnf.and_n_gate(&literals)
+                }
+                BooleanTree::OrN(nodes) => {
+                    let literals = nodes
+                        .iter()
+                        .map(|node| tseitin(cnf, node, variables))
+                        .collect::<Vec<_>>();
+                    cnf.or_n_gate(&literals)
+                }
+                BooleanTree::XorN(nodes) => match nodes.split_first() {
+                    None => tseitin(cnf, &BooleanTree::Value(false), variables),
+                    Some((first, rest)) => {
+                        let first = tseitin(cnf, first, variables);
+                        rest.iter().fold(first, |a, node| {
+                            let b = tseitin(cnf, node, variables);
+                            cnf.xor_gate(a, b)
+                        })
+                    }
+                },
+                BooleanTree::Forall(..) | BooleanTree::Exists(..) => {
+                    unreachable!("quantifiers are expanded first")
+                }
+            }
+        }
+
+        let mut tree = tree.clone();
+        tree.expand_quantifiers();
+        let variables = tree.get_variables();
+        let mut cnf = Cnf {
+            num_variables: variables.len(),
+            clauses: vec![],
+        };
+        let root = tseitin(&mut cnf, &tree, &variables);
+        cnf.add_clause([root]);
+        (cnf, variables)
+    }
+
+    /// fresh variable equivalent to `a & b`
+    pub fn and_gate(&mut self, a: Literal, b: Literal) -> Literal {
+        let v = self.new_variable();
+        self.add_clause([-v, a]);
+        self.add_clause([-v, b]);
+        self.add_clause([v, -a, -b]);
+        v
+    }
+
+    /// fresh variable equivalent to `a | b`
+    pub fn or_gate(&mut self, a: Literal, b: Literal) -> Literal {
+        -self.and_gate(-a, -b)
+    }
+
+    /// fresh variable equivalent to the conjunction of `literals`, true when empty
+    pub fn and_n_gate(&mut self, literals: &[Literal]) -> Literal {
+        let v = self.new_variable();
+        for &lit in literals {
+            self.add_clause([-v, lit]);
+        }
+        self.add_clause(std::iter::once(v).chain(literals.iter().map(|&lit| -lit)));
+        v
+    }
+
+    /// fresh variable equivalent to the disjunction of `literals`, false when empty
+    pub fn or_n_gate(&mut self, literals: &[Literal]) -> Literal {
+        let negated = literals.iter().map(|&lit| -lit).collect::<Vec<_>>();
+        -self.and_n_gate(&negated)
+    }
+
+    /// fresh variable equivalent to `a ^ b`
+    pub fn xor_gate(&mut self, a: Literal, b: Literal) -> Literal {
+        let v = self.new_variable();
+        self.add_clause([-v, a, b]);
+        self.add_clause([-v, -a, -b]);
+        self.add_clause([v, -a, b]);
+        self.add_clause([v, a, -b]);
+        v
+    }
+
+    /// fresh variable equivalent to `if c { t } else { e }`
+    pub fn ite_gate(&mut self, c: Literal, t: Literal, e: Literal) -> Literal {
+        let v = self.new_variable();
+        self.add_clause([-v, -c, t]);
+        self.add_clause([-v, c, e]);
+        self.add_clause([v, -c, -t]);
+        self.add_clause([v, c, -e]);
+        v
+    }
+
+    /// Variables 1 to 26 become 'A' to 'Z'
+    pub fn to_tree(&self) -> Result<BooleanTree, &'static str> {
+        if self.num_variables > 26 {
+            return Err("too many variables to name them with letters");
+        }
+        let literal = |lit: Literal| {
+            let variable = BooleanTree::Variable((b'A' + variable(lit) as u8 - 1) as char);
+            if lit > 0 {
+                variable
+            } else {
+                BooleanTree::Not(Box::new(variable))
+            }
+        };
+        let clause = |clause: &Clause| {
+            clause
+                .iter()
+                .map(|&lit| literal(lit))
+                .reduce(|a, b| BooleanTree::Or(Box::new(a), Box::new(b)))
+                .unwrap_or(BooleanTree::Value(false))
+        };
+        Ok(self
+            .clauses
+            .iter()
+            .map(clause)
+            .reduce(|a, b| BooleanTree::And(Box::new(a), Box::new(b)))
+            .unwrap_or(BooleanTree::Value(true)))
+    }
+
+    pub fn solve(&self) -> Option<Vec<bool>> {
+        match Solver::new(self).solve(&[]) {
+            SolverResult::Satisfiable(model) => Some(model),
+            SolverResult::Unsatisfiable(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dimacs_round_trip() {
+        let dimacs = "c example\np cnf 3 2\n1 -3 0\n2 3\n-1 0\n";
+        let cnf = Cnf::from_dimacs(dimacs).unwrap();
+        assert_eq!(cnf.num_variables, 3);
+        assert_eq!(cnf.clauses, vec![vec![1, -3], vec![2, 3, -1]]);
+        assert_eq!(Cnf::from_dimacs(&cnf.to_dimacs()).unwrap(), cnf);
+    }
+
+    #[test]
+    fn test_dimacs_errors() {
+        assert!(Cnf::from_dimacs("1 2 0\n").is_err());
+        assert!(Cnf::from_dimacs("p cnf 2 1\n1 2\n").is_err());
+        assert!(Cnf::from_dimacs("p cnf 2 2\n1 2 0\n").is_err());
+        assert!(Cnf::from_dimacs("p cnf 2 1\n1 x 0\n").is_err());
+        assert!(Cnf::from_dimacs("p wcnf 2 1\n1 2 0\n").is_err());
+    }
+
+    #[test]
+    fn test_to_tree() {
+        let mut cnf = Cnf::new();
+        assert_eq!(cnf.to_tree().unwrap().to_formula(), "1");
+        cnf.add_clause([1, -3]);
+        cnf.add_clause([2]);
+        assert_eq!(cnf.to_tree().unwrap().to_formula(), "AC!|B&");
+        cnf.add_clause([]);
+        assert_eq!(cnf.to_tree().unwrap().to_formula(), "AC!|B&0&");
+        cnf.add_clause([27]);
+        assert!(cnf.to_tree().is_err());
+    }
+
+    #[test]
+    fn test_from_tree() {
+        // the clauses are satisfiable under the assumptions of each input exactly
+        // when the tree is true for it
+        let check = |tree: BooleanTree| {
+            let (cnf, variables) = Cnf::from_tree(&tree);
+            let (expected_variables, inputs, outputs) = tree.compute_truth_table();
+            assert_eq!(variables, expected_variables);
+            for (input, output) in inputs.iter().zip(outputs) {
+                let assumptions = input
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &b)| {
+                        if b {
+                            i as Literal + 1
+                        } else {
+                            -(i as Literal) - 1
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let result = Solver::new(&cnf).solve(&assumptions);
+                assert_eq!(matches!(result, SolverResult::Satisfiable(_)), output);
+            }
+        };
+        for formula in [
+            "AB&C|",
+            "AB^C=",
+            "AB>!",
+            "A!A&",
+            "1A|",
+            "0",
+            "AB&BA&=",
+            "AB|A∃",
+            "AB↑C↓",
+            "AB<C↛",
+            "ABC?",
+            "AB↑CD↓E?",
+        ] {
+            check(BooleanTree::new(formula, true).unwrap());
+        }
+        // normalized into AndN, OrN and XorN nodes
+        for formula in ["ABCD&&&A|", "AB^CA^^D^", "AB|CD||E&!"] {
+            let mut tree = BooleanTree::new(formula, true).unwrap();
+            tree.normalize();
+            assert_ne!(tree, BooleanTree::new(formula, true).unwrap());
+            check(tree);
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut cnf = Cnf::new();
+        cnf.add_clause([1, 2]);
+        cnf.add_clause([-1, 2]);
+        cnf.add_clause([1, -2]);
+        let model = cnf.solve().unwrap();
+        assert!(cnf.evaluate(&model));
+        assert_eq!(model, vec![true, true]);
+        cnf.add_clause([-1, -2]);
+        assert_eq!(cnf.solve(), None);
+    }
+}
@@ -0,0 +1,493 @@
+//! CNF encodings of cardinality constraints (`at most k of these literals are true`)
+//! and pseudo-Boolean constraints (`sum(weight * literal) <= bound`).
+//! Auxiliary variables are allocated in the given `Cnf`.
+
+use {
+    crate::{Cnf, cnf::Literal},
+    std::collections::{HashMap, VecDeque},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CardinalityEncoding {
+    /// one clause per subset of `k + 1` literals, no auxiliary variable
+    Pairwise,
+    /// Sinz's sequential counter, `O(n * k)` clauses
+    SequentialCounter,
+    /// Bailleux and Boufkhad's unary totalizer tree
+    Totalizer,
+    /// Batcher's odd-even merge sort, the `k + 1`-th largest output is forced false
+    SortingNetwork,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PseudoBooleanEncoding {
+    /// decision diagram over the partial sums, one variable per node
+    Bdd,
+    /// binary adder network compared against the bound
+    Adder,
+}
+
+pub fn at_most_k(cnf: &mut Cnf, literals: &[Literal], k: usize, encoding: CardinalityEncoding) {
+    if k >= literals.len() {
+        return;
+    }
+    if k == 0 {
+        for &lit in literals {
+            cnf.add_clause([-lit]);
+        }
+        return;
+    }
+    match encoding {
+        CardinalityEncoding::Pairwise => pairwise(cnf, literals, k),
+        CardinalityEncoding::SequentialCounter => sequential_counter(cnf, literals, k),
+        CardinalityEncoding::Totalizer => {
+            let outputs = totalizer(cnf, literals, k + 1);
+            cnf.add_clause([-outputs[k]]);
+        }
+        CardinalityEncoding::SortingNetwork => {
+            let outputs = sorting_network(cnf, literals);
+            cnf.add_clause([-outputs[k]]);
+        }
+    }
+}
+
+pub fn at_least_k(cnf: &mut Cnf, literals: &[Literal], k: usize, encoding: CardinalityEncoding) {
+    if k > literals.len() {
+        cnf.add_clause([]);
+        return;
+    }
+    let negated = literals.iter().map(|&lit| -lit).collect::<Vec<_>>();
+    at_most_k(cnf, &negated, literals.len() - k, encoding);
+}
+
+pub fn exactly_k(cnf: &mut Cnf, literals: &[Literal], k: usize, encoding: CardinalityEncoding) {
+    at_most_k(cnf, literals, k, encoding);
+    at_least_k(cnf, literals, k, encoding);
+}
+
+pub fn exactly_one(cnf: &mut Cnf, literals: &[Literal], encoding: CardinalityEncoding) {
+    exactly_k(cnf, literals, 1, encoding);
+}
+
+fn pairwise(cnf: &mut Cnf, literals: &[Literal], k: usize) {
+    fn _pairwise(
+        cnf: &mut Cnf,
+        literals: &[Literal],
+        k: usize,
+        i: usize,
+        current: &mut Vec<Literal>,
+    ) {
+        if current.len() == k + 1 {
+            cnf.add_clause(current.iter().map(|&lit| -lit));
+            return;
+        }
+        for j in i..literals.len() {
+            current.push(literals[j]);
+            _pairwise(cnf, literals, k, j + 1, current);
+            current.pop();
+        }
+    }
+
+    _pairwise(cnf, literals, k, 0, &mut vec![]);
+}
+
+fn sequential_counter(cnf: &mut Cnf, literals: &[Literal], k: usize) {
+    // registers[i][j] is true if at least j + 1 of the first i + 1 literals are true
+    let n = literals.len();
+    let registers = (0..n - 1)
+        .map(|_| (0..k).map(|_| cnf.new_variable()).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    cnf.add_clause([-literals[0], registers[0][0]]);
+    for &register in &registers[0][1..] {
+        cnf.add_clause([-register]);
+    }
+    for i in 1..n - 1 {
+        cnf.add_clause([-literals[i], registers[i][0]]);
+        cnf.add_clause([-registers[i - 1][0], registers[i][0]]);
+        for j in 1..k {
+            cnf.add_clause([-literals[i], -registers[i - 1][j - 1], registers[i][j]]);
+            cnf.add_clause([-registers[i - 1][j], registers[i][j]]);
+        }
+        cnf.add_clause([-literals[i], -registers[i - 1][k - 1]]);
+    }
+    cnf.add_clause([-literals[n - 1], -registers[n - 2][k - 1]]);
+}
+
+/// Unary count of the true literals, capped at `limit`:
+/// `outputs[j]` is forced true when at least `j + 1` literals are true
+fn totalizer(cnf: &mut Cnf, literals: &[Literal], limit: usize) -> Vec<Literal> {
+    if literals.len() == 1 {
+        return literals.to_vec();
+    }
+    let (left, right) = literals.split_at(literals.len() / 2);
+    let left = totalizer(cnf, left, limit);
+    let right = totalizer(cnf, right, limit);
+    let outputs = (0..limit.min(left.len() + right.len()))
+        .map(|_| cnf.new_variable())
+        .collect::<Vec<_>>();
+    for i in 0..=left.len() {
+        for j in 0..=right.len() {
+            if i + j == 0 || i + j > outputs.len() {
+                continue;
+            }
+            let mut clause = vec![outputs[i + j - 1]];
+            if i > 0 {
+                clause.push(-left[i - 1]);
+            }
+            if j > 0 {
+                clause.push(-right[j - 1]);
+            }
+            cnf.add_clause(clause);
+        }
+    }
+    outputs
+}
+
+/// Outputs sorted from true to false: `outputs[j]` is forced true
+/// when at least `j + 1` literals are true
+fn sorting_network(cnf: &mut Cnf, literals: &[Literal]) -> Vec<Literal> {
+    fn compare(cnf: &mut Cnf, wires: &mut [Literal], i: usize, j: usize) {
+        let (a, b) = (wires[i], wires[j]);
+        let (max, min) = (cnf.new_variable(), cnf.new_variable());
+        cnf.add_clause([-a, max]);
+        cnf.add_clause([-b, max]);
+        cnf.add_clause([-a, -b, min]);
+        wires[i] = max;
+        wires[j] = min;
+    }
+
+    fn merge(cnf: &mut Cnf, wires: &mut [Literal], lo: usize, n: usize, r: usize) {
+        let step = r * 2;
+        if step < n {
+            merge(cnf, wires, lo, n, step);
+            merge(cnf, wires, lo + r, n, step);
+            for i in (lo + r..lo + n - r).step_by(step) {
+                compare(cnf, wires, i, i + r);
+            }
+        } else {
+            compare(cnf, wires, lo, lo + r);
+        }
+    }
+
+    fn sort(cnf: &mut Cnf, wires: &mut [Literal], lo: usize, n: usize) {
+        if n > 1 {
+            let m = n / 2;
+            sort(cnf, wires, lo, m);
+            sort(cnf, wires, lo + m, m);
+            merge(cnf, wires, lo, n, 1);
+        }
+    }
+
+    let mut wires = literals.to_vec();
+    let size = literals.len().next_power_of_two();
+    if size > literals.len() {
+        let padding = cnf.new_variable();
+        cnf.add_clause([-padding]);
+        wires.resize(size, padding);
+    }
+    sort(cnf, &mut wires, 0, size);
+    wires.truncate(literals.len());
+    wires
+}
+
+pub fn at_most_weighted(
+    cnf: &mut Cnf,
+    terms: &[(Literal, u64)],
+    bound: u64,
+    encoding: PseudoBooleanEncoding,
+) {
+    match encoding {
+        PseudoBooleanEncoding::Bdd => bdd(cnf, terms, bound),
+        PseudoBooleanEncoding::Adder => adder(cnf, terms, bound),
+    }
+}
+
+/// Each internal node gets a fresh variable that implies its own constraint
+fn bdd(cnf: &mut Cnf, terms: &[(Literal, u64)], bound: u64) {
+    #[derive(Clone, Copy)]
+    enum Node {
+        Constant(bool),
+        Variable(Literal),
+    }
+
+    fn build(
+        cnf: &mut Cnf,
+        terms: &[(Literal, u64)],
+        suffix_sums: &[Option<u64>],
+        i: usize,
+        bound: u64,
+        memo: &mut HashMap<(usize, u64), Node>,
+    ) -> Node {
+        if suffix_sums[i].is_some_and(|sum| sum <= bound) {
+            return Node::Constant(true);
+        }
+        if let Some(&node) = memo.get(&(i, bound)) {
+            return node;
+        }
+        let (literal, weight) = terms[i];
+        let high = match bound.checked_sub(weight) {
+            None => Node::Constant(false),
+            Some(remaining) => build(cnf, terms, suffix_sums, i + 1, remaining, memo),
+        };
+        let low = build(cnf, terms, suffix_sums, i + 1, bound, memo);
+        let node = cnf.new_variable();
+        match high {
+            Node::Constant(true) => {}
+            Node::Constant(false) => cnf.add_clause([-node, -literal]),
+            Node::Variable(high) => cnf.add_clause([-node, -literal, high]),
+        }
+        match low {
+            Node::Constant(true) => {}
+            Node::Constant(false) => cnf.add_clause([-node]),
+            Node::Variable(low) => cnf.add_clause([-node, low]),
+        }
+        memo.insert((i, bound), Node::Variable(node));
+        Node::Variable(node)
+    }
+
+    // `None` for a sum past `u64::MAX`, above any bound
+    let mut suffix_sums = vec![Some(0u64); terms.len() + 1];
+    for i in (0..terms.len()).rev() {
+        suffix_sums[i] = suffix_sums[i + 1].and_then(|sum| sum.checked_add(terms[i].1));
+    }
+    match build(cnf, terms, &suffix_sums, 0, bound, &mut HashMap::new()) {
+        Node::Constant(true) => {}
+        Node::Constant(false) => cnf.add_clause([]),
+        Node::Variable(root) => cnf.add_clause([root]),
+    }
+}
+
+/// Sums the weighted literals bit by bit with full and half adders,
+/// then forbids every binary sum lexicographically greater than `bound`
+fn adder(cnf: &mut Cnf, terms: &[(Literal, u64)], bound: u64) {
+    fn xor(cnf: &mut Cnf, inputs: &[Literal]) -> Literal {
+        let output = cnf.new_variable();
+        for mask in 0..1u32 << inputs.len() {
+            // forbid every assignment of the inputs paired with the wrong output
+            let parity = mask.count_ones() % 2 == 1;
+            let mut clause = inputs
+                .iter()
+                .enumerate()
+                .map(|(i, &lit)| if mask >> i & 1 == 1 { -lit } else { lit })
+                .collect::<Vec<_>>();
+            clause.push(if parity { output } else { -output });
+            cnf.add_clause(clause);
+        }
+        output
+    }
+
+    fn at_least_two(cnf: &mut Cnf, inputs: &[Literal]) -> Literal {
+        let output = cnf.new_variable();
+        for i in 0..inputs.len() {
+            for j in i + 1..inputs.len() {
+                cnf.add_clause([-inputs[i], -inputs[j], output]);
+                cnf.add_clause([inputs[i], inputs[j], -output]);
+            }
+        }
+        if inputs.len() == 2 {
+            cnf.add_clause([inputs[0], -output]);
+            cnf.add_clause([inputs[1], -output]);
+        }
+        output
+    }
+
+    let mut buckets: Vec<VecDeque<Literal>> = vec![];
+    for &(literal, weight) in terms {
+        for bit in 0..u64::BITS as usize {
+            if weight >> bit & 1 == 1 {
+                if buckets.len() <= bit {
+                    buckets.resize(bit + 1, VecDeque::new());
+                }
+                buckets[bit].push_back(literal);
+            }
+        }
+    }
+
+    let mut sum = vec![];
+    let mut bit = 0;
+    while bit < buckets.len() {
+        while buckets[bit].len() >= 2 {
+            let inputs = if buckets[bit].len() >= 3 {
+                buckets[bit].drain(..3).collect::<Vec<_>>()
+            } else {
+                buckets[bit].drain(..2).collect::<Vec<_>>()
+            };
+            let carry = at_least_two(cnf, &inputs);
+            let digit = xor(cnf, &inputs);
+            buckets[bit].push_back(digit);
+            if buckets.len() <= bit + 1 {
+                buckets.push(VecDeque::new());
+            }
+            buckets[bit + 1].push_back(carry);
+        }
+        sum.push(buckets[bit].pop_front());
+        bit += 1;
+    }
+
+    // sum > bound iff some bit is set in sum but not in bound and all higher bits are equal
+    let bound_bit = |j: usize| j < u64::BITS as usize && bound >> j & 1 == 1;
+    'bits: for (i, &digit) in sum.iter().enumerate() {
+        let Some(digit) = digit else { continue };
+        if bound_bit(i) {
+            continue;
+        }
+        let mut clause = vec![-digit];
+        for j in i + 1..sum.len().max(u64::BITS as usize) {
+            match (sum.get(j).copied().flatten(), bound_bit(j)) {
+                (None, true) => continue 'bits,
+                (None, false) => {}
+                (Some(higher), true) => clause.push(-higher),
+                (Some(higher), false) => clause.push(higher),
+            }
+        }
+        cnf.add_clause(clause);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{Solver, SolverResult, cnf::literal_value},
+    };
+
+    const CARDINALITY_ENCODINGS: [CardinalityEncoding; 4] = [
+        CardinalityEncoding::Pairwise,
+        CardinalityEncoding::SequentialCounter,
+        CardinalityEncoding::Totalizer,
+        CardinalityEncoding::SortingNetwork,
+    ];
+
+    /// Input assignments (as bitmasks over the first `n` variables) that extend to a model
+    fn projected_models(cnf: &Cnf, n: usize) -> Vec<u32> {
+        let mut solver = Solver::new(cnf);
+        (0..1u32 << n)
+            .filter(|mask| {
+                let assumptions = (1..=n as Literal)
+                    .map(|v| if mask >> (v - 1) & 1 == 1 { v } else { -v })
+                    .collect::<Vec<_>>();
+                matches!(solver.solve(&assumptions), SolverResult::Satisfiable(_))
+            })
+            .collect()
+    }
+
+    fn check_cardinality(
+        build: fn(&mut Cnf, &[Literal], usize, CardinalityEncoding),
+        holds: fn(u32, usize) -> bool,
+    ) {
+        for encoding in CARDINALITY_ENCODINGS {
+            for n in 1..=6 {
+                for k in 0..=n + 1 {
+                    let mut cnf = Cnf::new();
+                    cnf.num_variables = n;
+                    let literals = (1..=n as Literal).collect::<Vec<_>>();
+                    build(&mut cnf, &literals, k, encoding);
+                    let expected = (0..1u32 << n)
+                        .filter(|mask| holds(mask.count_ones(), k))
+                        .collect::<Vec<_>>();
+                    assert_eq!(
+                        projected_models(&cnf, n),
+                        expected,
+                        "{encoding:?} with n = {n} and k = {k}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_at_most_k() {
+        check_cardinality(at_most_k, |count, k| count as usize <= k);
+    }
+
+    #[test]
+    fn test_at_least_k() {
+        check_cardinality(at_least_k, |count, k| count as usize >= k);
+    }
+
+    #[test]
+    fn test_exactly_k() {
+        check_cardinality(exactly_k, |count, k| count as usize == k);
+    }
+
+    #[test]
+    fn test_exactly_one_truth_table() {
+        let mut cnf = Cnf::new();
+        exactly_one(&mut cnf, &[1, 2, 3, 4], CardinalityEncoding::Pairwise);
+        let (variables, _, outputs) = cnf.to_tree().unwrap().compute_truth_table();
+        assert_eq!(variables, vec!['A', 'B', 'C', 'D']);
+        let expected = (0..16u32)
+            .map(|row| row.count_ones() == 1)
+            .collect::<Vec<_>>();
+        assert_eq!(outputs, expected);
+    }
+
+    #[test]
+    fn test_negative_literals() {
+        for encoding in CARDINALITY_ENCODINGS {
+            let mut cnf = Cnf::new();
+            cnf.num_variables = 4;
+            at_most_k(&mut cnf, &[1, -2, 3, -4], 2, encoding);
+            let expected = (0..16u32)
+                .filter(|mask| (mask ^ 0b1010).count_ones() <= 2)
+                .collect::<Vec<_>>();
+            assert_eq!(projected_models(&cnf, 4), expected, "{encoding:?}");
+        }
+    }
+
+    #[test]
+    fn test_at_most_weighted() {
+        let terms = [(1, 3), (2, 2), (-3, 4), (4, 1), (5, 6)];
+        for encoding in [PseudoBooleanEncoding::Bdd, PseudoBooleanEncoding::Adder] {
+            for bound in 0..=17 {
+                let mut cnf = Cnf::new();
+                cnf.num_variables = 5;
+                at_most_weighted(&mut cnf, &terms, bound, encoding);
+                let expected = (0..32u32)
+                    .filter(|mask| {
+                        let model = (0..5).map(|i| mask >> i & 1 == 1).collect::<Vec<_>>();
+                        let sum: u64 = terms
+                            .iter()
+                            .filter(|&&(lit, _)| literal_value(&model, lit))
+                            .map(|(_, weight)| weight)
+                            .sum();
+                        sum <= bound
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    projected_models(&cnf, 5),
+                    expected,
+                    "{encoding:?} with bound {bound}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_at_most_weighted_large() {
+        let heavy = 10_000_000_000_000_000_000;
+        let terms = [(1, heavy), (2, heavy), (3, 3)];
+        for encoding in [PseudoBooleanEncoding::Bdd, PseudoBooleanEncoding::Adder] {
+            for bound in [5, heavy, heavy + 3, u64::MAX] {
+                let mut cnf = Cnf::new();
+                cnf.num_variables = 3;
+                at_most_weighted(&mut cnf, &terms, bound, encoding);
+                let expected = (0..8u32)
+                    .filter(|mask| {
+                        let sum: u128 = (0..3)
+                            .filter(|i| mask >> i & 1 == 1)
+                            .map(|i| terms[i].1 as u128)
+                            .sum();
+                        sum <= bound as u128
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    projected_models(&cnf, 3),
+                    expected,
+                    "{encoding:?} with bound {bound}"
+                );
+            }
+        }
+    }
+}
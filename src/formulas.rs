@@ -8,7 +8,7 @@ pub fn eval_formula(formula: &str) -> bool {
 }
 
 fn parse_formula(formula: &str) -> BooleanTree {
-    BooleanTree::new(&formula, true)
+    BooleanTree::new(formula, true)
         .unwrap_or_else(|err| panic!("failed to parse formula \"{formula}\": {err}"))
 }
 
@@ -68,6 +68,41 @@ mod tests {
         assert!(eval_formula("11="));
     }
 
+    #[test]
+    fn test_eval_formula_additional_connectives() {
+        assert!(eval_formula("00↑"));
+        assert!(eval_formula("01↑"));
+        assert!(eval_formula("10↑"));
+        assert!(!eval_formula("11↑"));
+
+        assert!(eval_formula("00↓"));
+        assert!(!eval_formula("01↓"));
+        assert!(!eval_formula("10↓"));
+        assert!(!eval_formula("11↓"));
+
+        assert!(eval_formula("00<"));
+        assert!(!eval_formula("01<"));
+        assert!(eval_formula("10<"));
+        assert!(eval_formula("11<"));
+
+        assert!(!eval_formula("00↛"));
+        assert!(!eval_formula("01↛"));
+        assert!(eval_formula("10↛"));
+        assert!(!eval_formula("11↛"));
+    }
+
+    #[test]
+    fn test_eval_formula_ite() {
+        assert!(!eval_formula("000?"));
+        assert!(eval_formula("001?"));
+        assert!(!eval_formula("010?"));
+        assert!(eval_formula("011?"));
+        assert!(!eval_formula("100?"));
+        assert!(!eval_formula("101?"));
+        assert!(eval_formula("110?"));
+        assert!(eval_formula("111?"));
+    }
+
     #[test]
     fn test_eval_formula_complex() {
         assert!(eval_formula("10|1&"));
@@ -115,6 +150,12 @@ mod tests {
         eval_formula("10||");
     }
 
+    #[test]
+    #[should_panic]
+    fn test_eval_formula_ite_missing_operand() {
+        eval_formula("11?");
+    }
+
     #[test]
     #[should_panic]
     fn test_eval_formula_not_enough_operations() {
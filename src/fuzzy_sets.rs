@@ -0,0 +1,220 @@
+//! Set formulas on fuzzy sets, where each element belongs to a set with a degree in [0, 1]
+
+use {
+    crate::sets::{compile_pointwise, eval_pointwise},
+    itertools::Itertools,
+    std::{collections::HashMap, hash::Hash},
+};
+
+/// Membership degree of each element, the missing ones having degree 0
+pub type FuzzySet<T> = HashMap<T, f64>;
+
+/// Conjunction of degrees, `&`, with its dual conorm for `|`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TNorm {
+    /// `min(a, b)`, Zadeh's operators
+    Minimum,
+    /// `a * b`
+    Product,
+    /// `max(a + b - 1, 0)`
+    Lukasiewicz,
+}
+
+impl TNorm {
+    pub fn norm(self, a: f64, b: f64) -> f64 {
+        match self {
+            TNorm::Minimum => a.min(b),
+            TNorm::Product => a * b,
+            TNorm::Lukasiewicz => (a + b - 1.0).max(0.0),
+        }
+    }
+
+    /// `1 - norm(1 - a, 1 - b)`
+    pub fn conorm(self, a: f64, b: f64) -> f64 {
+        match self {
+            TNorm::Minimum => a.max(b),
+            TNorm::Product => a + b - a * b,
+            TNorm::Lukasiewicz => (a + b).min(1.0),
+        }
+    }
+
+    /// `!` is `1 - a`, `>` is `!a | b`, `^` is `(a & !b) | (!a & b)` and `=` is
+    /// `(a > b) & (b > a)`: on degrees 0 and 1, the Boolean operators
+    fn apply(self, c: char, a: f64, b: f64) -> f64 {
+        let implication = |a: f64, b: f64| self.conorm(1.0 - a, b);
+        match c {
+            '&' => self.norm(a, b),
+            '|' => self.conorm(a, b),
+            '>' => implication(a, b),
+            '^' => self.conorm(self.norm(a, 1.0 - b), self.norm(1.0 - a, b)),
+            '=' => self.norm(implication(a, b), implication(b, a)),
+            _ => unreachable!("not a fuzzy set operator"),
+        }
+    }
+}
+
+/// The universe is the union of the supports of the sets, the result holds the
+/// elements of the universe with a positive degree
+pub fn eval_fuzzy_sets<T: Eq + Hash + Clone>(
+    formula: &str,
+    sets: &[FuzzySet<T>],
+    tnorm: TNorm,
+) -> Result<FuzzySet<T>, &'static str> {
+    let steps = compile_pointwise(formula, "|&^=>", sets.len())?;
+    if sets
+        .iter()
+        .flat_map(HashMap::values)
+        .any(|degree| !(0.0..=1.0).contains(degree))
+    {
+        return Err("membership degree out of [0, 1]");
+    }
+    let universe = sets
+        .iter()
+        .flatten()
+        .filter(|&(_, &degree)| degree > 0.0)
+        .map(|(x, _)| x)
+        .unique();
+    let degree = |x: &T, i: usize| sets[i].get(x).copied().unwrap_or(0.0);
+    Ok(universe
+        .filter_map(|x| {
+            let result = eval_pointwise(
+                &steps,
+                |i| degree(x, i),
+                |a| 1.0 - a,
+                |c, a, b| tnorm.apply(c, a, b),
+            );
+            (result > 0.0).then(|| (x.clone(), result))
+        })
+        .collect())
+}
+
+/// Elements of the result sorted, with their degree
+pub fn eval_fuzzy_set(formula: &str, sets: Vec<Vec<(i32, f64)>>, tnorm: TNorm) -> Vec<(i32, f64)> {
+    let sets = sets.into_iter().map(FuzzySet::from_iter).collect_vec();
+    eval_fuzzy_sets(formula, &sets, tnorm)
+        .unwrap_or_else(|error| panic!("{error}"))
+        .into_iter()
+        .sorted_unstable_by_key(|&(x, _)| x)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            eval_set,
+            sets::fixtures::{FORMULAS, region_sets},
+        },
+    };
+
+    const TNORMS: [TNorm; 3] = [TNorm::Minimum, TNorm::Product, TNorm::Lukasiewicz];
+
+    fn close(a: &[(i32, f64)], b: &[(i32, f64)]) -> bool {
+        a.len() == b.len()
+            && a.iter()
+                .zip(b)
+                .all(|((x, d), (y, e))| x == y && (d - e).abs() < 1e-9)
+    }
+
+    #[test]
+    fn test_eval_fuzzy_set() {
+        let sets = vec![vec![(1, 0.8), (2, 0.5)], vec![(1, 0.5), (3, 1.0)]];
+        let expected = [
+            ("AB&", TNorm::Minimum, vec![(1, 0.5)]),
+            ("AB&", TNorm::Product, vec![(1, 0.4)]),
+            ("AB&", TNorm::Lukasiewicz, vec![(1, 0.3)]),
+            ("AB|", TNorm::Minimum, vec![(1, 0.8), (2, 0.5), (3, 1.0)]),
+            ("AB|", TNorm::Product, vec![(1, 0.9), (2, 0.5), (3, 1.0)]),
+            (
+                "AB|",
+                TNorm::Lukasiewicz,
+                vec![(1, 1.0), (2, 0.5), (3, 1.0)],
+            ),
+            ("A!", TNorm::Product, vec![(1, 0.2), (2, 0.5), (3, 1.0)]),
+            (
+                "AB>",
+                TNorm::Lukasiewicz,
+                vec![(1, 0.7), (2, 0.5), (3, 1.0)],
+            ),
+            ("AB^", TNorm::Minimum, vec![(1, 0.5), (2, 0.5), (3, 1.0)]),
+            ("AB=", TNorm::Minimum, vec![(1, 0.5), (2, 0.5)]),
+        ];
+        for (formula, tnorm, result) in expected {
+            let computed = eval_fuzzy_set(formula, sets.clone(), tnorm);
+            assert!(
+                close(&computed, &result),
+                "{formula} {tnorm:?}: {computed:?}"
+            );
+        }
+
+        let scores = [
+            FuzzySet::from([("ada", 0.9)]),
+            FuzzySet::from([("ada", 1.5)]),
+        ];
+        assert_eq!(
+            eval_fuzzy_sets("AB&", &scores, TNorm::Minimum),
+            Err("membership degree out of [0, 1]")
+        );
+        assert_eq!(
+            eval_fuzzy_sets("A1&", &scores, TNorm::Minimum),
+            Err("invalid character")
+        );
+    }
+
+    #[test]
+    fn test_boundary_degrees() {
+        // degree 0 is the same as no degree: 1 is not in the universe
+        let sets = vec![vec![(1, 0.0), (2, 1.0)], vec![(1, 0.0), (2, 0.5)]];
+        for tnorm in TNORMS {
+            assert_eq!(eval_fuzzy_set("A!", sets.clone(), tnorm), []);
+            assert_eq!(eval_fuzzy_set("AA!|", sets.clone(), tnorm), [(2, 1.0)]);
+            assert_eq!(eval_fuzzy_set("AB&", sets.clone(), tnorm), [(2, 0.5)]);
+            assert_eq!(eval_fuzzy_set("AB>", sets.clone(), tnorm), [(2, 0.5)]);
+            assert_eq!(eval_fuzzy_set("BA>", sets.clone(), tnorm), [(2, 1.0)]);
+        }
+        for degree in [-0.1, 1.0 + 1e-9, f64::NAN] {
+            assert_eq!(
+                eval_fuzzy_sets("A", &[FuzzySet::from([(1, degree)])], TNorm::Minimum),
+                Err("membership degree out of [0, 1]")
+            );
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_sets_extend_sets() {
+        // degree 0 given outside of a set
+        let sets = region_sets();
+        let crisp = sets
+            .iter()
+            .map(|set| {
+                (1..8)
+                    .map(|x| (x, if set.contains(&x) { 1.0 } else { 0.0 }))
+                    .collect_vec()
+            })
+            .collect_vec();
+        for formula in FORMULAS {
+            let expected = eval_set(formula, sets.clone())
+                .into_iter()
+                .map(|x| (x, 1.0))
+                .collect_vec();
+            for tnorm in TNORMS {
+                let computed = eval_fuzzy_set(formula, crisp.clone(), tnorm);
+                assert!(close(&computed, &expected), "{formula} {tnorm:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_tnorms() {
+        let degrees = [0.0, 0.2, 0.5, 0.7, 1.0];
+        for tnorm in TNORMS {
+            for (a, b) in degrees.into_iter().cartesian_product(degrees) {
+                assert!((tnorm.norm(a, b) - tnorm.norm(b, a)).abs() < 1e-12);
+                assert!((tnorm.norm(a, 1.0) - a).abs() < 1e-12);
+                assert!(tnorm.norm(a, b) <= TNorm::Minimum.norm(a, b) + 1e-12);
+                assert!((tnorm.conorm(a, b) - (1.0 - tnorm.norm(1.0 - a, 1.0 - b))).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,764 @@
+//! Gray codes: sequences in which successive codes differ in a single digit.
+//! The reflected binary code is computed directly, codes with extra constraints
+//! (balanced, single-track, Beckett–Gray) are found by backtracking search,
+//! which is only practical for small widths.
+//! Subsets, combinations, permutations and compositions are enumerated in
+//! minimal-change orders built on the same idea.
+
+pub fn gray_code(n: u32) -> u32 {
+    n ^ n >> 1
+}
+
+/// Inverse of `gray_code`: bit `i` of `n` is the parity of the bits of `g` from `i` up
+pub fn gray_decode(g: u32) -> u32 {
+    let mut n = g;
+    for shift in [1, 2, 4, 8, 16] {
+        n ^= n >> shift;
+    }
+    n
+}
+
+/// Digits of the `n`-th code of the reflected Gray code in `base`, least significant first:
+/// the digits below a digit are traversed in reverse whenever that digit is odd
+pub fn nary_gray_code(n: u64, base: u32, width: usize) -> Vec<u32> {
+    assert!(base >= 2, "base must be at least 2");
+    let mut digits = vec![0; width];
+    let mut rest = n;
+    for digit in digits.iter_mut() {
+        *digit = (rest % base as u64) as u32;
+        rest /= base as u64;
+    }
+    assert_eq!(rest, 0, "{n} has more than {width} digits in base {base}");
+    let mut reversed = false;
+    for digit in digits.iter_mut().rev() {
+        if reversed {
+            *digit = base - 1 - *digit;
+        }
+        reversed ^= *digit % 2 == 1;
+    }
+    digits
+}
+
+/// Inverse of `nary_gray_code`
+pub fn nary_gray_decode(digits: &[u32], base: u32) -> u64 {
+    assert!(base >= 2, "base must be at least 2");
+    let mut reversed = false;
+    let mut n = 0;
+    for &digit in digits.iter().rev() {
+        assert!(digit < base, "digit {digit} is not in base {base}");
+        let value = if reversed { base - 1 - digit } else { digit };
+        reversed ^= digit % 2 == 1;
+        n = n * base as u64 + value as u64;
+    }
+    n
+}
+
+/// Iterator over the reflected binary code of `width` bits, with the bit flipped
+/// from the previous code, `None` for the first one
+#[derive(Clone, Debug)]
+pub struct GrayCodes {
+    width: u32,
+    next: u64,
+}
+
+impl GrayCodes {
+    pub fn new(width: u32) -> Self {
+        assert!(width <= 32, "codes are at most 32 bits wide");
+        Self { width, next: 0 }
+    }
+}
+
+impl Iterator for GrayCodes {
+    type Item = (u32, Option<u32>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >> self.width != 0 {
+            return None;
+        }
+        let n = self.next as u32;
+        self.next += 1;
+        let flipped = (n != 0).then(|| n.trailing_zeros());
+        Some((gray_code(n), flipped))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = ((1u64 << self.width) - self.next) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for GrayCodes {}
+
+/// Cyclic code of `width` bits in which every bit flips about as often as the others:
+/// the numbers of flips differ by at most 2
+pub fn balanced_gray_code(width: u32) -> Option<Vec<u32>> {
+    fn search(path: &mut Vec<u32>, visited: &mut [bool], counts: &mut [usize], low: usize) -> bool {
+        let length = visited.len();
+        let code = *path.last().unwrap();
+        let high = low + 2;
+        if path.len() == length {
+            // closing the cycle flips the only bit left
+            let closing = code.trailing_zeros() as usize;
+            return code.is_power_of_two()
+                && counts[closing] < high
+                && (0..counts.len()).all(|bit| counts[bit] + (bit == closing) as usize >= low);
+        }
+        let missing = counts
+            .iter()
+            .map(|&count| low.saturating_sub(count))
+            .sum::<usize>();
+        if missing > length - path.len() + 1 {
+            return false;
+        }
+        let mut bits = (0..counts.len()).collect::<Vec<_>>();
+        bits.sort_by_key(|&bit| counts[bit]);
+        for bit in bits {
+            let next = code ^ 1 << bit;
+            if visited[next as usize] || counts[bit] >= high {
+                continue;
+            }
+            visited[next as usize] = true;
+            counts[bit] += 1;
+            path.push(next);
+            if search(path, visited, counts, low) {
+                return true;
+            }
+            path.pop();
+            counts[bit] -= 1;
+            visited[next as usize] = false;
+        }
+        false
+    }
+
+    assert!((1..=16).contains(&width), "width must be between 1 and 16");
+    let length = 1usize << width;
+    // the counts are even, the lowest one is the even floor of the average
+    let low = (length / width as usize) & !1;
+    let mut visited = vec![false; length];
+    visited[0] = true;
+    let mut counts = vec![0; width as usize];
+    let mut path = vec![0];
+    search(&mut path, &mut visited, &mut counts, low).then_some(path)
+}
+
+/// Cyclic code of `period` codes of `width` bits in which the sequence of each bit
+/// is the one of bit 0 shifted by `period / width` codes, so that a single track
+/// read by equally spaced heads encodes the position
+pub fn single_track_gray_code(width: u32, period: usize) -> Option<Vec<u32>> {
+    fn search(path: &mut Vec<u32>, visited: &mut [bool], width: u32, period: usize) -> bool {
+        let shift = period / width as usize;
+        let t = path.len() - 1;
+        let code = path[t];
+        // bit k of each code is bit 0 of the code `k * shift` later
+        for k in 1..width {
+            let offset = k as usize * shift;
+            if t >= offset && code & 1 != path[t - offset] >> k & 1 {
+                return false;
+            }
+            if t + offset >= period && code >> k & 1 != path[t + offset - period] & 1 {
+                return false;
+            }
+        }
+        if path.len() == period {
+            return (code ^ path[0]).is_power_of_two();
+        }
+        for bit in 0..width {
+            let next = code ^ 1 << bit;
+            if visited[next as usize] {
+                continue;
+            }
+            visited[next as usize] = true;
+            path.push(next);
+            if search(path, visited, width, period) {
+                return true;
+            }
+            path.pop();
+            visited[next as usize] = false;
+        }
+        false
+    }
+
+    assert!((1..=16).contains(&width), "width must be between 1 and 16");
+    assert!(
+        period.is_multiple_of(width as usize) && period <= 1 << width,
+        "the period must be a multiple of the width, at most 2^width"
+    );
+    // codes invariant by rotation, like 0, cannot be on a cycle shorter than 2^width
+    (0..1 << width).find_map(|start| {
+        let mut visited = vec![false; 1 << width];
+        visited[start as usize] = true;
+        let mut path = vec![start];
+        search(&mut path, &mut visited, width, period).then_some(path)
+    })
+}
+
+/// Cyclic code through every code of `width` bits in which a bit reset to 0
+/// is always the one that has been set for the longest time
+pub fn beckett_gray_code(width: u32) -> Option<Vec<u32>> {
+    fn search(path: &mut Vec<u32>, visited: &mut [bool], queue: &mut Vec<u32>, width: u32) -> bool {
+        let code = *path.last().unwrap();
+        if path.len() == visited.len() {
+            return queue.len() == 1;
+        }
+        let mut moves = (0..width)
+            .filter(|bit| code >> bit & 1 == 0)
+            .collect::<Vec<_>>();
+        moves.extend(queue.first());
+        for bit in moves {
+            let next = code ^ 1 << bit;
+            if visited[next as usize] {
+                continue;
+            }
+            let set = next >> bit & 1 == 1;
+            if set {
+                queue.push(bit);
+            } else {
+                queue.remove(0);
+            }
+            visited[next as usize] = true;
+            path.push(next);
+            if search(path, visited, queue, width) {
+                return true;
+            }
+            path.pop();
+            visited[next as usize] = false;
+            if set {
+                queue.pop();
+            } else {
+                queue.insert(0, bit);
+            }
+        }
+        false
+    }
+
+    assert!((1..=16).contains(&width), "width must be between 1 and 16");
+    let mut visited = vec![false; 1 << width];
+    visited[0] = true;
+    let mut path = vec![0];
+    search(&mut path, &mut visited, &mut vec![], width).then_some(path)
+}
+
+// =====================================================
+// ============== COMBINATORIAL GENERATION =============
+// =====================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubsetChange {
+    Insert(usize),
+    Remove(usize),
+}
+
+/// Subsets of `0..n` in reflected Gray code order, as sorted elements,
+/// each one with the element inserted or removed from the previous one
+#[derive(Clone, Debug)]
+pub struct GraySubsets {
+    codes: GrayCodes,
+    members: Vec<bool>,
+}
+
+impl GraySubsets {
+    pub fn new(n: usize) -> Self {
+        Self {
+            codes: GrayCodes::new(n as u32),
+            members: vec![false; n],
+        }
+    }
+}
+
+impl Iterator for GraySubsets {
+    type Item = (Vec<usize>, Option<SubsetChange>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, flipped) = self.codes.next()?;
+        let change = flipped.map(|bit| {
+            let element = bit as usize;
+            self.members[element] ^= true;
+            if self.members[element] {
+                SubsetChange::Insert(element)
+            } else {
+                SubsetChange::Remove(element)
+            }
+        });
+        let subset = (0..self.members.len())
+            .filter(|&element| self.members[element])
+            .collect();
+        Some((subset, change))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.codes.size_hint()
+    }
+}
+
+impl ExactSizeIterator for GraySubsets {}
+
+/// `k`-combinations of `0..n` in revolving-door order (Knuth's Algorithm R), the order
+/// of the codes of weight `k` in the reflected Gray code. Each combination is sorted and
+/// comes with the `(removed, added)` pair of elements exchanged with the previous one.
+#[derive(Clone, Debug)]
+pub struct RevolvingDoor {
+    /// `c[1..=k]` in increasing order, `c[k + 1] = n` as a sentinel, `c[0]` unused
+    c: Vec<usize>,
+    k: usize,
+    started: bool,
+    done: bool,
+}
+
+impl RevolvingDoor {
+    pub fn new(n: usize, k: usize) -> Self {
+        let mut c = (0..=k).map(|j| j.saturating_sub(1)).collect::<Vec<_>>();
+        c.push(n);
+        Self {
+            c,
+            k,
+            started: false,
+            done: k > n,
+        }
+    }
+
+    /// next combination in place, `false` after the last one
+    fn advance(&mut self) -> bool {
+        let (c, k) = (&mut self.c, self.k);
+        if k == 0 {
+            return false;
+        }
+        // easy case: move c1 alone
+        if k % 2 == 1 && c[1] + 1 < c[2] {
+            c[1] += 1;
+            return true;
+        }
+        if k % 2 == 0 && c[1] > 0 {
+            c[1] -= 1;
+            return true;
+        }
+        let mut j = 2;
+        let mut decrease = k % 2 == 1;
+        while j <= k {
+            if decrease {
+                // here c[j] = c[j - 1] + 1
+                if c[j] >= j {
+                    c[j] = c[j - 1];
+                    c[j - 1] = j - 2;
+                    return true;
+                }
+            } else if c[j] + 1 < c[j + 1] {
+                // here c[j - 1] = j - 2
+                c[j - 1] = c[j];
+                c[j] += 1;
+                return true;
+            }
+            j += 1;
+            decrease = !decrease;
+        }
+        false
+    }
+}
+
+impl Iterator for RevolvingDoor {
+    type Item = (Vec<usize>, Option<(usize, usize)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let previous = self.c[1..=self.k].to_vec();
+        if !self.started {
+            self.started = true;
+            return Some((previous, None));
+        }
+        if !self.advance() {
+            self.done = true;
+            return None;
+        }
+        let current = self.c[1..=self.k].to_vec();
+        let removed = *previous.iter().find(|x| !current.contains(x)).unwrap();
+        let added = *current.iter().find(|x| !previous.contains(x)).unwrap();
+        Some((current, Some((removed, added))))
+    }
+}
+
+/// Permutations of `0..n` by adjacent transpositions (Steinhaus–Johnson–Trotter
+/// with Even's speedup), each one with the position `i` swapped with `i + 1`
+#[derive(Clone, Debug)]
+pub struct SteinhausJohnsonTrotter {
+    permutation: Vec<usize>,
+    /// direction in which each element moves, by element
+    left: Vec<bool>,
+    started: bool,
+}
+
+impl SteinhausJohnsonTrotter {
+    pub fn new(n: usize) -> Self {
+        Self {
+            permutation: (0..n).collect(),
+            left: vec![true; n],
+            started: false,
+        }
+    }
+}
+
+impl Iterator for SteinhausJohnsonTrotter {
+    type Item = (Vec<usize>, Option<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some((self.permutation.clone(), None));
+        }
+        let n = self.permutation.len();
+        // the largest element that can move in its direction
+        let mobile = (0..n)
+            .filter(|&i| {
+                let element = self.permutation[i];
+                let neighbour = if self.left[element] {
+                    i.checked_sub(1)
+                } else {
+                    Some(i + 1).filter(|&j| j < n)
+                };
+                neighbour.is_some_and(|j| self.permutation[j] < element)
+            })
+            .max_by_key(|&i| self.permutation[i])?;
+        let element = self.permutation[mobile];
+        let position = if self.left[element] {
+            mobile - 1
+        } else {
+            mobile
+        };
+        self.permutation.swap(position, position + 1);
+        for larger in element + 1..n {
+            self.left[larger] ^= true;
+        }
+        Some((self.permutation.clone(), Some(position)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompositionChange {
+    /// part `i` split into parts `i` and `i + 1`
+    Split(usize),
+    /// parts `i` and `i + 1` merged into part `i`
+    Merge(usize),
+}
+
+/// Compositions of `n` (ordered sums of positive parts) in Gray order:
+/// the cuts between the `n` units follow the reflected Gray code
+#[derive(Clone, Debug)]
+pub struct GrayCompositions {
+    n: usize,
+    cuts: GraySubsets,
+}
+
+impl GrayCompositions {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            cuts: GraySubsets::new(n.saturating_sub(1)),
+        }
+    }
+}
+
+impl Iterator for GrayCompositions {
+    type Item = (Vec<usize>, Option<CompositionChange>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (cuts, change) = self.cuts.next()?;
+        // cut `c` is after the first `c + 1` units
+        let mut parts = vec![];
+        let mut start = 0;
+        for end in cuts
+            .iter()
+            .map(|c| c + 1)
+            .chain([self.n])
+            .filter(|&end| end > 0)
+        {
+            parts.push(end - start);
+            start = end;
+        }
+        let part = |cut: usize| cuts.iter().filter(|&&c| c < cut).count();
+        let change = change.map(|change| match change {
+            SubsetChange::Insert(cut) => CompositionChange::Split(part(cut)),
+            SubsetChange::Remove(cut) => CompositionChange::Merge(part(cut)),
+        });
+        Some((parts, change))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// distinct codes, each one bit away from the next, the last one from the first
+    fn check_cycle(codes: &[u32], width: u32) {
+        let mut sorted = codes.to_vec();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), codes.len());
+        assert!(codes.iter().all(|&code| code >> width == 0));
+        for (i, &code) in codes.iter().enumerate() {
+            let next = codes[(i + 1) % codes.len()];
+            assert!((code ^ next).is_power_of_two(), "{code:b} -> {next:b}");
+        }
+    }
+
+    #[test]
+    fn test_gray_code() {
+        assert_eq!(gray_code(0), 0);
+        assert_eq!(gray_code(1), 1);
+        assert_eq!(gray_code(2), 3);
+        assert_eq!(gray_code(3), 2);
+        assert_eq!(gray_code(4), 6);
+        assert_eq!(gray_code(5), 7);
+        assert_eq!(gray_code(6), 5);
+        assert_eq!(gray_code(7), 4);
+        assert_eq!(gray_code(8), 12);
+        assert_eq!(gray_code(9), 13);
+        assert_eq!(gray_code(10), 15);
+        assert_eq!(gray_code(11), 14);
+        assert_eq!(gray_code(12), 10);
+        assert_eq!(gray_code(13), 11);
+        assert_eq!(gray_code(14), 9);
+        assert_eq!(gray_code(15), 8);
+        assert_eq!(gray_code(16), 24);
+        assert_eq!(gray_code(17), 25);
+        assert_eq!(gray_code(18), 27);
+        assert_eq!(gray_code(19), 26);
+        assert_eq!(gray_code(20), 30);
+    }
+
+    #[test]
+    fn test_gray_decode() {
+        for width in 1..=16 {
+            let codes = (0..1 << width).map(gray_code).collect::<Vec<_>>();
+            check_cycle(&codes, width);
+            for (n, &code) in codes.iter().enumerate() {
+                assert_eq!(gray_decode(code), n as u32);
+            }
+        }
+        for n in [u32::MAX, u32::MAX >> 1, 1 << 31, 0xdead_beef] {
+            assert_eq!(gray_decode(gray_code(n)), n);
+        }
+    }
+
+    #[test]
+    fn test_nary_gray_code() {
+        assert_eq!(nary_gray_code(3, 3, 2), [2, 1]);
+        assert_eq!(nary_gray_code(5, 3, 2), [0, 1]);
+        for base in 2..=5u32 {
+            for width in 1..=4 {
+                let count = (base as u64).pow(width as u32);
+                let codes = (0..count)
+                    .map(|n| nary_gray_code(n, base, width))
+                    .collect::<Vec<_>>();
+                for (n, code) in codes.iter().enumerate() {
+                    assert_eq!(nary_gray_decode(code, base), n as u64);
+                    if base == 2 {
+                        let bits = code.iter().rev().fold(0, |bits, &bit| bits << 1 | bit);
+                        assert_eq!(bits, gray_code(n as u32));
+                    }
+                }
+                for pair in codes.windows(2) {
+                    let changes = pair[0]
+                        .iter()
+                        .zip(&pair[1])
+                        .filter(|(x, y)| x != y)
+                        .collect::<Vec<_>>();
+                    assert_eq!(changes.len(), 1);
+                    assert_eq!(changes[0].0.abs_diff(*changes[0].1), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_gray_codes() {
+        for width in 0..=12 {
+            let codes = GrayCodes::new(width).collect::<Vec<_>>();
+            assert_eq!(codes.len(), 1 << width);
+            assert_eq!(codes[0], (0, None));
+            for pair in codes.windows(2) {
+                let [(previous, _), (code, Some(bit))] = pair else {
+                    panic!("only the first code has no flipped bit");
+                };
+                assert_eq!(previous ^ code, 1 << bit);
+            }
+        }
+        assert_eq!(GrayCodes::new(32).len(), 1 << 32);
+    }
+
+    #[test]
+    fn test_balanced_gray_code() {
+        for width in 1..=5 {
+            let codes = balanced_gray_code(width).unwrap();
+            assert_eq!(codes.len(), 1 << width);
+            check_cycle(&codes, width);
+            let mut counts = vec![0; width as usize];
+            for (i, &code) in codes.iter().enumerate() {
+                let flipped = code ^ codes[(i + 1) % codes.len()];
+                counts[flipped.trailing_zeros() as usize] += 1;
+            }
+            assert!(counts.iter().max().unwrap() - counts.iter().min().unwrap() <= 2);
+        }
+    }
+
+    #[test]
+    fn test_single_track_gray_code() {
+        for (width, period) in [(3, 6), (5, 30)] {
+            let codes = single_track_gray_code(width, period).unwrap();
+            assert_eq!(codes.len(), period);
+            check_cycle(&codes, width);
+            let shift = period / width as usize;
+            for (t, &code) in codes.iter().enumerate() {
+                for k in 0..width {
+                    assert_eq!(code >> k & 1, codes[(t + k as usize * shift) % period] & 1);
+                }
+            }
+        }
+        // no single-track code goes through every code of more than 1 bit
+        assert_eq!(single_track_gray_code(2, 4), None);
+        assert_eq!(single_track_gray_code(4, 16), None);
+    }
+
+    #[test]
+    fn test_beckett_gray_code() {
+        for width in [1, 2, 5] {
+            let codes = beckett_gray_code(width).unwrap();
+            assert_eq!(codes.len(), 1 << width);
+            check_cycle(&codes, width);
+            let mut queue = std::collections::VecDeque::new();
+            for (i, &code) in codes.iter().enumerate() {
+                let next = codes[(i + 1) % codes.len()];
+                let bit = (code ^ next).trailing_zeros();
+                if next >> bit & 1 == 1 {
+                    queue.push_back(bit);
+                } else {
+                    assert_eq!(queue.pop_front(), Some(bit));
+                }
+            }
+        }
+        assert_eq!(beckett_gray_code(3), None);
+        assert_eq!(beckett_gray_code(4), None);
+    }
+
+    #[test]
+    fn test_gray_subsets() {
+        for n in 0..=10 {
+            let subsets = GraySubsets::new(n).collect::<Vec<_>>();
+            assert_eq!(subsets.len(), 1 << n);
+            assert_eq!(subsets[0], (vec![], None));
+            let masks = subsets
+                .iter()
+                .map(|(subset, _)| subset.iter().fold(0, |mask, x| mask | 1 << x))
+                .collect::<Vec<u32>>();
+            assert_eq!(masks, (0..1 << n).map(gray_code).collect::<Vec<_>>());
+            for pair in subsets.windows(2) {
+                let [(previous, _), (subset, Some(change))] = pair else {
+                    panic!("missing change");
+                };
+                let mut expected = previous.clone();
+                match *change {
+                    SubsetChange::Insert(x) => expected.push(x),
+                    SubsetChange::Remove(x) => expected.retain(|&y| y != x),
+                }
+                expected.sort();
+                assert_eq!(&expected, subset);
+            }
+        }
+    }
+
+    #[test]
+    fn test_revolving_door() {
+        for n in 0..=9 {
+            for k in 0..=n + 1 {
+                let combinations = RevolvingDoor::new(n, k).collect::<Vec<_>>();
+                // the codes of weight k in the reflected Gray code
+                let expected = GraySubsets::new(n)
+                    .map(|(subset, _)| subset)
+                    .filter(|subset| subset.len() == k)
+                    .collect::<Vec<_>>();
+                assert_eq!(
+                    combinations
+                        .iter()
+                        .map(|(combination, _)| combination.clone())
+                        .collect::<Vec<_>>(),
+                    expected,
+                    "{n} choose {k}"
+                );
+                for pair in combinations.windows(2) {
+                    let [(previous, _), (combination, Some((removed, added)))] = pair else {
+                        panic!("missing change");
+                    };
+                    let mut expected = previous.clone();
+                    expected.retain(|x| x != removed);
+                    expected.push(*added);
+                    expected.sort();
+                    assert_eq!(&expected, combination);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_steinhaus_johnson_trotter() {
+        assert_eq!(
+            SteinhausJohnsonTrotter::new(3)
+                .map(|(permutation, _)| permutation)
+                .collect::<Vec<_>>(),
+            [
+                [0, 1, 2],
+                [0, 2, 1],
+                [2, 0, 1],
+                [2, 1, 0],
+                [1, 2, 0],
+                [1, 0, 2]
+            ]
+        );
+        for n in 0..=7 {
+            let permutations = SteinhausJohnsonTrotter::new(n).collect::<Vec<_>>();
+            assert_eq!(permutations.len(), (1..=n).product::<usize>());
+            let mut sorted = permutations.clone();
+            sorted.sort();
+            sorted.dedup();
+            assert_eq!(sorted.len(), permutations.len());
+            for pair in permutations.windows(2) {
+                let [(previous, _), (permutation, Some(i))] = pair else {
+                    panic!("missing change");
+                };
+                let mut expected = previous.clone();
+                expected.swap(*i, i + 1);
+                assert_eq!(&expected, permutation);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gray_compositions() {
+        assert_eq!(
+            GrayCompositions::new(0).collect::<Vec<_>>(),
+            [(vec![], None)]
+        );
+        assert_eq!(
+            GrayCompositions::new(3)
+                .map(|(parts, _)| parts)
+                .collect::<Vec<_>>(),
+            [vec![3], vec![1, 2], vec![1, 1, 1], vec![2, 1]]
+        );
+        for n in 1..=10 {
+            let compositions = GrayCompositions::new(n).collect::<Vec<_>>();
+            assert_eq!(compositions.len(), 1 << (n - 1));
+            for pair in compositions.windows(2) {
+                let [(previous, _), (parts, Some(change))] = pair else {
+                    panic!("missing change");
+                };
+                assert_eq!(parts.iter().sum::<usize>(), n);
+                // merging parts i and i + 1 of the finer composition gives the other one
+                let (coarse, fine, i) = match *change {
+                    CompositionChange::Split(i) => (previous, parts, i),
+                    CompositionChange::Merge(i) => (parts, previous, i),
+                };
+                let mut merged = fine.clone();
+                merged[i] += merged.remove(i + 1);
+                assert_eq!(&merged, coarse);
+            }
+        }
+    }
+}
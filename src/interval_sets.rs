@@ -0,0 +1,275 @@
+//! Sets of integers or reals stored as unions of half-open intervals `[start, end)`,
+//! for ranges far too large to list element by element
+
+use {
+    crate::sets::{compile_pointwise, eval_pointwise},
+    itertools::Itertools,
+    std::cmp::Ordering,
+};
+
+/// Union of sorted, disjoint, nonempty intervals `[start, end)`, none of them ending
+/// where the next one starts. On integers, `[a, b)` holds `a` to `b - 1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IntervalSet<T> {
+    intervals: Vec<(T, T)>,
+}
+
+fn compare<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b).expect("incomparable interval bounds")
+}
+
+impl<T: PartialOrd + Copy> IntervalSet<T> {
+    /// Normalizes the intervals: empty ones are dropped, overlapping or touching ones merged
+    pub fn new(intervals: impl IntoIterator<Item = (T, T)>) -> Self {
+        let mut sorted = intervals
+            .into_iter()
+            .filter(|(start, end)| compare(start, end) == Ordering::Less)
+            .collect_vec();
+        sorted.sort_by(|a, b| compare(&a.0, &b.0));
+        let mut merged: Vec<(T, T)> = vec![];
+        for (start, end) in sorted {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => {
+                    if end > last.1 {
+                        last.1 = end;
+                    }
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+        Self { intervals: merged }
+    }
+
+    pub fn empty() -> Self {
+        Self { intervals: vec![] }
+    }
+
+    pub fn intervals(&self) -> &[(T, T)] {
+        &self.intervals
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        let after = self.intervals.partition_point(|&(start, _)| start <= x);
+        after > 0 && x < self.intervals[after - 1].1
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    /// Sweeps the bounds of both sets: between two consecutive bounds, the points are in
+    /// the result when `keep` holds for their membership in `self` and in `other`
+    fn combine(&self, other: &Self, keep: impl Fn(bool, bool) -> bool) -> Self {
+        assert!(!keep(false, false), "the result would be unbounded");
+        let mut bounds = [self, other]
+            .into_iter()
+            .enumerate()
+            .flat_map(|(which, set)| {
+                set.intervals
+                    .iter()
+                    .flat_map(move |&(start, end)| [(start, which), (end, which)])
+            })
+            .collect_vec();
+        bounds.sort_by(|a, b| compare(&a.0, &b.0));
+
+        let mut inside = [false, false];
+        let mut intervals = vec![];
+        let mut start = None;
+        let mut i = 0;
+        while i < bounds.len() {
+            let point = bounds[i].0;
+            // a normalized set has each bound once, so this toggles each set at most once
+            while i < bounds.len() && bounds[i].0 == point {
+                inside[bounds[i].1] ^= true;
+                i += 1;
+            }
+            match (start, keep(inside[0], inside[1])) {
+                (None, true) => start = Some(point),
+                (Some(first), false) => {
+                    intervals.push((first, point));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        Self { intervals }
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a || b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a && b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a && !b)
+    }
+
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a != b)
+    }
+
+    /// Points of `[universe.0, universe.1)` out of the set
+    pub fn complement(&self, universe: (T, T)) -> Self {
+        IntervalSet::new([universe]).difference(self)
+    }
+}
+
+/// Evaluates a set formula in reverse Polish notation, as `eval_set_in_universe` does,
+/// with complements, `=` and `>` taken in `[universe.0, universe.1)`
+pub fn eval_interval_sets<T: PartialOrd + Copy>(
+    formula: &str,
+    sets: &[IntervalSet<T>],
+    universe: (T, T),
+) -> Result<IntervalSet<T>, &'static str> {
+    let steps = compile_pointwise(formula, "|&^=>", sets.len())?;
+    let whole = IntervalSet::new([universe]);
+    if !sets.iter().all(|set| set.is_subset(&whole)) {
+        return Err("set is not a subset of the universe");
+    }
+    Ok(eval_pointwise(
+        &steps,
+        |i| sets[i].clone(),
+        |a| whole.difference(&a),
+        |c, a, b| match c {
+            '|' => a.union(&b),
+            '&' => a.intersection(&b),
+            '^' => a.symmetric_difference(&b),
+            '=' => whole.difference(&a.symmetric_difference(&b)),
+            _ => whole.difference(&a).union(&b),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{eval_set_with_universe, sets::fixtures::FORMULAS},
+    };
+
+    fn elements(set: &IntervalSet<i32>) -> Vec<i32> {
+        set.intervals()
+            .iter()
+            .flat_map(|&(start, end)| start..end)
+            .collect()
+    }
+
+    #[test]
+    fn test_interval_set() {
+        let set = IntervalSet::new([(5, 8), (1, 3), (3, 4), (10, 10), (7, 9), (12, 11)]);
+        assert_eq!(set.intervals(), [(1, 4), (5, 9)]);
+        assert!(set.contains(1) && set.contains(3) && set.contains(8));
+        assert!(!set.contains(0) && !set.contains(4) && !set.contains(9));
+        let other = IntervalSet::new([(0, 2), (6, 12)]);
+        assert_eq!(set.union(&other).intervals(), [(0, 4), (5, 12)]);
+        assert_eq!(set.intersection(&other).intervals(), [(1, 2), (6, 9)]);
+        assert_eq!(
+            set.symmetric_difference(&other).intervals(),
+            [(0, 1), (2, 4), (5, 6), (9, 12)]
+        );
+        assert_eq!(
+            set.complement((0, 10)).intervals(),
+            [(0, 1), (4, 5), (9, 10)]
+        );
+        assert!(IntervalSet::empty().complement((3, 3)).is_empty());
+
+        // time windows in hours
+        let meetings = IntervalSet::new([(9.0, 10.5), (10.5, 11.0), (14.25, 15.0)]);
+        let lunch = IntervalSet::new([(12.0, 13.5)]);
+        let busy = eval_interval_sets("AB|", &[meetings, lunch], (8.0, 18.0)).unwrap();
+        assert_eq!(busy.intervals(), [(9.0, 11.0), (12.0, 13.5), (14.25, 15.0)]);
+        assert_eq!(
+            eval_interval_sets("AB|!", &[busy.clone(), IntervalSet::empty()], (8.0, 18.0))
+                .unwrap()
+                .intervals(),
+            [(8.0, 9.0), (11.0, 12.0), (13.5, 14.25), (15.0, 18.0)]
+        );
+        assert_eq!(
+            eval_interval_sets("A!", &[busy], (10.0, 18.0)),
+            Err("set is not a subset of the universe")
+        );
+
+        // IPv4 ranges
+        let ip = |a: u64, b: u64, c: u64, d: u64| a << 24 | b << 16 | c << 8 | d;
+        let private = IntervalSet::new([(ip(10, 0, 0, 0), ip(11, 0, 0, 0))]);
+        let blocked = IntervalSet::new([(ip(10, 1, 0, 0), ip(10, 2, 0, 0))]);
+        let allowed = eval_interval_sets("AB!&", &[private, blocked], (0, 1 << 32)).unwrap();
+        assert_eq!(
+            allowed.intervals(),
+            [
+                (ip(10, 0, 0, 0), ip(10, 1, 0, 0)),
+                (ip(10, 2, 0, 0), ip(11, 0, 0, 0))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_touching_intervals() {
+        // degenerate and reversed intervals are empty, touching ones merge
+        assert!(IntervalSet::new([(3, 3), (5, 2)]).is_empty());
+        assert_eq!(IntervalSet::new([(3, 5), (0, 3)]).intervals(), [(0, 5)]);
+        assert_eq!(IntervalSet::new([(0, 3), (3, 3)]).intervals(), [(0, 3)]);
+
+        // sets meeting at a single bound
+        let sets = [IntervalSet::new([(0, 3)]), IntervalSet::new([(3, 5)])];
+        let eval = |formula| {
+            eval_interval_sets(formula, &sets, (0, 5))
+                .unwrap()
+                .intervals()
+                .to_vec()
+        };
+        assert_eq!(eval("AB|"), [(0, 5)]);
+        assert_eq!(eval("AB&"), []);
+        assert_eq!(eval("AB^"), [(0, 5)]);
+        assert_eq!(eval("AB="), []);
+        assert_eq!(eval("AB>"), [(3, 5)]);
+        assert_eq!(eval("A!"), [(3, 5)]);
+
+        // sets sharing both bounds, with the universe as bounds
+        let sets = [IntervalSet::new([(2, 6)]), IntervalSet::new([(2, 6)])];
+        let eval = |formula| {
+            eval_interval_sets(formula, &sets, (2, 6))
+                .unwrap()
+                .intervals()
+                .to_vec()
+        };
+        assert_eq!(eval("A!"), []);
+        assert_eq!(eval("AB^"), []);
+        assert_eq!(eval("AB="), [(2, 6)]);
+        assert_eq!(eval("AA!|"), [(2, 6)]);
+        assert_eq!(
+            eval_interval_sets("A", &sets, (2, 5)),
+            Err("set is not a subset of the universe")
+        );
+        assert_eq!(
+            eval_interval_sets("A!", &[IntervalSet::empty()], (4, 4)),
+            Ok(IntervalSet::empty())
+        );
+    }
+
+    #[test]
+    fn test_interval_sets_match_eval_set() {
+        // bounds shared between sets, intervals touching the universe bounds
+        let sets = [
+            IntervalSet::new([(0, 4), (6, 9)]),
+            IntervalSet::new([(4, 6), (9, 12)]),
+            IntervalSet::new([(2, 6), (11, 12)]),
+        ];
+        let enumerated = sets.iter().map(elements).collect_vec();
+        for formula in FORMULAS {
+            let result = eval_interval_sets(formula, &sets, (0, 12)).unwrap();
+            assert_eq!(
+                Ok(elements(&result)),
+                eval_set_with_universe(formula, enumerated.clone(), 0..12),
+                "{formula}"
+            );
+        }
+    }
+}
@@ -1,15 +1,62 @@
+mod adders;
+mod aig;
+mod bitsets;
 mod boolean_tree;
+mod cnf;
+mod encodings;
 mod formulas;
+mod fuzzy_sets;
+mod gray_codes;
+mod interval_sets;
+mod maxsat;
+mod multipliers;
+mod multisets;
+mod netlist;
 mod numbers;
+mod qbf;
+mod relations;
 mod sets;
+mod solver;
 mod space_filling_curves;
 mod truth_table;
 
 pub use {
+    adders::{AdderArchitecture, AdderReport, adder_circuit},
+    aig::{Aig, AigLiteral},
+    bitsets::{BitSet, DenseSet, RoaringSet},
     boolean_tree::BooleanTree,
+    cnf::{Clause, Cnf, Literal},
+    encodings::{
+        CardinalityEncoding, PseudoBooleanEncoding, at_least_k, at_most_k, at_most_weighted,
+        exactly_k, exactly_one,
+    },
     formulas::{conjunctive_normal_form, eval_formula, negation_normal_form, sat},
-    numbers::{adder, gray_code, multiplier},
-    sets::{eval_set, powerset},
+    fuzzy_sets::{FuzzySet, TNorm, eval_fuzzy_set, eval_fuzzy_sets},
+    gray_codes::{
+        CompositionChange, GrayCodes, GrayCompositions, GraySubsets, RevolvingDoor,
+        SteinhausJohnsonTrotter, SubsetChange, balanced_gray_code, beckett_gray_code, gray_code,
+        gray_decode, nary_gray_code, nary_gray_decode, single_track_gray_code,
+    },
+    interval_sets::{IntervalSet, eval_interval_sets},
+    maxsat::{MaxSatSolution, WeightedCnf},
+    multipliers::{MultiplierReport, PartialProducts, Reduction, multiplier_circuit},
+    multisets::{Multiset, eval_multiset, eval_multisets},
+    netlist::{Driver, Equivalence, Gate, Netlist, Wire},
+    numbers::{
+        Flagged, Word, adder, adder_with_flags, eq, lt, multiplier, multiplier_with_flags, negator,
+        non_restoring_divider, restoring_divider, signed_divider, signed_lt, subtractor,
+        subtractor_with_flags,
+    },
+    qbf::{Qbf, Quantifier},
+    relations::Relation,
+    sets::{
+        DenseBackend, KSubsets, Powerset, PowersetOrder, PrunedPowerset, SetFormula, SetRelation,
+        VennCounts, eval_btree_sets, eval_hash_sets, eval_set, eval_set_dense, eval_set_generic,
+        eval_set_in_universe, eval_set_with_universe, powerset, powerset_generic,
+        powerset_in_order, powerset_of_set, set_relation_counterexample, subset_rank,
+        subset_unrank,
+    },
+    solver::{Solver, SolverResult},
     space_filling_curves::{map, reverse_map},
     truth_table::print_truth_table,
 };
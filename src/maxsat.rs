@@ -0,0 +1,460 @@
+//! Weighted partial MaxSAT: every hard clause must hold
+//! and the total weight of the falsified soft clauses is minimized
+
+use {
+    crate::{
+        Cnf, PseudoBooleanEncoding, Solver, SolverResult,
+        cnf::{Clause, Literal, literal_value, variable},
+        encodings::at_most_weighted,
+    },
+    std::collections::HashSet,
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WeightedCnf {
+    pub num_variables: usize,
+    pub hard: Vec<Clause>,
+    pub soft: Vec<(Clause, u64)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaxSatSolution {
+    /// `model[v - 1]` is the value of variable `v`
+    pub model: Vec<bool>,
+    pub cost: u64,
+}
+
+impl WeightedCnf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update_num_variables(&mut self, clause: &Clause) {
+        assert!(!clause.contains(&0), "0 is not a valid literal");
+        if let Some(max) = clause.iter().map(|&lit| variable(lit)).max() {
+            self.num_variables = self.num_variables.max(max);
+        }
+    }
+
+    pub fn add_hard(&mut self, clause: impl IntoIterator<Item = Literal>) {
+        let clause = clause.into_iter().collect();
+        self.update_num_variables(&clause);
+        self.hard.push(clause);
+    }
+
+    pub fn add_soft(&mut self, clause: impl IntoIterator<Item = Literal>, weight: u64) {
+        assert!(weight > 0, "soft clauses must have a positive weight");
+        let clause = clause.into_iter().collect();
+        self.update_num_variables(&clause);
+        self.soft.push((clause, weight));
+    }
+
+    /// Total weight of the soft clauses falsified by `model`, at most `u64::MAX`;
+    /// hard clauses are not checked
+    pub fn cost(&self, model: &[bool]) -> u64 {
+        self.soft
+            .iter()
+            .filter(|(clause, _)| !clause.iter().any(|&lit| literal_value(model, lit)))
+            .fold(0, |cost, (_, weight)| cost.saturating_add(*weight))
+    }
+
+    fn hard_solver(&self) -> Option<Solver> {
+        let mut solver = Solver::new(&Cnf {
+            num_variables: self.num_variables,
+            clauses: self.hard.clone(),
+        });
+        match solver.solve(&[]) {
+            SolverResult::Satisfiable(_) => Some(solver),
+            SolverResult::Unsatisfiable(_) => None,
+        }
+    }
+
+    fn solution(&self, model: &[bool]) -> MaxSatSolution {
+        let model = model[..self.num_variables].to_vec();
+        MaxSatSolution {
+            cost: self.cost(&model),
+            model,
+        }
+    }
+
+    /// Core-guided search (WPM1, the weighted version of Fu-Malik).
+    /// Each unsatisfiable core is relaxed with fresh variables constrained to exactly one,
+    /// soft clauses heavier than the lightest clause of the core are split in two.
+    /// Returns `None` if the hard clauses are unsatisfiable.
+    pub fn fu_malik(&self) -> Option<MaxSatSolution> {
+        struct Soft {
+            clause: Clause,
+            weight: u64,
+            selector: Literal,
+        }
+
+        fn add_soft(solver: &mut Solver, softs: &mut Vec<Soft>, clause: Clause, weight: u64) {
+            let selector = solver.new_variable();
+            let mut selected = clause.clone();
+            selected.push(selector);
+            solver.add_clause(&selected);
+            softs.push(Soft {
+                clause,
+                weight,
+                selector,
+            });
+        }
+
+        let mut solver = self.hard_solver()?;
+        let mut softs = vec![];
+        for (clause, weight) in &self.soft {
+            add_soft(&mut solver, &mut softs, clause.clone(), *weight);
+        }
+
+        loop {
+            let assumptions = softs.iter().map(|soft| -soft.selector).collect::<Vec<_>>();
+            let core = match solver.solve(&assumptions) {
+                SolverResult::Satisfiable(model) => return Some(self.solution(&model)),
+                SolverResult::Unsatisfiable(core) => core.into_iter().collect::<HashSet<_>>(),
+            };
+            let in_core = (0..softs.len())
+                .filter(|&i| core.contains(&-softs[i].selector))
+                .collect::<Vec<_>>();
+            let min_weight = in_core.iter().map(|&i| softs[i].weight).min().unwrap();
+
+            let mut relaxations = vec![];
+            for i in in_core {
+                // retire the current version of the clause
+                solver.add_clause(&[softs[i].selector]);
+                let mut clause = softs[i].clause.clone();
+                if softs[i].weight > min_weight {
+                    let remaining = softs[i].weight - min_weight;
+                    add_soft(&mut solver, &mut softs, clause.clone(), remaining);
+                }
+                let relaxation = solver.new_variable();
+                relaxations.push(relaxation);
+                clause.push(relaxation);
+                softs[i].weight = min_weight;
+                softs[i].selector = solver.new_variable();
+                let mut selected = clause.clone();
+                selected.push(softs[i].selector);
+                solver.add_clause(&selected);
+                softs[i].clause = clause;
+            }
+            solver.add_clause(&relaxations);
+            for (i, &r1) in relaxations.iter().enumerate() {
+                for &r2 in &relaxations[i + 1..] {
+                    solver.add_clause(&[-r1, -r2]);
+                }
+            }
+        }
+    }
+
+    /// SAT-UNSAT search: every model found forces the next one to be strictly cheaper,
+    /// the last model found before the formula becomes unsatisfiable is optimal.
+    /// Returns `None` if the hard clauses are unsatisfiable.
+    pub fn linear_search(&self) -> Option<MaxSatSolution> {
+        let mut solver = self.hard_solver()?;
+        let mut terms = vec![];
+        for (clause, weight) in &self.soft {
+            let blocking = solver.new_variable();
+            let mut relaxed = clause.clone();
+            relaxed.push(blocking);
+            solver.add_clause(&relaxed);
+            terms.push((blocking, *weight));
+        }
+
+        let mut best = None;
+        while let SolverResult::Satisfiable(model) = solver.solve(&[]) {
+            let solution = self.solution(&model);
+            if solution.cost == 0 {
+                return Some(solution);
+            }
+            let mut bound = Cnf {
+                num_variables: solver.num_variables(),
+                clauses: vec![],
+            };
+            at_most_weighted(
+                &mut bound,
+                &terms,
+                solution.cost - 1,
+                PseudoBooleanEncoding::Bdd,
+            );
+            solver.reserve_variables(bound.num_variables);
+            for clause in &bound.clauses {
+                solver.add_clause(clause);
+            }
+            best = Some(solution);
+        }
+        best
+    }
+
+    /// Reads both the classic format (`p wcnf vars clauses top`, hard clauses weighted `top`)
+    /// and the 2022 format (`h` for hard clauses, no problem line).
+    /// The total soft weight must fit in a `u64`.
+    pub fn from_wcnf(wcnf: &str) -> Result<Self, &'static str> {
+        let mut weighted_cnf = WeightedCnf::new();
+        let mut total_weight = 0u64;
+        let mut top = None;
+        let mut header = None;
+        for line in wcnf.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+            if let Some(problem) = line.strip_prefix('p') {
+                if header.is_some()
+                    || !weighted_cnf.hard.is_empty()
+                    || !weighted_cnf.soft.is_empty()
+                {
+                    return Err("misplaced problem line");
+                }
+                let fields = problem.split_whitespace().collect::<Vec<_>>();
+                let (variables, clauses) = match fields[..] {
+                    ["wcnf", variables, clauses] => (variables, clauses),
+                    ["wcnf", variables, clauses, t] => {
+                        top = Some(t.parse::<u64>().map_err(|_| "invalid top weight")?);
+                        (variables, clauses)
+                    }
+                    _ => return Err("invalid problem line"),
+                };
+                weighted_cnf.num_variables =
+                    variables.parse().map_err(|_| "invalid variable count")?;
+                header = Some(
+                    clauses
+                        .parse::<usize>()
+                        .map_err(|_| "invalid clause count")?,
+                );
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+            let weight = match tokens.next().unwrap() {
+                "h" if header.is_none() => None,
+                "h" => return Err("hard clause marker in classic format"),
+                token => match token.parse::<u64>() {
+                    Ok(0) | Err(_) => return Err("invalid weight"),
+                    Ok(weight) if top.is_some_and(|top| weight >= top) => None,
+                    Ok(weight) => Some(weight),
+                },
+            };
+            let mut clause = vec![];
+            let mut terminated = false;
+            for token in tokens {
+                if terminated {
+                    return Err("literal after end of clause");
+                }
+                match token.parse::<Literal>().map_err(|_| "invalid literal")? {
+                    0 => terminated = true,
+                    literal => clause.push(literal),
+                }
+            }
+            if !terminated {
+                return Err("unterminated clause");
+            }
+            match weight {
+                None => weighted_cnf.add_hard(clause),
+                Some(weight) => {
+                    total_weight = total_weight
+                        .checked_add(weight)
+                        .ok_or("total soft weight too large")?;
+                    weighted_cnf.add_soft(clause, weight)
+                }
+            }
+        }
+
+        match header {
+            Some(n) if n != weighted_cnf.hard.len() + weighted_cnf.soft.len() => {
+                Err("clause count does not match problem line")
+            }
+            _ => Ok(weighted_cnf),
+        }
+    }
+
+    /// Writes the classic format, with `top` one more than the total soft weight
+    pub fn to_wcnf(&self) -> Result<String, &'static str> {
+        let top = self
+            .soft
+            .iter()
+            .try_fold(1u64, |top, (_, weight)| top.checked_add(*weight))
+            .ok_or("total soft weight too large for a top weight")?;
+        let mut wcnf = format!(
+            "p wcnf {} {} {top}\n",
+            self.num_variables,
+            self.hard.len() + self.soft.len()
+        );
+        let weighted = std::iter::chain(
+            self.hard.iter().map(|clause| (clause, top)),
+            self.soft.iter().map(|(clause, weight)| (clause, *weight)),
+        );
+        for (clause, weight) in weighted {
+            wcnf += &weight.to_string();
+            for literal in clause {
+                wcnf += &format!(" {literal}");
+            }
+            wcnf += " 0\n";
+        }
+        Ok(wcnf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::Rng};
+
+    fn brute_force(weighted_cnf: &WeightedCnf) -> Option<u64> {
+        let n = weighted_cnf.num_variables;
+        let hard = Cnf {
+            num_variables: n,
+            clauses: weighted_cnf.hard.clone(),
+        };
+        (0..1u32 << n)
+            .map(|mask| (0..n).map(|i| mask >> i & 1 == 1).collect::<Vec<_>>())
+            .filter(|model| hard.evaluate(model))
+            .map(|model| weighted_cnf.cost(&model))
+            .min()
+    }
+
+    fn random_clause(rng: &mut impl Rng) -> Clause {
+        (0..rng.random_range(1..=3))
+            .map(|_| {
+                let v = rng.random_range(1..=6);
+                if rng.random() { v } else { -v }
+            })
+            .collect()
+    }
+
+    fn random_weighted_cnf(rng: &mut impl Rng) -> WeightedCnf {
+        let mut weighted_cnf = WeightedCnf::new();
+        weighted_cnf.num_variables = 6;
+        for _ in 0..rng.random_range(0..8) {
+            weighted_cnf.add_hard(random_clause(rng));
+        }
+        for _ in 0..rng.random_range(1..15) {
+            weighted_cnf.add_soft(random_clause(rng), rng.random_range(1..=5));
+        }
+        weighted_cnf
+    }
+
+    fn scheduling() -> WeightedCnf {
+        // meetings 1, 2 and 3 compete for a single morning slot
+        let mut weighted_cnf = WeightedCnf::new();
+        for (a, b) in [(1, 2), (1, 3), (2, 3)] {
+            weighted_cnf.add_hard([-a, -b]);
+        }
+        // each pair of meetings also wants one of them in the morning
+        weighted_cnf.add_hard([1, 2]);
+        weighted_cnf.add_hard([1, 3]);
+        weighted_cnf.add_hard([2, 3]);
+        weighted_cnf.add_soft([1], 3);
+        weighted_cnf.add_soft([2], 5);
+        weighted_cnf.add_soft([3], 1);
+        weighted_cnf.add_soft([-2], 1);
+        weighted_cnf
+    }
+
+    #[test]
+    fn test_scheduling() {
+        assert_eq!(scheduling().fu_malik(), None);
+        assert_eq!(scheduling().linear_search(), None);
+
+        let mut weighted_cnf = scheduling();
+        weighted_cnf.hard.truncate(3);
+        weighted_cnf.add_hard([1, 2, 3]);
+        let expected = MaxSatSolution {
+            model: vec![false, true, false],
+            cost: 5,
+        };
+        assert_eq!(weighted_cnf.fu_malik(), Some(expected.clone()));
+        assert_eq!(weighted_cnf.linear_search(), Some(expected));
+    }
+
+    #[test]
+    fn test_no_conflict() {
+        let mut weighted_cnf = WeightedCnf::new();
+        weighted_cnf.add_soft([1, 2], 4);
+        weighted_cnf.add_soft([-1], 2);
+        for solution in [weighted_cnf.fu_malik(), weighted_cnf.linear_search()] {
+            let solution = solution.unwrap();
+            assert_eq!(solution.cost, 0);
+            assert_eq!(weighted_cnf.cost(&solution.model), 0);
+        }
+    }
+
+    #[test]
+    fn test_random_optimum() {
+        let mut rng = rand::rng();
+        for _ in 0..100 {
+            let weighted_cnf = random_weighted_cnf(&mut rng);
+            let expected = brute_force(&weighted_cnf);
+            let hard = Cnf {
+                num_variables: weighted_cnf.num_variables,
+                clauses: weighted_cnf.hard.clone(),
+            };
+            for solution in [weighted_cnf.fu_malik(), weighted_cnf.linear_search()] {
+                assert_eq!(solution.as_ref().map(|s| s.cost), expected);
+                if let Some(solution) = solution {
+                    assert!(hard.evaluate(&solution.model));
+                    assert_eq!(weighted_cnf.cost(&solution.model), solution.cost);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wcnf_classic() {
+        let wcnf = "c comment\np wcnf 3 4 10\n10 1 -2 0\n10 -1 2 3 0\n3 2 0\n1 -3 0\n";
+        let weighted_cnf = WeightedCnf::from_wcnf(wcnf).unwrap();
+        assert_eq!(weighted_cnf.num_variables, 3);
+        assert_eq!(weighted_cnf.hard, vec![vec![1, -2], vec![-1, 2, 3]]);
+        assert_eq!(weighted_cnf.soft, vec![(vec![2], 3), (vec![-3], 1)]);
+        assert_eq!(
+            WeightedCnf::from_wcnf(&weighted_cnf.to_wcnf().unwrap()).unwrap(),
+            weighted_cnf
+        );
+
+        let mut heavy = WeightedCnf::new();
+        heavy.add_soft(vec![1], u64::MAX - 1);
+        assert!(
+            heavy
+                .to_wcnf()
+                .unwrap()
+                .starts_with("p wcnf 1 1 18446744073709551615\n")
+        );
+        heavy.add_soft(vec![-1], 1);
+        assert_eq!(
+            heavy.to_wcnf(),
+            Err("total soft weight too large for a top weight")
+        );
+    }
+
+    #[test]
+    fn test_wcnf_2022() {
+        let wcnf = "c comment\nh 1 -2 0\nh -1 2 3 0\n3 2 0\n1 -3 0\n";
+        let weighted_cnf = WeightedCnf::from_wcnf(wcnf).unwrap();
+        assert_eq!(weighted_cnf.num_variables, 3);
+        assert_eq!(weighted_cnf.hard, vec![vec![1, -2], vec![-1, 2, 3]]);
+        assert_eq!(weighted_cnf.soft, vec![(vec![2], 3), (vec![-3], 1)]);
+    }
+
+    #[test]
+    fn test_wcnf_errors() {
+        assert!(WeightedCnf::from_wcnf("p wcnf 2 1 5\n5 1 2\n").is_err());
+        assert!(WeightedCnf::from_wcnf("p wcnf 2 2 5\n5 1 2 0\n").is_err());
+        assert!(WeightedCnf::from_wcnf("p wcnf 2 1 5\nh 1 2 0\n").is_err());
+        assert!(WeightedCnf::from_wcnf("0 1 2 0\n").is_err());
+        assert!(WeightedCnf::from_wcnf("x 1 2 0\n").is_err());
+        assert!(WeightedCnf::from_wcnf("1 1 0 2 0\n").is_err());
+        let heavy = "h 1 0\n10000000000000000000 -1 0\n10000000000000000000 -1 0\n";
+        assert_eq!(
+            WeightedCnf::from_wcnf(heavy),
+            Err("total soft weight too large")
+        );
+    }
+
+    #[test]
+    fn test_heavy_cost() {
+        let mut weighted_cnf = WeightedCnf::new();
+        weighted_cnf.add_soft(vec![1], 10_000_000_000_000_000_000);
+        weighted_cnf.add_soft(vec![1], 10_000_000_000_000_000_000);
+        weighted_cnf.add_soft(vec![-1], 1);
+        assert_eq!(weighted_cnf.cost(&[false]), u64::MAX);
+        assert_eq!(weighted_cnf.cost(&[true]), 1);
+        assert_eq!(weighted_cnf.fu_malik().unwrap().cost, 1);
+        assert_eq!(weighted_cnf.linear_search().unwrap().cost, 1);
+    }
+}
@@ -0,0 +1,328 @@
+//! Multipliers built from the gates of the adders: partial products from an AND array
+//! or Booth encoding, summed row by row or by a tree of carry-save adders
+
+use crate::{
+    adders::{Circuit, Signal, from_signals, to_signals},
+    numbers::Word,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartialProducts {
+    /// `a[i] & b` shifted by `i`, one row per bit of `a`
+    AndArray,
+    /// digits in {-1, 0, 1} from each pair of adjacent bits of `a`, one row per bit
+    BoothRadix2,
+    /// digits in {-2, ..., 2} from overlapping triples of bits of `a`, one row per two bits
+    BoothRadix4,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reduction {
+    /// rows added one after the other with ripple-carry adders
+    Sequential,
+    /// every column reduced as much as possible at each level of carry-save adders
+    Wallace,
+    /// columns reduced just enough to reach the next height of the sequence 2, 3, 4, 6, 9, ...
+    Dadda,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MultiplierReport<T> {
+    pub product: T,
+    /// rows generated from the multiplier, without the row of Booth negation corrections
+    pub partial_products: usize,
+    pub gates: usize,
+    pub depth: usize,
+}
+
+/// bits of a partial product with their column, columns past the width are dropped
+type Row = Vec<(usize, Signal)>;
+
+fn and_array(circuit: &mut Circuit, a: &[Signal], b: &[Signal]) -> Vec<Row> {
+    let n = a.len();
+    (0..n)
+        .map(|i| (i..n).map(|j| (j, circuit.and(a[i], b[j - i]))).collect())
+        .collect()
+}
+
+/// A negative digit `-d` is written `!d + 1`: the row is complemented
+/// and the 1 goes into the last row, with the other corrections.
+fn booth_radix_2(circuit: &mut Circuit, a: &[Signal], b: &[Signal]) -> Vec<Row> {
+    let n = a.len();
+    let mut rows = vec![];
+    let mut corrections = vec![];
+    for i in 0..n {
+        let previous = if i == 0 {
+            Circuit::input(false)
+        } else {
+            a[i - 1]
+        };
+        let nonzero = circuit.xor(a[i], previous);
+        // when both bits are set, the digit 0 is written as -0
+        let negative = a[i];
+        let row = (i..n)
+            .map(|j| {
+                let bit = circuit.and(b[j - i], nonzero);
+                (j, circuit.xor(bit, negative))
+            })
+            .collect();
+        rows.push(row);
+        corrections.push((i, negative));
+    }
+    rows.push(corrections);
+    rows
+}
+
+fn booth_radix_4(circuit: &mut Circuit, a: &[Signal], b: &[Signal]) -> Vec<Row> {
+    let n = a.len();
+    let mut rows = vec![];
+    let mut corrections = vec![];
+    for i in (0..n).step_by(2) {
+        let previous = if i == 0 {
+            Circuit::input(false)
+        } else {
+            a[i - 1]
+        };
+        let one = circuit.xor(a[i], previous);
+        let high_differs = circuit.xor(a[i + 1], a[i]);
+        let not_one = circuit.not(one);
+        let two = circuit.and(high_differs, not_one);
+        let negative = a[i + 1];
+        let row = (i..n)
+            .map(|j| {
+                let mut bit = circuit.and(b[j - i], one);
+                if j > i {
+                    let doubled = circuit.and(b[j - i - 1], two);
+                    bit = circuit.or(bit, doubled);
+                }
+                (j, circuit.xor(bit, negative))
+            })
+            .collect();
+        rows.push(row);
+        corrections.push((i, negative));
+    }
+    rows.push(corrections);
+    rows
+}
+
+fn sequential(circuit: &mut Circuit, rows: &[Row], n: usize) -> Vec<Signal> {
+    let to_bits = |row: &Row| {
+        let mut bits = vec![Circuit::input(false); n];
+        for &(j, bit) in row {
+            bits[j] = bit;
+        }
+        bits
+    };
+    let mut sum = to_bits(&rows[0]);
+    for row in &rows[1..] {
+        sum = circuit
+            .ripple_carry(&sum, &to_bits(row), Circuit::input(false))
+            .0;
+    }
+    sum
+}
+
+/// Carry-save adder on 2 or 3 bits of column `j`: the sum stays in `next[j]`
+/// and the carry goes to `next[j + 1]`, unless it falls past the width
+fn compress(circuit: &mut Circuit, bits: &[Signal], j: usize, next: &mut [Vec<Signal>]) {
+    if j + 1 == next.len() {
+        let sum = circuit.reduce(bits, Circuit::xor).unwrap();
+        next[j].push(sum);
+        return;
+    }
+    let (sum, carry) = match *bits {
+        [x, y] => circuit.half_adder(x, y),
+        [x, y, z] => circuit.full_adder(x, y, z),
+        _ => unreachable!(),
+    };
+    next[j].push(sum);
+    next[j + 1].push(carry);
+}
+
+fn wallace(circuit: &mut Circuit, mut columns: Vec<Vec<Signal>>) -> Vec<Vec<Signal>> {
+    while columns.iter().any(|column| column.len() > 2) {
+        let mut next = vec![vec![]; columns.len()];
+        for (j, column) in columns.iter().enumerate() {
+            for bits in column.chunks(3) {
+                match bits {
+                    [bit] => next[j].push(*bit),
+                    _ => compress(circuit, bits, j, &mut next),
+                }
+            }
+        }
+        columns = next;
+    }
+    columns
+}
+
+fn dadda(circuit: &mut Circuit, mut columns: Vec<Vec<Signal>>) -> Vec<Vec<Signal>> {
+    let max_height = columns.iter().map(Vec::len).max().unwrap();
+    let mut heights = vec![2];
+    while *heights.last().unwrap() < max_height {
+        heights.push(heights.last().unwrap() * 3 / 2);
+    }
+    heights.pop();
+    for &height in heights.iter().rev() {
+        let mut next = vec![vec![]; columns.len()];
+        for (j, column) in columns.iter().enumerate() {
+            let mut bits = column.as_slice();
+            // next[j] already holds the carries coming from column j - 1
+            while bits.len() + next[j].len() > height {
+                let excess = bits.len() + next[j].len() - height;
+                let taken = if excess >= 2 && bits.len() >= 3 { 3 } else { 2 };
+                compress(circuit, &bits[..taken], j, &mut next);
+                bits = &bits[taken..];
+            }
+            next[j].extend_from_slice(bits);
+        }
+        columns = next;
+    }
+    columns
+}
+
+/// Builds the multiplier and simulates it on `a` and `b`. The product wraps around,
+/// Booth encoding reads `a` as signed, which does not change the low half of the product.
+pub fn multiplier_circuit<T: Word>(
+    a: T,
+    b: T,
+    partial_products: PartialProducts,
+    reduction: Reduction,
+) -> MultiplierReport<T> {
+    let (a, b) = (to_signals(a), to_signals(b));
+    let n = a.len();
+    let mut circuit = Circuit::default();
+    let rows = match partial_products {
+        PartialProducts::AndArray => and_array(&mut circuit, &a, &b),
+        PartialProducts::BoothRadix2 => booth_radix_2(&mut circuit, &a, &b),
+        PartialProducts::BoothRadix4 => booth_radix_4(&mut circuit, &a, &b),
+    };
+    let row_count = match partial_products {
+        PartialProducts::AndArray => rows.len(),
+        _ => rows.len() - 1,
+    };
+
+    let product = if reduction == Reduction::Sequential {
+        sequential(&mut circuit, &rows, n)
+    } else {
+        let mut columns = vec![vec![]; n];
+        for &(j, bit) in rows.iter().flatten() {
+            columns[j].push(bit);
+        }
+        let columns = if reduction == Reduction::Wallace {
+            wallace(&mut circuit, columns)
+        } else {
+            dadda(&mut circuit, columns)
+        };
+        // the two remaining rows go through a carry-propagate adder
+        let mut operands = [vec![], vec![]];
+        for column in columns {
+            for (k, operand) in operands.iter_mut().enumerate() {
+                operand.push(column.get(k).copied().unwrap_or(Circuit::input(false)));
+            }
+        }
+        let [x, y] = operands;
+        circuit.ripple_carry(&x, &y, Circuit::input(false)).0
+    };
+
+    MultiplierReport {
+        product: from_signals(&product),
+        partial_products: row_count,
+        gates: circuit.gates,
+        depth: product.iter().map(|signal| signal.depth).max().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        rand::{Rng, distr::StandardUniform, prelude::Distribution},
+    };
+
+    const ENCODINGS: [PartialProducts; 3] = [
+        PartialProducts::AndArray,
+        PartialProducts::BoothRadix2,
+        PartialProducts::BoothRadix4,
+    ];
+    const REDUCTIONS: [Reduction; 3] =
+        [Reduction::Sequential, Reduction::Wallace, Reduction::Dadda];
+
+    fn check_width<T: Word + std::ops::Mul<Output = T>>(wrapping_mul: fn(T, T) -> T)
+    where
+        StandardUniform: Distribution<T>,
+    {
+        let mut rng = rand::rng();
+        let edges = [T::ZERO, T::ONE, !T::ZERO, !T::ZERO >> 1, !(!T::ZERO >> 1)];
+        let pairs = edges
+            .iter()
+            .flat_map(|&a| edges.iter().map(move |&b| (a, b)))
+            .chain((0..20).map(|_| (rng.random(), rng.random())));
+        for (a, b) in pairs {
+            for encoding in ENCODINGS {
+                for reduction in REDUCTIONS {
+                    let report = multiplier_circuit(a, b, encoding, reduction);
+                    assert_eq!(
+                        report.product,
+                        wrapping_mul(a, b),
+                        "{a:?} * {b:?} with {encoding:?} and {reduction:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wrapping_mul() {
+        check_width::<u8>(u8::wrapping_mul);
+        check_width::<u16>(u16::wrapping_mul);
+        check_width::<u32>(u32::wrapping_mul);
+        check_width::<u64>(u64::wrapping_mul);
+        check_width::<u128>(u128::wrapping_mul);
+    }
+
+    #[test]
+    fn test_exhaustive_8_bits() {
+        for encoding in ENCODINGS {
+            for reduction in [Reduction::Wallace, Reduction::Dadda] {
+                for a in 0..=u8::MAX {
+                    for b in (0..=u8::MAX).step_by(7) {
+                        let report = multiplier_circuit(a, b, encoding, reduction);
+                        assert_eq!(report.product, a.wrapping_mul(b));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_costs() {
+        let report = |encoding, reduction| multiplier_circuit(0u32, 0, encoding, reduction);
+        assert_eq!(
+            report(PartialProducts::AndArray, Reduction::Sequential).partial_products,
+            32
+        );
+        assert_eq!(
+            report(PartialProducts::BoothRadix2, Reduction::Sequential).partial_products,
+            32
+        );
+        assert_eq!(
+            report(PartialProducts::BoothRadix4, Reduction::Sequential).partial_products,
+            16
+        );
+        for encoding in ENCODINGS {
+            let sequential = report(encoding, Reduction::Sequential);
+            let wallace = report(encoding, Reduction::Wallace);
+            let dadda = report(encoding, Reduction::Dadda);
+            assert!(wallace.depth < sequential.depth, "{encoding:?}");
+            assert!(dadda.depth < sequential.depth, "{encoding:?}");
+            assert!(dadda.gates <= wallace.gates, "{encoding:?}");
+        }
+        // half as many rows to sum
+        for reduction in REDUCTIONS {
+            assert!(
+                report(PartialProducts::BoothRadix4, reduction).gates
+                    < report(PartialProducts::BoothRadix2, reduction).gates
+            );
+        }
+    }
+}
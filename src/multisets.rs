@@ -0,0 +1,150 @@
+//! Set formulas on multisets, sets where each element comes with a multiplicity
+
+use {
+    crate::sets::{compile_pointwise, eval_pointwise},
+    itertools::Itertools,
+    std::{collections::HashMap, hash::Hash},
+};
+
+/// Multiplicity of each element, the missing ones having none
+pub type Multiset<T> = HashMap<T, usize>;
+
+/// `|` and `&` take the maximum and minimum multiplicity, `+` adds them and `-` subtracts
+/// them, down to 0. `^` is the difference between the multiplicities. `!`, `=` and `>` are
+/// taken in the universe `u`: `!a` is `u - a`, `a = b` is `!(a ^ b)` and `a > b` is `!a | b`.
+/// A sum can exceed `u`, its complement is then 0. With multiplicities of at most 1, these
+/// are the set operations of `eval_set`.
+fn multiset_operation(c: char, u: usize, a: usize, b: usize) -> usize {
+    match c {
+        '|' => a.max(b),
+        '&' => a.min(b),
+        '+' => a + b,
+        '-' => a.saturating_sub(b),
+        '^' => a.abs_diff(b),
+        '=' => complement(u, a.abs_diff(b)),
+        '>' => complement(u, a).max(b),
+        _ => unreachable!("not a multiset operator"),
+    }
+}
+
+fn complement(u: usize, a: usize) -> usize {
+    u.saturating_sub(a)
+}
+
+/// The universe is the union of the multisets, each element with its highest multiplicity.
+/// The result holds no element of multiplicity 0.
+pub fn eval_multisets<T: Eq + Hash + Clone>(
+    formula: &str,
+    sets: &[Multiset<T>],
+) -> Result<Multiset<T>, &'static str> {
+    let steps = compile_pointwise(formula, "|&+-^=>", sets.len())?;
+    let mut universe: Multiset<T> = HashMap::new();
+    for (x, &count) in sets.iter().flatten() {
+        let max = universe.entry(x.clone()).or_default();
+        *max = count.max(*max);
+    }
+    let multiplicity = |x: &T, i: usize| sets[i].get(x).copied().unwrap_or(0);
+    Ok(universe
+        .into_iter()
+        .filter_map(|(x, u)| {
+            let count = eval_pointwise(
+                &steps,
+                |i| multiplicity(&x, i),
+                |a| complement(u, a),
+                |c, a, b| multiset_operation(c, u, a, b),
+            );
+            (count > 0).then_some((x, count))
+        })
+        .collect())
+}
+
+/// Sorted elements of the result, each one repeated as many times as its multiplicity,
+/// as the elements of the multisets are
+pub fn eval_multiset(formula: &str, sets: Vec<Vec<i32>>) -> Vec<i32> {
+    let sets = sets
+        .into_iter()
+        .map(|set| set.into_iter().counts())
+        .collect_vec();
+    eval_multisets(formula, &sets)
+        .unwrap_or_else(|error| panic!("{error}"))
+        .into_iter()
+        .sorted_unstable()
+        .flat_map(|(x, count)| std::iter::repeat_n(x, count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            eval_set,
+            sets::fixtures::{FORMULAS, region_sets},
+        },
+    };
+
+    #[test]
+    fn test_eval_multiset() {
+        let sets = vec![vec![1, 1, 1, 2], vec![1, 2, 2, 3]];
+        assert_eq!(eval_multiset("AB|", sets.clone()), [1, 1, 1, 2, 2, 3]);
+        assert_eq!(eval_multiset("AB&", sets.clone()), [1, 2]);
+        assert_eq!(eval_multiset("AB+", sets.clone()), [1, 1, 1, 1, 2, 2, 2, 3]);
+        assert_eq!(eval_multiset("AB-", sets.clone()), [1, 1]);
+        assert_eq!(eval_multiset("BA-", sets.clone()), [2, 3]);
+        assert_eq!(eval_multiset("AB^", sets.clone()), [1, 1, 2, 3]);
+        // in the universe [1, 1, 1, 2, 2, 3]
+        assert_eq!(eval_multiset("A!", sets.clone()), [2, 3]);
+        assert_eq!(eval_multiset("AB=", sets.clone()), [1, 2]);
+        assert_eq!(eval_multiset("AB>", sets.clone()), [1, 2, 2, 3]);
+
+        let counts = [
+            HashMap::from([("bolt", 40), ("nut", 10)]),
+            HashMap::from([("nut", 25)]),
+        ];
+        assert_eq!(
+            eval_multisets("AB-", &counts),
+            Ok(HashMap::from([("bolt", 40)]))
+        );
+        assert_eq!(eval_multisets("AB+", &counts).unwrap()["nut"], 35);
+        assert_eq!(
+            eval_multisets("AC|", &counts),
+            Err("formula refers to a set that was not given")
+        );
+        assert_eq!(
+            eval_multisets("A+", &counts),
+            Err("not enough operands for set operation")
+        );
+        assert_eq!(eval_multisets("AB", &counts), Err("not enough operators"));
+    }
+
+    #[test]
+    fn test_sums_under_complement() {
+        // the sum of 1 and 1 is past the universe [1]
+        let sets = vec![vec![1], vec![1]];
+        assert_eq!(eval_multiset("AB+", sets.clone()), [1, 1]);
+        assert_eq!(eval_multiset("AB+!", sets.clone()), []);
+        assert_eq!(eval_multiset("AB+!!", sets.clone()), [1]);
+        assert_eq!(eval_multiset("AB+A>", sets.clone()), [1]);
+        assert_eq!(eval_multiset("AAB++A=", sets.clone()), []);
+        assert_eq!(eval_multiset("AB+A-", sets.clone()), [1]);
+
+        // in the universe [1, 1, 2]
+        let sets = vec![vec![1, 1, 2], vec![1, 2, 2]];
+        assert_eq!(eval_multiset("AB-!", sets.clone()), [1, 2, 2]);
+        assert_eq!(eval_multiset("A!B-", sets.clone()), []);
+        assert_eq!(eval_multiset("A!B+", sets.clone()), [1, 2, 2, 2]);
+        assert_eq!(eval_multiset("AB+B>", sets.clone()), [1, 2, 2]);
+    }
+
+    #[test]
+    fn test_multisets_extend_sets() {
+        let sets = region_sets();
+        for formula in FORMULAS {
+            assert_eq!(
+                eval_multiset(formula, sets.clone()),
+                eval_set(formula, sets.clone()),
+                "{formula}"
+            );
+        }
+    }
+}
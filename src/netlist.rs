@@ -0,0 +1,1369 @@
+//! Combinational circuits: gates over numbered wires, some of them named,
+//! simulated in topological order on 64 input vectors at once,
+//! and exchanged with synthesis tools as structural Verilog or BLIF
+
+use {
+    crate::{BooleanTree, Cnf, cnf::Literal},
+    std::collections::{HashMap, HashSet},
+};
+
+pub type Wire = usize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Gate {
+    And(Wire, Wire),
+    Or(Wire, Wire),
+    Xor(Wire, Wire),
+    Not(Wire),
+    Nand(Wire, Wire),
+    /// `Mux(s, a, b)` is `a` when `s` is set, `b` otherwise
+    Mux(Wire, Wire, Wire),
+}
+
+impl Gate {
+    pub fn operands(&self) -> Vec<Wire> {
+        match *self {
+            Gate::Not(a) => vec![a],
+            Gate::And(a, b) | Gate::Or(a, b) | Gate::Xor(a, b) | Gate::Nand(a, b) => vec![a, b],
+            Gate::Mux(s, a, b) => vec![s, a, b],
+        }
+    }
+
+    /// bit-parallel evaluation, `values` is indexed by wire
+    fn evaluate(&self, values: &[u64]) -> u64 {
+        match *self {
+            Gate::And(a, b) => values[a] & values[b],
+            Gate::Or(a, b) => values[a] | values[b],
+            Gate::Xor(a, b) => values[a] ^ values[b],
+            Gate::Not(a) => !values[a],
+            Gate::Nand(a, b) => !(values[a] & values[b]),
+            Gate::Mux(s, a, b) => (values[s] & values[a]) | (!values[s] & values[b]),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Driver {
+    Input,
+    Constant(bool),
+    Gate(Gate),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Netlist {
+    drivers: Vec<Option<Driver>>,
+    names: Vec<Option<String>>,
+    wires_by_name: HashMap<String, Wire>,
+    inputs: Vec<Wire>,
+    outputs: Vec<(String, Wire)>,
+}
+
+impl Netlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn num_wires(&self) -> usize {
+        self.drivers.len()
+    }
+
+    pub fn num_gates(&self) -> usize {
+        self.drivers
+            .iter()
+            .filter(|driver| matches!(driver, Some(Driver::Gate(_))))
+            .count()
+    }
+
+    pub fn inputs(&self) -> &[Wire] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[(String, Wire)] {
+        &self.outputs
+    }
+
+    /// `None` for a wire declared with `add_wire` and not driven yet
+    pub fn driver(&self, wire: Wire) -> Option<Driver> {
+        self.drivers[wire]
+    }
+
+    pub fn name(&self, wire: Wire) -> Option<&str> {
+        self.names[wire].as_deref()
+    }
+
+    pub fn wire(&self, name: &str) -> Option<Wire> {
+        self.wires_by_name.get(name).copied()
+    }
+
+    pub fn set_name(&mut self, wire: Wire, name: &str) {
+        assert!(
+            self.wire(name).is_none_or(|other| other == wire),
+            "wire name '{name}' is already taken"
+        );
+        if let Some(old) = self.names[wire].take() {
+            self.wires_by_name.remove(&old);
+        }
+        self.names[wire] = Some(name.to_string());
+        self.wires_by_name.insert(name.to_string(), wire);
+    }
+
+    /// undriven wire, to be driven later with `drive`: gates can then refer to it beforehand
+    pub fn add_wire(&mut self) -> Wire {
+        self.drivers.push(None);
+        self.names.push(None);
+        self.drivers.len() - 1
+    }
+
+    pub fn add_input(&mut self, name: &str) -> Wire {
+        let wire = self.add_wire();
+        self.drivers[wire] = Some(Driver::Input);
+        self.set_name(wire, name);
+        self.inputs.push(wire);
+        wire
+    }
+
+    pub fn add_output(&mut self, name: &str, wire: Wire) {
+        assert!(wire < self.num_wires(), "no wire {wire}");
+        assert!(
+            self.outputs.iter().all(|(other, _)| other != name),
+            "output name '{name}' is already taken"
+        );
+        self.outputs.push((name.to_string(), wire));
+    }
+
+    pub fn constant(&mut self, value: bool) -> Wire {
+        let wire = self.add_wire();
+        self.drivers[wire] = Some(Driver::Constant(value));
+        wire
+    }
+
+    pub fn drive(&mut self, wire: Wire, gate: Gate) {
+        assert!(
+            gate.operands()
+                .iter()
+                .all(|&operand| operand < self.num_wires()),
+            "gate operand is not a wire"
+        );
+        assert!(
+            self.drivers[wire].is_none(),
+            "wire {wire} is already driven"
+        );
+        self.drivers[wire] = Some(Driver::Gate(gate));
+    }
+
+    pub fn add_gate(&mut self, gate: Gate) -> Wire {
+        let wire = self.add_wire();
+        self.drive(wire, gate);
+        wire
+    }
+
+    pub fn and(&mut self, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::And(a, b))
+    }
+
+    pub fn or(&mut self, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::Or(a, b))
+    }
+
+    pub fn xor(&mut self, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::Xor(a, b))
+    }
+
+    pub fn not(&mut self, a: Wire) -> Wire {
+        self.add_gate(Gate::Not(a))
+    }
+
+    pub fn nand(&mut self, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::Nand(a, b))
+    }
+
+    pub fn mux(&mut self, s: Wire, a: Wire, b: Wire) -> Wire {
+        self.add_gate(Gate::Mux(s, a, b))
+    }
+
+    /// Wires the outputs depend on, each one after its operands
+    pub fn topological_order(&self) -> Result<Vec<Wire>, &'static str> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum State {
+            Unvisited,
+            Visiting,
+            Done,
+        }
+
+        let mut states = vec![State::Unvisited; self.num_wires()];
+        let mut order = vec![];
+        for &(_, output) in &self.outputs {
+            // explicit stack: (wire, operands already pushed)
+            let mut stack = vec![(output, false)];
+            while let Some((wire, expanded)) = stack.pop() {
+                if expanded {
+                    states[wire] = State::Done;
+                    order.push(wire);
+                    continue;
+                }
+                match states[wire] {
+                    State::Done => continue,
+                    State::Visiting => return Err("combinational loop"),
+                    State::Unvisited => {}
+                }
+                states[wire] = State::Visiting;
+                stack.push((wire, true));
+                match self.drivers[wire] {
+                    None => return Err("undriven wire"),
+                    Some(Driver::Input | Driver::Constant(_)) => {}
+                    Some(Driver::Gate(gate)) => {
+                        for operand in gate.operands() {
+                            match states[operand] {
+                                State::Visiting => return Err("combinational loop"),
+                                State::Unvisited => stack.push((operand, false)),
+                                State::Done => {}
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    /// Bit `k` of `inputs[i]` is the value of the `i`-th input in the `k`-th vector,
+    /// returns the outputs in the same layout
+    pub fn simulate_parallel(&self, inputs: &[u64]) -> Result<Vec<u64>, &'static str> {
+        if inputs.len() != self.inputs.len() {
+            return Err("wrong number of inputs");
+        }
+        let mut values = vec![0; self.num_wires()];
+        for (&wire, &value) in self.inputs.iter().zip(inputs) {
+            values[wire] = value;
+        }
+        for wire in self.topological_order()? {
+            values[wire] = match self.drivers[wire].unwrap() {
+                Driver::Input => values[wire],
+                Driver::Constant(b) => {
+                    if b {
+                        !0
+                    } else {
+                        0
+                    }
+                }
+                Driver::Gate(gate) => gate.evaluate(&values),
+            };
+        }
+        Ok(self.outputs.iter().map(|&(_, wire)| values[wire]).collect())
+    }
+
+    pub fn simulate(&self, inputs: &[bool]) -> Result<Vec<bool>, &'static str> {
+        let inputs = inputs
+            .iter()
+            .map(|&b| if b { !0 } else { 0 })
+            .collect::<Vec<_>>();
+        let outputs = self.simulate_parallel(&inputs)?;
+        Ok(outputs.into_iter().map(|word| word & 1 == 1).collect())
+    }
+
+    // =====================================================
+    // ==================== GENERATORS =====================
+    // =====================================================
+
+    fn add_word(&mut self, prefix: char, width: u32) -> Vec<Wire> {
+        (0..width)
+            .map(|i| self.add_input(&format!("{prefix}{i}")))
+            .collect()
+    }
+
+    /// returns the sum bits and the carry-out, as in `adder`
+    fn ripple_carry(&mut self, a: &[Wire], b: &[Wire]) -> (Vec<Wire>, Wire) {
+        let mut carry = self.constant(false);
+        let mut sum = vec![];
+        for (&ai, &bi) in a.iter().zip(b) {
+            let propagate = self.xor(ai, bi);
+            sum.push(self.xor(propagate, carry));
+            let generate = self.and(ai, bi);
+            let carried = self.and(propagate, carry);
+            carry = self.or(generate, carried);
+        }
+        (sum, carry)
+    }
+
+    /// Inputs `a0`..`a{width-1}` then `b0`..., outputs the sum bits `s0`... then `carry`,
+    /// least significant bits first
+    pub fn ripple_adder(width: u32) -> Self {
+        let mut netlist = Self::new();
+        let a = netlist.add_word('a', width);
+        let b = netlist.add_word('b', width);
+        let (sum, carry) = netlist.ripple_carry(&a, &b);
+        for (i, wire) in sum.into_iter().enumerate() {
+            netlist.add_output(&format!("s{i}"), wire);
+        }
+        netlist.add_output("carry", carry);
+        netlist
+    }
+
+    /// Same inputs as `ripple_adder`, outputs the wrapped product bits `p0`...
+    /// Like `multiplier`, each row `b & a[i]` shifted by `i` goes through a ripple-carry adder.
+    pub fn array_multiplier(width: u32) -> Self {
+        let mut netlist = Self::new();
+        let a = netlist.add_word('a', width);
+        let b = netlist.add_word('b', width);
+        let zero = netlist.constant(false);
+        let mut product = vec![zero; width as usize];
+        for (i, &ai) in a.iter().enumerate() {
+            let mut row = vec![zero; i];
+            row.extend(b[..b.len() - i].iter().map(|&bj| netlist.and(ai, bj)));
+            product = netlist.ripple_carry(&product, &row).0;
+        }
+        for (i, wire) in product.into_iter().enumerate() {
+            netlist.add_output(&format!("p{i}"), wire);
+        }
+        netlist
+    }
+
+    /// Same interface as `ripple_adder`, the carries come from a Kogge-Stone prefix network
+    pub fn lookahead_adder(width: u32) -> Self {
+        let mut netlist = Self::new();
+        let a = netlist.add_word('a', width);
+        let b = netlist.add_word('b', width);
+        let propagates = a
+            .iter()
+            .zip(&b)
+            .map(|(&ai, &bi)| netlist.xor(ai, bi))
+            .collect::<Vec<_>>();
+        // (generate, propagate) of the bits 0..=i
+        let mut nodes = a
+            .iter()
+            .zip(&b)
+            .zip(&propagates)
+            .map(|((&ai, &bi), &p)| (netlist.and(ai, bi), p))
+            .collect::<Vec<_>>();
+        let mut distance = 1;
+        while distance < nodes.len() {
+            for i in (distance..nodes.len()).rev() {
+                let ((high_generate, high_propagate), (low_generate, low_propagate)) =
+                    (nodes[i], nodes[i - distance]);
+                let carried = netlist.and(high_propagate, low_generate);
+                nodes[i] = (
+                    netlist.or(high_generate, carried),
+                    netlist.and(high_propagate, low_propagate),
+                );
+            }
+            distance *= 2;
+        }
+        for i in 0..propagates.len() {
+            let sum = match i {
+                0 => propagates[0],
+                _ => netlist.xor(propagates[i], nodes[i - 1].0),
+            };
+            netlist.add_output(&format!("s{i}"), sum);
+        }
+        netlist.add_output("carry", nodes[nodes.len() - 1].0);
+        netlist
+    }
+
+    /// One input per variable of the formulas, named after it, in alphabetical order,
+    /// and one output per formula. Quantifiers are expanded first.
+    pub fn from_formulas(formulas: &[(&str, BooleanTree)]) -> Self {
+        fn build(netlist: &mut Netlist, tree: &BooleanTree) -> Wire {
+            match tree {
+                BooleanTree::Value(b) => netlist.constant(*b),
+                BooleanTree::Variable(c) => netlist.wire(&c.to_string()).unwrap(),
+                BooleanTree::Not(node) => {
+                    let a = build(netlist, node);
+                    netlist.not(a)
+                }
+                BooleanTree::Ite(node1, node2, node3) => {
+                    let s = build(netlist, node1);
+                    let a = build(netlist, node2);
+                    let b = build(netlist, node3);
+                    netlist.mux(s, a, b)
+                }
+                BooleanTree::Or(node1, node2)
+                | BooleanTree::And(node1, node2)
+                | BooleanTree::Xor(node1, node2)
+                | BooleanTree::Implication(node1, node2)
+                | BooleanTree::Equivalence(node1, node2)
+                | BooleanTree::Nand(node1, node2)
+                | BooleanTree::Nor(node1, node2)
+                | BooleanTree::ConverseImplication(node1, node2)
+                | BooleanTree::NonImplication(node1, node2) => {
+                    let a = build(netlist, node1);
+                    let b = build(netlist, node2);
+                    match tree {
+                        BooleanTree::Or(..) => netlist.or(a, b),
+                        BooleanTree::And(..) => netlist.and(a, b),
+                        BooleanTree::Xor(..) => netlist.xor(a, b),
+                        BooleanTree::Nand(..) => netlist.nand(a, b),
+                        BooleanTree::Implication(..) => {
+                            let not_a = netlist.not(a);
+                            netlist.or(not_a, b)
+                        }
+                        BooleanTree::Equivalence(..) => {
+                            let differ = netlist.xor(a, b);
+                            netlist.not(differ)
+                        }
+                        BooleanTree::Nor(..) => {
+                            let either = netlist.or(a, b);
+                            netlist.not(either)
+                        }
+                        BooleanTree::ConverseImplication(..) => {
+                            let not_b = netlist.not(b);
+                            netlist.or(a, not_b)
+                        }
+                        _ => {
+                            let not_b = netlist.not(b);
+                            netlist.and(a, not_b)
+                        }
+                    }
+                }
+                BooleanTree::AndN(nodes) | BooleanTree::OrN(nodes) | BooleanTree::XorN(nodes) => {
+                    let gate = match tree {
+                        BooleanTree::AndN(_) => Gate::And,
+                        BooleanTree::OrN(_) => Gate::Or,
+                        _ => Gate::Xor,
+                    };
+                    let wires = nodes
+                        .iter()
+                        .map(|node| build(netlist, node))
+                        .collect::<Vec<_>>();
+                    match wires.split_first() {
+                        None => netlist.constant(matches!(tree, BooleanTree::AndN(_))),
+                        Some((&first, rest)) => rest
+                            .iter()
+                            .fold(first, |x, &y| netlist.add_gate(gate(x, y))),
+                    }
+                }
+                BooleanTree::Forall(..) | BooleanTree::Exists(..) => {
+                    unreachable!("quantifiers are expanded first")
+                }
+            }
+        }
+
+        let trees = formulas
+            .iter()
+            .map(|(_, tree)| {
+                let mut tree = tree.clone();
+                tree.expand_quantifiers();
+                tree
+            })
+            .collect::<Vec<_>>();
+        let mut variables = trees
+            .iter()
+            .flat_map(BooleanTree::get_variables)
+            .collect::<Vec<_>>();
+        variables.sort();
+        variables.dedup();
+        let mut netlist = Netlist::new();
+        for c in variables {
+            netlist.add_input(&c.to_string());
+        }
+        for ((name, _), tree) in formulas.iter().zip(&trees) {
+            let wire = build(&mut netlist, tree);
+            netlist.add_output(name, wire);
+        }
+        netlist
+    }
+
+    pub fn from_tree(tree: &BooleanTree, output: &str) -> Self {
+        Self::from_formulas(&[(output, tree.clone())])
+    }
+
+    // =====================================================
+    // ==================== EQUIVALENCE ====================
+    // =====================================================
+
+    /// Formula of an output, the inputs are named 'A', 'B', ... in order.
+    /// Shared wires are duplicated: the tree can be much larger than the netlist.
+    pub fn output_tree(&self, output: usize) -> Result<BooleanTree, &'static str> {
+        let output = self.outputs.get(output).ok_or("no such output")?;
+        if self.inputs.len() > 26 {
+            return Err("too many inputs to name them with letters");
+        }
+        let mut trees: HashMap<Wire, BooleanTree> = HashMap::new();
+        for (i, &wire) in self.inputs.iter().enumerate() {
+            trees.insert(wire, BooleanTree::Variable((b'A' + i as u8) as char));
+        }
+        let mut netlist = self.clone();
+        netlist.outputs = vec![output.clone()];
+        for wire in netlist.topological_order()? {
+            let tree = |wire: Wire| Box::new(trees[&wire].clone());
+            let node = match self.drivers[wire].unwrap() {
+                Driver::Input => continue,
+                Driver::Constant(b) => BooleanTree::Value(b),
+                Driver::Gate(Gate::And(a, b)) => BooleanTree::And(tree(a), tree(b)),
+                Driver::Gate(Gate::Or(a, b)) => BooleanTree::Or(tree(a), tree(b)),
+                Driver::Gate(Gate::Xor(a, b)) => BooleanTree::Xor(tree(a), tree(b)),
+                Driver::Gate(Gate::Not(a)) => BooleanTree::Not(tree(a)),
+                Driver::Gate(Gate::Nand(a, b)) => BooleanTree::Nand(tree(a), tree(b)),
+                Driver::Gate(Gate::Mux(s, a, b)) => BooleanTree::Ite(tree(s), tree(a), tree(b)),
+            };
+            trees.insert(wire, node);
+        }
+        Ok(trees.remove(&output.1).unwrap())
+    }
+
+    /// Tseitin transformation: variable `i + 1` is the `i`-th input,
+    /// returns the literal of each output
+    pub fn to_cnf(&self) -> Result<(Cnf, Vec<Literal>), &'static str> {
+        let mut cnf = Cnf {
+            num_variables: self.inputs.len(),
+            clauses: vec![],
+        };
+        let mut literals = vec![0; self.num_wires()];
+        for (i, &wire) in self.inputs.iter().enumerate() {
+            literals[wire] = i as Literal + 1;
+        }
+        for wire in self.topological_order()? {
+            literals[wire] = match self.drivers[wire].unwrap() {
+                Driver::Input => continue,
+                Driver::Constant(b) => {
+                    let v = cnf.new_variable();
+                    cnf.add_clause([if b { v } else { -v }]);
+                    v
+                }
+                Driver::Gate(Gate::And(a, b)) => cnf.and_gate(literals[a], literals[b]),
+                Driver::Gate(Gate::Or(a, b)) => cnf.or_gate(literals[a], literals[b]),
+                Driver::Gate(Gate::Xor(a, b)) => cnf.xor_gate(literals[a], literals[b]),
+                Driver::Gate(Gate::Not(a)) => -literals[a],
+                Driver::Gate(Gate::Nand(a, b)) => -cnf.and_gate(literals[a], literals[b]),
+                Driver::Gate(Gate::Mux(s, a, b)) => {
+                    cnf.ite_gate(literals[s], literals[a], literals[b])
+                }
+            };
+        }
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|&(_, wire)| literals[wire])
+            .collect();
+        Ok((cnf, outputs))
+    }
+
+    /// copies the gates into `target`, the inputs connected to `inputs`, returns the outputs
+    fn instantiate(
+        &self,
+        target: &mut Netlist,
+        inputs: &[Wire],
+    ) -> Result<Vec<Wire>, &'static str> {
+        let mut wires = vec![0; self.num_wires()];
+        for (&wire, &input) in self.inputs.iter().zip(inputs) {
+            wires[wire] = input;
+        }
+        for wire in self.topological_order()? {
+            wires[wire] = match self.drivers[wire].unwrap() {
+                Driver::Input => continue,
+                Driver::Constant(b) => target.constant(b),
+                Driver::Gate(gate) => target.add_gate(match gate {
+                    Gate::And(a, b) => Gate::And(wires[a], wires[b]),
+                    Gate::Or(a, b) => Gate::Or(wires[a], wires[b]),
+                    Gate::Xor(a, b) => Gate::Xor(wires[a], wires[b]),
+                    Gate::Not(a) => Gate::Not(wires[a]),
+                    Gate::Nand(a, b) => Gate::Nand(wires[a], wires[b]),
+                    Gate::Mux(s, a, b) => Gate::Mux(wires[s], wires[a], wires[b]),
+                }),
+            };
+        }
+        Ok(self.outputs.iter().map(|&(_, wire)| wires[wire]).collect())
+    }
+
+    /// Both circuits on shared inputs (matched by position, named after `self`),
+    /// with a single output `differ` set when any pair of outputs differs
+    pub fn miter(&self, other: &Netlist) -> Result<Netlist, &'static str> {
+        if self.inputs.len() != other.inputs.len() {
+            return Err("different numbers of inputs");
+        }
+        if self.outputs.len() != other.outputs.len() {
+            return Err("different numbers of outputs");
+        }
+        let mut miter = Netlist::new();
+        let inputs = self
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, &wire)| match self.name(wire) {
+                Some(name) => miter.add_input(name),
+                None => miter.add_input(&format!("i{i}")),
+            })
+            .collect::<Vec<_>>();
+        let outputs = self.instantiate(&mut miter, &inputs)?;
+        let other_outputs = other.instantiate(&mut miter, &inputs)?;
+        let mut differ = miter.constant(false);
+        for (x, y) in outputs.into_iter().zip(other_outputs) {
+            let different = miter.xor(x, y);
+            differ = miter.or(differ, different);
+        }
+        miter.add_output("differ", differ);
+        Ok(miter)
+    }
+
+    /// Proves with SAT that both circuits compute the same outputs on every input
+    pub fn check_equivalence(&self, other: &Netlist) -> Result<Equivalence, &'static str> {
+        let miter = self.miter(other)?;
+        let (mut cnf, outputs) = miter.to_cnf()?;
+        cnf.add_clause([outputs[0]]);
+        Ok(match cnf.solve() {
+            None => Equivalence::Equivalent,
+            Some(mut model) => {
+                model.truncate(self.inputs.len());
+                Equivalence::Counterexample(model)
+            }
+        })
+    }
+
+    // =====================================================
+    // ================= VERILOG AND BLIF ==================
+    // =====================================================
+
+    /// Names of the inputs and of the wires the outputs depend on. A wire without a name
+    /// takes the name of an output it drives when it is free, `n<wire>` otherwise.
+    fn signal_names(&self) -> Result<(Vec<Wire>, HashMap<Wire, String>), &'static str> {
+        let order = self.topological_order()?;
+        let taken = |name: &str| {
+            self.wire(name).is_some() || self.outputs.iter().any(|(output, _)| output == name)
+        };
+        let mut names = HashMap::new();
+        for &wire in self.inputs.iter().chain(&order) {
+            let name = match self.name(wire) {
+                Some(name) => name.to_string(),
+                None => match self
+                    .outputs
+                    .iter()
+                    .find(|(output, driver)| *driver == wire && self.wire(output).is_none())
+                {
+                    Some((output, _)) => output.clone(),
+                    None => {
+                        let mut name = format!("n{wire}");
+                        while taken(&name) {
+                            name.push('_');
+                        }
+                        name
+                    }
+                },
+            };
+            names.insert(wire, name);
+        }
+        for (output, wire) in &self.outputs {
+            if self
+                .wire(output)
+                .is_some_and(|other| other != *wire || self.inputs.contains(wire))
+            {
+                return Err("output name clashes with a wire name");
+            }
+        }
+        if names
+            .values()
+            .chain(self.outputs.iter().map(|(output, _)| output))
+            .any(|name| name.is_empty() || name.contains(char::is_whitespace))
+        {
+            return Err("names must be nonempty and without whitespace");
+        }
+        Ok((order, names))
+    }
+
+    /// Structural Verilog module with one primitive per gate,
+    /// multiplexers and constants are continuous assignments
+    pub fn to_verilog(&self, module: &str) -> Result<String, &'static str> {
+        /// escaped identifier when the name is not a plain one
+        fn identifier(name: &str) -> String {
+            const KEYWORDS: [&str; 16] = [
+                "and",
+                "assign",
+                "buf",
+                "end",
+                "endmodule",
+                "inout",
+                "input",
+                "module",
+                "nand",
+                "nor",
+                "not",
+                "or",
+                "output",
+                "reg",
+                "wire",
+                "xor",
+            ];
+            let mut chars = name.chars();
+            let plain = chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+                && !KEYWORDS.contains(&name);
+            if plain {
+                name.to_string()
+            } else {
+                format!("\\{name} ")
+            }
+        }
+
+        let (order, names) = self.signal_names()?;
+        let id = |wire: Wire| identifier(&names[&wire]);
+        let is_port = |wire: Wire| {
+            self.inputs.contains(&wire)
+                || self.outputs.iter().any(|(name, _)| *name == names[&wire])
+        };
+        let inputs = self.inputs.iter().map(|&wire| id(wire)).collect::<Vec<_>>();
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|(name, _)| identifier(name))
+            .collect::<Vec<_>>();
+        let internal = order
+            .iter()
+            .filter(|&&wire| !is_port(wire))
+            .map(|&wire| id(wire))
+            .collect::<Vec<_>>();
+
+        let mut verilog = format!(
+            "module {}({});\n",
+            identifier(module),
+            [&inputs[..], &outputs[..]].concat().join(", ")
+        );
+        for (keyword, signals) in [("input", inputs), ("output", outputs), ("wire", internal)] {
+            if !signals.is_empty() {
+                verilog += &format!("    {keyword} {};\n", signals.join(", "));
+            }
+        }
+        for &wire in &order {
+            let statement = match self.drivers[wire].unwrap() {
+                Driver::Input => continue,
+                Driver::Constant(b) => format!("assign {} = 1'b{};", id(wire), b as u8),
+                Driver::Gate(Gate::Mux(s, a, b)) => {
+                    format!("assign {} = {} ? {} : {};", id(wire), id(s), id(a), id(b))
+                }
+                Driver::Gate(gate) => {
+                    let primitive = match gate {
+                        Gate::And(..) => "and",
+                        Gate::Or(..) => "or",
+                        Gate::Xor(..) => "xor",
+                        Gate::Not(_) => "not",
+                        Gate::Nand(..) => "nand",
+                        Gate::Mux(..) => unreachable!(),
+                    };
+                    let terminals = std::iter::once(wire)
+                        .chain(gate.operands())
+                        .map(id)
+                        .collect::<Vec<_>>();
+                    format!("{primitive}({});", terminals.join(", "))
+                }
+            };
+            verilog += &format!("    {statement}\n");
+        }
+        for (name, wire) in &self.outputs {
+            if names[wire] != *name {
+                verilog += &format!("    assign {} = {};\n", identifier(name), id(*wire));
+            }
+        }
+        Ok(verilog + "endmodule\n")
+    }
+
+    /// BLIF model with one single-output cover (`.names`) per gate
+    pub fn to_blif(&self, model: &str) -> Result<String, &'static str> {
+        if model.is_empty() || model.contains(char::is_whitespace) {
+            return Err("names must be nonempty and without whitespace");
+        }
+        let (order, names) = self.signal_names()?;
+        let mut blif = format!(".model {model}\n");
+        if !self.inputs.is_empty() {
+            let inputs = self.inputs.iter().map(|wire| names[wire].as_str());
+            blif += &format!(".inputs {}\n", inputs.collect::<Vec<_>>().join(" "));
+        }
+        if !self.outputs.is_empty() {
+            let outputs = self.outputs.iter().map(|(name, _)| name.as_str());
+            blif += &format!(".outputs {}\n", outputs.collect::<Vec<_>>().join(" "));
+        }
+        for &wire in &order {
+            let (operands, cover) = match self.drivers[wire].unwrap() {
+                Driver::Input => continue,
+                Driver::Constant(b) => (vec![], if b { "1\n" } else { "" }),
+                Driver::Gate(gate) => (
+                    gate.operands(),
+                    match gate {
+                        Gate::And(..) => "11 1\n",
+                        Gate::Or(..) => "1- 1\n-1 1\n",
+                        Gate::Xor(..) => "10 1\n01 1\n",
+                        Gate::Not(_) => "0 1\n",
+                        Gate::Nand(..) => "0- 1\n-0 1\n",
+                        Gate::Mux(..) => "11- 1\n0-1 1\n",
+                    },
+                ),
+            };
+            let signals = operands.iter().chain([&wire]).map(|w| names[w].as_str());
+            blif += &format!(".names {}\n{cover}", signals.collect::<Vec<_>>().join(" "));
+        }
+        for (name, wire) in &self.outputs {
+            if names[wire] != *name {
+                blif += &format!(".names {} {name}\n1 1\n", names[wire]);
+            }
+        }
+        Ok(blif + ".end\n")
+    }
+
+    /// Reads the first model of a combinational BLIF file. Covers written by `to_blif`
+    /// become the same gate, buffers are merged with their operand,
+    /// and any other cover becomes a sum of products.
+    pub fn from_blif(blif: &str) -> Result<Self, &'static str> {
+        struct Cover<'a> {
+            operands: Vec<&'a str>,
+            /// input plane of each row, and whether the rows give the on-set or the off-set
+            rows: Vec<&'a str>,
+            on_set: bool,
+        }
+
+        // comments removed and continued lines joined
+        let mut lines = vec![];
+        let mut line = vec![];
+        for text in blif.lines() {
+            let text = text.split('#').next().unwrap();
+            let (text, continued) = match text.trim_end().strip_suffix('\\') {
+                Some(text) => (text, true),
+                None => (text, false),
+            };
+            line.extend(text.split_whitespace());
+            if !continued && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+        }
+
+        let (mut inputs, mut outputs) = (vec![], vec![]);
+        let mut covers: HashMap<&str, Cover> = HashMap::new();
+        let mut defined = vec![];
+        let mut has_model = false;
+        for tokens in lines {
+            match tokens[0] {
+                ".model" if has_model => break,
+                ".model" => has_model = true,
+                ".end" => break,
+                ".inputs" => inputs.extend(&tokens[1..]),
+                ".outputs" => outputs.extend(&tokens[1..]),
+                ".names" => {
+                    let (&output, operands) = tokens[1..].split_last().ok_or("missing signals")?;
+                    if covers.contains_key(output) || inputs.contains(&output) {
+                        return Err("signal defined twice");
+                    }
+                    let cover = Cover {
+                        operands: operands.to_vec(),
+                        rows: vec![],
+                        on_set: true,
+                    };
+                    covers.insert(output, cover);
+                    defined.push(output);
+                }
+                keyword if keyword.starts_with('.') => return Err("unsupported BLIF construct"),
+                _ => {
+                    let cover = defined
+                        .last()
+                        .and_then(|output| covers.get_mut(output))
+                        .ok_or("cover row outside of .names")?;
+                    let (plane, value) = match tokens[..] {
+                        [value] if cover.operands.is_empty() => ("", value),
+                        [plane, value] => (plane, value),
+                        _ => return Err("invalid cover row"),
+                    };
+                    if plane.len() != cover.operands.len()
+                        || !plane.chars().all(|c| "01-".contains(c))
+                    {
+                        return Err("invalid cover row");
+                    }
+                    let on_set = match value {
+                        "1" => true,
+                        "0" => false,
+                        _ => return Err("invalid cover row"),
+                    };
+                    if !cover.rows.is_empty() && cover.on_set != on_set {
+                        return Err("cover mixes on-set and off-set rows");
+                    }
+                    cover.rows.push(plane);
+                    cover.on_set = on_set;
+                }
+            }
+        }
+        if !has_model {
+            return Err("missing .model");
+        }
+
+        let mut netlist = Netlist::new();
+        let mut wires = HashMap::new();
+        for &input in &inputs {
+            if wires.contains_key(input) {
+                return Err("signal defined twice");
+            }
+            wires.insert(input, netlist.add_input(input));
+        }
+        // depth-first, a signal is built once all its operands are
+        let mut visiting = HashSet::new();
+        for &root in outputs.iter().chain(&defined) {
+            let mut stack = vec![root];
+            while let Some(&signal) = stack.last() {
+                if wires.contains_key(signal) {
+                    stack.pop();
+                    continue;
+                }
+                let cover = covers.get(signal).ok_or("undefined signal")?;
+                let pending = cover
+                    .operands
+                    .iter()
+                    .filter(|operand| !wires.contains_key(*operand))
+                    .collect::<Vec<_>>();
+                if pending.is_empty() {
+                    let operands = cover
+                        .operands
+                        .iter()
+                        .map(|operand| wires[operand])
+                        .collect::<Vec<_>>();
+                    let wire = netlist.add_cover(&operands, &cover.rows, cover.on_set);
+                    if netlist.name(wire).is_none() {
+                        netlist.set_name(wire, signal);
+                    }
+                    wires.insert(signal, wire);
+                    stack.pop();
+                } else if !visiting.insert(signal) {
+                    return Err("combinational loop");
+                } else {
+                    stack.extend(pending);
+                }
+            }
+        }
+        for (i, output) in outputs.iter().enumerate() {
+            if outputs[..i].contains(output) {
+                return Err("output listed twice");
+            }
+            netlist.add_output(output, wires[output]);
+        }
+        Ok(netlist)
+    }
+
+    /// Gates of a cover, `rows` are planes over `operands`
+    fn add_cover(&mut self, operands: &[Wire], rows: &[&str], on_set: bool) -> Wire {
+        let mut planes = rows.to_vec();
+        planes.sort();
+        match (operands, &planes[..], on_set) {
+            (_, [], _) => return self.constant(!on_set),
+            ([], _, _) => return self.constant(on_set),
+            (&[a], ["1"], true) => return a,
+            (&[a], ["0"], true) => return self.not(a),
+            (&[a, b], ["11"], true) => return self.and(a, b),
+            (&[a, b], ["-1", "1-"], true) => return self.or(a, b),
+            (&[a, b], ["01", "10"], true) => return self.xor(a, b),
+            (&[a, b], ["-0", "0-"], true) => return self.nand(a, b),
+            (&[s, a, b], ["0-1", "11-"], true) => return self.mux(s, a, b),
+            _ => {}
+        }
+        let mut negations = HashMap::new();
+        let mut products = vec![];
+        for plane in planes {
+            let mut literals = vec![];
+            for (c, &operand) in plane.chars().zip(operands) {
+                match c {
+                    '1' => literals.push(operand),
+                    '0' => literals.push(
+                        *negations
+                            .entry(operand)
+                            .or_insert_with(|| self.not(operand)),
+                    ),
+                    _ => {}
+                }
+            }
+            let product = match literals.split_first() {
+                None => self.constant(true),
+                Some((&first, rest)) => rest.iter().fold(first, |x, &y| self.and(x, y)),
+            };
+            products.push(product);
+        }
+        let sum = products[1..]
+            .iter()
+            .fold(products[0], |x, &y| self.or(x, y));
+        if on_set { sum } else { self.not(sum) }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Equivalence {
+    Equivalent,
+    /// values of the inputs for which some output differs
+    Counterexample(Vec<bool>),
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::numbers::{adder_with_flags, multiplier},
+        itertools::Itertools,
+        rand::Rng,
+    };
+
+    /// word `i` holds bit `i` of every value, value `k` in bit `k`
+    fn transpose(values: &[u64], width: u32) -> Vec<u64> {
+        (0..width)
+            .map(|i| {
+                values
+                    .iter()
+                    .enumerate()
+                    .fold(0, |word, (k, value)| word | (value >> i & 1) << k)
+            })
+            .collect()
+    }
+
+    fn check_arithmetic(netlist: &Netlist, width: u32, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut inputs = transpose(a, width);
+        inputs.extend(transpose(b, width));
+        let outputs = netlist.simulate_parallel(&inputs).unwrap();
+        (0..a.len())
+            .map(|k| {
+                outputs
+                    .iter()
+                    .enumerate()
+                    .fold(0, |value, (i, word)| value | (word >> k & 1) << i)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ripple_adder() {
+        let netlist = Netlist::ripple_adder(8);
+        assert_eq!(netlist.inputs().len(), 16);
+        assert_eq!(netlist.outputs().len(), 9);
+        // every pair of bytes, 64 at a time
+        let pairs = (0..=u8::MAX).flat_map(|a| (0..=u8::MAX).map(move |b| (a, b)));
+        for chunk in &pairs.chunks(64) {
+            let (a, b): (Vec<u8>, Vec<u8>) = chunk.unzip();
+            let widen = |x: &[u8]| x.iter().map(|&x| x as u64).collect::<Vec<_>>();
+            let results = check_arithmetic(&netlist, 8, &widen(&a), &widen(&b));
+            for ((a, b), result) in a.into_iter().zip(b).zip(results) {
+                let expected = adder_with_flags(a, b);
+                assert_eq!(result, expected.value as u64 | (expected.carry as u64) << 8);
+            }
+        }
+
+        let netlist = Netlist::ripple_adder(32);
+        let mut rng = rand::rng();
+        let a = (0..64)
+            .map(|_| rng.random::<u32>() as u64)
+            .collect::<Vec<_>>();
+        let b = (0..64)
+            .map(|_| rng.random::<u32>() as u64)
+            .collect::<Vec<_>>();
+        for ((a, b), result) in a.iter().zip(&b).zip(check_arithmetic(&netlist, 32, &a, &b)) {
+            assert_eq!(result, a + b);
+        }
+    }
+
+    #[test]
+    fn test_array_multiplier() {
+        let mut rng = rand::rng();
+        for width in [8, 16, 32] {
+            let netlist = Netlist::array_multiplier(width);
+            let mask = (1 << width) - 1;
+            let a = (0..64)
+                .map(|_| rng.random::<u64>() & mask)
+                .collect::<Vec<_>>();
+            let b = (0..64)
+                .map(|_| rng.random::<u64>() & mask)
+                .collect::<Vec<_>>();
+            let results = check_arithmetic(&netlist, width, &a, &b);
+            for ((&a, &b), result) in a.iter().zip(&b).zip(results) {
+                assert_eq!(result, multiplier(a, b) & mask, "{a} * {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_gates() {
+        let mut netlist = Netlist::new();
+        let (s, a, b) = (
+            netlist.add_input("s"),
+            netlist.add_input("a"),
+            netlist.add_input("b"),
+        );
+        let gates = [
+            ("and", Gate::And(a, b)),
+            ("or", Gate::Or(a, b)),
+            ("xor", Gate::Xor(a, b)),
+            ("not", Gate::Not(a)),
+            ("nand", Gate::Nand(a, b)),
+            ("mux", Gate::Mux(s, a, b)),
+        ];
+        for (name, gate) in gates {
+            let wire = netlist.add_gate(gate);
+            netlist.add_output(name, wire);
+        }
+        for mask in 0..8 {
+            let (s, a, b) = (mask & 4 != 0, mask & 2 != 0, mask & 1 != 0);
+            assert_eq!(
+                netlist.simulate(&[s, a, b]).unwrap(),
+                [a & b, a | b, a ^ b, !a, !(a & b), if s { a } else { b }]
+            );
+        }
+        assert_eq!(netlist.num_gates(), 6);
+        assert_eq!(netlist.wire("s"), Some(s));
+        assert_eq!(netlist.name(b), Some("b"));
+        assert!(netlist.simulate(&[true]).is_err());
+    }
+
+    #[test]
+    fn test_topological_order() {
+        // gates refer to wires driven afterwards
+        let mut netlist = Netlist::new();
+        let x = netlist.add_input("x");
+        let later = netlist.add_wire();
+        let out = netlist.and(x, later);
+        netlist.add_output("out", out);
+        assert_eq!(netlist.topological_order(), Err("undriven wire"));
+        netlist.drive(later, Gate::Not(x));
+        assert_eq!(netlist.simulate(&[true]).unwrap(), [false]);
+        let order = netlist.topological_order().unwrap();
+        let position = |wire| order.iter().position(|&w| w == wire).unwrap();
+        assert!(position(later) < position(out));
+
+        let mut netlist = Netlist::new();
+        let x = netlist.add_input("x");
+        let feedback = netlist.add_wire();
+        let out = netlist.or(x, feedback);
+        netlist.drive(feedback, Gate::Not(out));
+        netlist.add_output("out", out);
+        assert_eq!(netlist.simulate(&[true]), Err("combinational loop"));
+    }
+
+    #[test]
+    fn test_output_tree() {
+        let netlist = Netlist::ripple_adder(3);
+        for output in 0..netlist.outputs().len() {
+            let tree = netlist.output_tree(output).unwrap();
+            let (variables, inputs, outputs) = tree.compute_truth_table();
+            for (row, expected) in inputs.into_iter().zip(outputs) {
+                let mut values = vec![false; netlist.inputs().len()];
+                for (c, b) in variables.iter().zip(row) {
+                    values[(*c as u8 - b'A') as usize] = b;
+                }
+                assert_eq!(netlist.simulate(&values).unwrap()[output], expected);
+            }
+        }
+        let mut netlist = Netlist::new();
+        let (s, a, b) = (
+            netlist.add_input("s"),
+            netlist.add_input("a"),
+            netlist.add_input("b"),
+        );
+        let nand = netlist.nand(a, b);
+        let mux = netlist.mux(s, nand, b);
+        netlist.add_output("out", mux);
+        assert_eq!(netlist.output_tree(0).unwrap().to_formula(), "ABC↑C?");
+        assert!(Netlist::ripple_adder(14).output_tree(0).is_err());
+        assert_eq!(netlist.output_tree(1), Err("no such output"));
+    }
+
+    #[test]
+    fn test_equivalence() {
+        for width in [1, 4, 16, 32] {
+            let ripple = Netlist::ripple_adder(width);
+            let lookahead = Netlist::lookahead_adder(width);
+            assert_eq!(
+                ripple.check_equivalence(&lookahead),
+                Ok(Equivalence::Equivalent)
+            );
+        }
+
+        // s6 ignores its incoming carry
+        let mut buggy = Netlist::lookahead_adder(8);
+        let Some(Driver::Gate(Gate::Xor(propagate, _))) = buggy.driver(buggy.outputs()[6].1) else {
+            panic!("s6 is the xor of the propagate and the carry");
+        };
+        buggy.outputs[6].1 = propagate;
+        let ripple = Netlist::ripple_adder(8);
+        let Ok(Equivalence::Counterexample(inputs)) = ripple.check_equivalence(&buggy) else {
+            panic!("expected a counterexample");
+        };
+        assert_ne!(ripple.simulate(&inputs), buggy.simulate(&inputs));
+
+        assert!(ripple.check_equivalence(&Netlist::ripple_adder(4)).is_err());
+        assert!(
+            Netlist::ripple_adder(8)
+                .check_equivalence(&Netlist::array_multiplier(8))
+                .is_err()
+        );
+    }
+
+    fn check_same_outputs(x: &Netlist, y: &Netlist) {
+        let mut rng = rand::rng();
+        for _ in 0..8 {
+            let inputs = (0..x.inputs().len())
+                .map(|_| rng.random())
+                .collect::<Vec<u64>>();
+            assert_eq!(x.simulate_parallel(&inputs), y.simulate_parallel(&inputs));
+        }
+    }
+
+    fn half_adder() -> Netlist {
+        let mut netlist = Netlist::new();
+        let a = netlist.add_input("a");
+        let b = netlist.add_input("b");
+        let s = netlist.xor(a, b);
+        let c = netlist.and(a, b);
+        netlist.add_output("s", s);
+        netlist.add_output("c", c);
+        netlist
+    }
+
+    #[test]
+    fn test_from_formulas() {
+        let formulas = ["AB>C=", "AB↑C↓D!<", "ABC?", "AB^C∀", "AB↛"];
+        let trees = formulas
+            .iter()
+            .map(|formula| BooleanTree::new(formula, true).unwrap())
+            .collect::<Vec<_>>();
+        let named = ["f", "g", "h", "i", "j"].into_iter().zip(trees.clone());
+        let netlist = Netlist::from_formulas(&named.collect::<Vec<_>>());
+        assert_eq!(netlist.inputs().len(), 4);
+        for (k, tree) in trees.iter().enumerate() {
+            let (variables, rows, expected) = tree.compute_truth_table();
+            for (row, expected) in rows.into_iter().zip(expected) {
+                let mut values = vec![false; 4];
+                for (c, value) in variables.iter().zip(row) {
+                    values[(*c as u8 - b'A') as usize] = value;
+                }
+                assert_eq!(netlist.simulate(&values).unwrap()[k], expected);
+            }
+        }
+
+        let mut tree = BooleanTree::new("AB&C&", true).unwrap();
+        tree.normalize();
+        let netlist = Netlist::from_tree(&tree, "f");
+        assert_eq!(netlist.num_gates(), 2);
+        assert_eq!(netlist.simulate(&[true, true, true]), Ok(vec![true]));
+    }
+
+    #[test]
+    fn test_verilog() {
+        assert_eq!(
+            half_adder().to_verilog("half_adder").unwrap(),
+            "module half_adder(a, b, s, c);
+    input a, b;
+    output s, c;
+    xor(s, a, b);
+    and(c, a, b);
+endmodule
+"
+        );
+
+        let mut netlist = Netlist::new();
+        let s = netlist.add_input("s");
+        let a = netlist.add_input("a[0]");
+        let one = netlist.constant(true);
+        let mux = netlist.mux(s, a, one);
+        netlist.set_name(mux, "wire");
+        let not = netlist.not(mux);
+        netlist.add_output("y", not);
+        netlist.add_output("z", not);
+        netlist.add_output("a_copy", a);
+        assert_eq!(
+            netlist.to_verilog("top").unwrap(),
+            "module top(s, \\a[0] , y, z, a_copy);
+    input s, \\a[0] ;
+    output y, z, a_copy;
+    wire n2, \\wire ;
+    assign n2 = 1'b1;
+    assign \\wire  = s ? \\a[0]  : n2;
+    not(y, \\wire );
+    assign z = y;
+    assign a_copy = \\a[0] ;
+endmodule
+"
+        );
+
+        netlist.add_output("s", mux);
+        assert!(netlist.to_verilog("top").is_err());
+    }
+
+    #[test]
+    fn test_blif() {
+        assert_eq!(
+            half_adder().to_blif("half_adder").unwrap(),
+            ".model half_adder
+.inputs a b
+.outputs s c
+.names a b s
+10 1
+01 1
+.names a b c
+11 1
+.end
+"
+        );
+
+        let mut tree = BooleanTree::new("ABC?D↑E1^|", true).unwrap();
+        tree.expand_quantifiers();
+        let formulas = [("f", tree), ("g", BooleanTree::Value(false))];
+        for netlist in [
+            Netlist::ripple_adder(8),
+            Netlist::array_multiplier(4),
+            Netlist::lookahead_adder(16),
+            Netlist::from_formulas(&formulas),
+        ] {
+            let read = Netlist::from_blif(&netlist.to_blif("circuit").unwrap()).unwrap();
+            // gates no output depends on are not written
+            let used = netlist
+                .topological_order()
+                .unwrap()
+                .into_iter()
+                .filter(|&wire| matches!(netlist.driver(wire), Some(Driver::Gate(_))))
+                .count();
+            assert_eq!(read.num_gates(), used);
+            assert_eq!(read.outputs().len(), netlist.outputs().len());
+            check_same_outputs(&netlist, &read);
+            assert_eq!(
+                read.check_equivalence(&netlist),
+                Ok(Equivalence::Equivalent)
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_blif() {
+        let blif = "# majority and friends
+.model test
+.inputs x y \\
+    z
+.outputs maj any one x_copy
+.names t1 t2 t3 maj # defined before its operands
+1-- 1
+-1- 1
+--1 1
+.names x y t1
+11 1
+.names y z t2
+11 1
+.names x z t3
+11 1
+.names x y z any
+000 0
+.names one
+1
+.names x x_copy
+1 1
+.end
+.model ignored
+";
+        let netlist = Netlist::from_blif(blif).unwrap();
+        assert_eq!(netlist.wire("t2"), Some(4));
+        for mask in 0..8 {
+            let [x, y, z] = [0, 1, 2].map(|i| mask >> i & 1 == 1);
+            let majority = (x as u8 + y as u8 + z as u8) >= 2;
+            assert_eq!(
+                netlist.simulate(&[x, y, z]),
+                Ok(vec![majority, x || y || z, true, x])
+            );
+        }
+
+        for blif in [
+            ".inputs a\n.outputs b\n.names a b\n1 1\n",
+            ".model m\n.outputs a\n.names b a\n1 1\n.names a b\n1 1\n",
+            ".model m\n.outputs a\n.names b a\n1 1\n",
+            ".model m\n.inputs a\n.outputs b\n.latch a b 0\n",
+            ".model m\n.inputs a\n.outputs b\n.names a b\n1 1\n0 0\n",
+            ".model m\n.inputs a\n.outputs b\n.names a b\n11 1\n",
+            ".model m\n.inputs a\n.outputs b\n1 1\n.names a b\n",
+            ".model m\n.inputs a\n.outputs a\n.names a\n1\n",
+        ] {
+            assert!(Netlist::from_blif(blif).is_err(), "{blif}");
+        }
+    }
+}
@@ -1,27 +1,228 @@
-pub fn adder(a: u32, b: u32) -> u32 {
-    let mut res = 0;
-    let mut carry = 0;
-    for i in 0..32 {
-        let ai = (a >> i) & 1;
-        let bi = (b >> i) & 1;
-        res |= (ai ^ bi ^ carry) << i;
+use std::{
+    fmt::Debug,
+    ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr},
+};
+
+/// Unsigned machine word, the operands of the gate-level arithmetic below
+pub trait Word:
+    Copy
+    + Eq
+    + Debug
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    const BITS: u32;
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            const BITS: u32 = <$t>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+        }
+    )*};
+}
+
+impl_word!(u8, u16, u32, u64, u128);
+
+/// Wrapped result of an operation, with the unsigned carry-out
+/// and the overflow of the same operation on two's complement operands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Flagged<T> {
+    pub value: T,
+    pub carry: bool,
+    pub overflow: bool,
+}
+
+/// all ones if `bit` is 1, zero if it is 0
+fn mask<T: Word>(bit: T) -> T {
+    adder(!bit, T::ONE)
+}
+
+/// most significant bit, 1 for negative two's complement numbers
+fn sign<T: Word>(x: T) -> T {
+    x >> (T::BITS - 1)
+}
+
+/// `if bit { a } else { b }`, bit by bit
+fn select<T: Word>(bit: T, a: T, b: T) -> T {
+    (a & mask(bit)) | (b & !mask(bit))
+}
+
+fn ripple_carry<T: Word>(a: T, b: T, carry_in: T) -> Flagged<T> {
+    let mut res = T::ZERO;
+    let mut carry = carry_in;
+    let mut carry_into_sign = T::ZERO;
+    for i in 0..T::BITS {
+        let ai = (a >> i) & T::ONE;
+        let bi = (b >> i) & T::ONE;
+        res = res | (ai ^ bi ^ carry) << i;
+        carry_into_sign = carry;
         carry = (ai & bi) | (ai & carry) | (bi & carry);
     }
-    return res;
+    Flagged {
+        value: res,
+        carry: carry == T::ONE,
+        overflow: carry != carry_into_sign,
+    }
+}
+
+pub fn adder_with_flags<T: Word>(a: T, b: T) -> Flagged<T> {
+    ripple_carry(a, b, T::ZERO)
+}
+
+pub fn adder<T: Word>(a: T, b: T) -> T {
+    adder_with_flags(a, b).value
+}
+
+/// two's complement: `!a + 1`
+pub fn negator<T: Word>(a: T) -> T {
+    adder(!a, T::ONE)
+}
+
+/// `a + !b + 1` in a single pass, `carry` is the borrow (set when `a < b` as unsigned)
+pub fn subtractor_with_flags<T: Word>(a: T, b: T) -> Flagged<T> {
+    let flagged = ripple_carry(a, !b, T::ONE);
+    Flagged {
+        carry: !flagged.carry,
+        ..flagged
+    }
+}
+
+pub fn subtractor<T: Word>(a: T, b: T) -> T {
+    subtractor_with_flags(a, b).value
+}
+
+/// `carry` is set when the unsigned product does not fit in `T`
+pub fn multiplier_with_flags<T: Word>(a: T, b: T) -> Flagged<T> {
+    // shift-and-add into a double width accumulator
+    let mut low = T::ZERO;
+    let mut high = T::ZERO;
+    for i in 0..T::BITS {
+        let ai = (a >> i) & T::ONE;
+        let partial = b & mask(ai);
+        let sum = adder_with_flags(low, partial << i);
+        low = sum.value;
+        if i > 0 {
+            high = adder(high, partial >> (T::BITS - i));
+        }
+        if sum.carry {
+            high = adder(high, T::ONE);
+        }
+    }
+
+    // the signed product differs from the unsigned one by b * 2^n when a < 0 (and vice versa)
+    let signed_high = subtractor(subtractor(high, b & mask(sign(a))), a & mask(sign(b)));
+    Flagged {
+        value: low,
+        carry: high != T::ZERO,
+        overflow: signed_high != mask(sign(low)),
+    }
 }
 
-pub fn multiplier(a: u32, b: u32) -> u32 {
-    let mut res = 0;
-    for i in 0..32 {
-        let ai = (a >> i) & 1;
-        let mask = adder(!ai, 1);
-        res = adder(res, (b & mask) << i);
+pub fn multiplier<T: Word>(a: T, b: T) -> T {
+    let mut res = T::ZERO;
+    for i in 0..T::BITS {
+        let ai = (a >> i) & T::ONE;
+        res = adder(res, (b & mask(ai)) << i);
     }
     res
 }
 
-pub fn gray_code(n: u32) -> u32 {
-    n ^ n >> 1
+/// Long division: each step tries to subtract the divisor from the partial remainder
+/// and restores it when the result is negative. `None` when dividing by zero,
+/// otherwise the quotient and the remainder.
+pub fn restoring_divider<T: Word>(a: T, b: T) -> Option<(T, T)> {
+    if eq(b, T::ZERO) {
+        return None;
+    }
+    let mut quotient = T::ZERO;
+    let mut remainder = T::ZERO;
+    for i in (0..T::BITS).rev() {
+        // the bit shifted out makes the partial remainder larger than any divisor
+        let shifted_out = sign(remainder);
+        remainder = (remainder << 1) | ((a >> i) & T::ONE);
+        let difference = subtractor_with_flags(remainder, b);
+        let fits = shifted_out | if difference.carry { T::ZERO } else { T::ONE };
+        remainder = select(fits, difference.value, remainder);
+        quotient = quotient | fits << i;
+    }
+    Some((quotient, remainder))
+}
+
+/// Like `restoring_divider`, but a negative partial remainder is kept and the divisor
+/// is added back at the next step instead. The partial remainder needs two more bits
+/// than `T`, it is stored as a double width `(high, low)` pair.
+pub fn non_restoring_divider<T: Word>(a: T, b: T) -> Option<(T, T)> {
+    fn wide_adder<T: Word>((a_high, a_low): (T, T), (b_high, b_low): (T, T)) -> (T, T) {
+        let low = adder_with_flags(a_low, b_low);
+        let carry = if low.carry { T::ONE } else { T::ZERO };
+        (adder(adder(a_high, b_high), carry), low.value)
+    }
+
+    if eq(b, T::ZERO) {
+        return None;
+    }
+    let divisor = (T::ZERO, b);
+    let negated_divisor = wide_adder((!T::ZERO, !b), (T::ZERO, T::ONE));
+    let mut quotient = T::ZERO;
+    let mut remainder = (T::ZERO, T::ZERO);
+    for i in (0..T::BITS).rev() {
+        let is_negative = sign(remainder.0);
+        let shifted = (
+            (remainder.0 << 1) | sign(remainder.1),
+            (remainder.1 << 1) | ((a >> i) & T::ONE),
+        );
+        let term = (
+            select(is_negative, divisor.0, negated_divisor.0),
+            select(is_negative, divisor.1, negated_divisor.1),
+        );
+        remainder = wide_adder(shifted, term);
+        quotient = quotient | (sign(remainder.0) ^ T::ONE) << i;
+    }
+    if sign(remainder.0) == T::ONE {
+        remainder = wide_adder(remainder, divisor);
+    }
+    Some((quotient, remainder.1))
+}
+
+/// Two's complement division truncating toward zero, the remainder has the sign of `a`.
+/// `MIN / -1` wraps around to `MIN`.
+pub fn signed_divider<T: Word>(a: T, b: T) -> Option<(T, T)> {
+    let absolute = |x: T| select(sign(x), negator(x), x);
+    let (quotient, remainder) = restoring_divider(absolute(a), absolute(b))?;
+    Some((
+        select(sign(a) ^ sign(b), negator(quotient), quotient),
+        select(sign(a), negator(remainder), remainder),
+    ))
+}
+
+/// unsigned `a < b`: the subtraction borrows
+pub fn lt<T: Word>(a: T, b: T) -> bool {
+    subtractor_with_flags(a, b).carry
+}
+
+/// two's complement `a < b`: the difference is negative unless the subtraction overflows
+pub fn signed_lt<T: Word>(a: T, b: T) -> bool {
+    let difference = subtractor_with_flags(a, b);
+    (sign(difference.value) == T::ONE) != difference.overflow
+}
+
+/// no bit of `a ^ b` is set
+pub fn eq<T: Word>(a: T, b: T) -> bool {
+    let difference = a ^ b;
+    let mut any = T::ZERO;
+    for i in 0..T::BITS {
+        any = any | ((difference >> i) & T::ONE);
+    }
+    any == T::ZERO
 }
 
 #[cfg(test)]
@@ -30,7 +231,7 @@ mod tests {
 
     #[test]
     fn test_addition_table() {
-        for i in 0..10 {
+        for i in 0..10u32 {
             for j in 0..10 {
                 assert_eq!(adder(i, j), i + j);
             }
@@ -49,7 +250,7 @@ mod tests {
 
     #[test]
     fn test_multiplication_table() {
-        for i in 1..10 {
+        for i in 1..10u32 {
             for j in 3..10 {
                 println!("{i}*{j}={}", multiplier(i, j));
                 assert_eq!(multiplier(i, j), i * j);
@@ -69,28 +270,86 @@ mod tests {
         }
     }
 
-    #[test]
-    fn test_gray_code() {
-        assert_eq!(gray_code(0), 0);
-        assert_eq!(gray_code(1), 1);
-        assert_eq!(gray_code(2), 3);
-        assert_eq!(gray_code(3), 2);
-        assert_eq!(gray_code(4), 6);
-        assert_eq!(gray_code(5), 7);
-        assert_eq!(gray_code(6), 5);
-        assert_eq!(gray_code(7), 4);
-        assert_eq!(gray_code(8), 12);
-        assert_eq!(gray_code(9), 13);
-        assert_eq!(gray_code(10), 15);
-        assert_eq!(gray_code(11), 14);
-        assert_eq!(gray_code(12), 10);
-        assert_eq!(gray_code(13), 11);
-        assert_eq!(gray_code(14), 9);
-        assert_eq!(gray_code(15), 8);
-        assert_eq!(gray_code(16), 24);
-        assert_eq!(gray_code(17), 25);
-        assert_eq!(gray_code(18), 27);
-        assert_eq!(gray_code(19), 26);
-        assert_eq!(gray_code(20), 30);
+    macro_rules! width_tests {
+        ($($name:ident: $unsigned:ty, $signed:ty;)*) => {$(
+            mod $name {
+                use {super::super::*, rand::Rng};
+
+                fn operands() -> Vec<($unsigned, $unsigned)> {
+                    let edges = [0, 1, <$unsigned>::MAX, <$unsigned>::MAX >> 1, !(<$unsigned>::MAX >> 1)];
+                    let mut rng = rand::rng();
+                    let random = (0..200).map(|_| (rng.random(), rng.random()));
+                    edges
+                        .iter()
+                        .flat_map(|&a| edges.iter().map(move |&b| (a, b)))
+                        .chain(random)
+                        .collect()
+                }
+
+                #[test]
+                fn test_adder_with_flags() {
+                    for (a, b) in operands() {
+                        let (value, carry) = a.overflowing_add(b);
+                        let overflow = (a as $signed).overflowing_add(b as $signed).1;
+                        let expected = Flagged { value, carry, overflow };
+                        assert_eq!(adder_with_flags(a, b), expected, "{a} + {b}");
+                        assert_eq!(adder(a, b), value, "{a} + {b}");
+                    }
+                }
+
+                #[test]
+                fn test_subtractor_with_flags() {
+                    for (a, b) in operands() {
+                        let (value, carry) = a.overflowing_sub(b);
+                        let overflow = (a as $signed).overflowing_sub(b as $signed).1;
+                        let expected = Flagged { value, carry, overflow };
+                        assert_eq!(subtractor_with_flags(a, b), expected, "{a} - {b}");
+                        assert_eq!(subtractor(a, b), value, "{a} - {b}");
+                        assert_eq!(negator(b), b.wrapping_neg(), "-{b}");
+                    }
+                }
+
+                #[test]
+                fn test_dividers() {
+                    for (a, b) in operands() {
+                        let expected = (b != 0).then(|| (a / b, a % b));
+                        assert_eq!(restoring_divider(a, b), expected, "{a} / {b}");
+                        assert_eq!(non_restoring_divider(a, b), expected, "{a} / {b}");
+                        let (a, b) = (a as $signed, b as $signed);
+                        let expected = (b != 0)
+                            .then(|| (a.wrapping_div(b) as $unsigned, a.wrapping_rem(b) as $unsigned));
+                        assert_eq!(signed_divider(a as $unsigned, b as $unsigned), expected, "{a} / {b}");
+                    }
+                }
+
+                #[test]
+                fn test_comparators() {
+                    for (a, b) in operands().into_iter().chain(operands().into_iter().map(|(a, _)| (a, a))) {
+                        assert_eq!(lt(a, b), a < b, "{a} < {b}");
+                        assert_eq!(eq(a, b), a == b, "{a} == {b}");
+                        assert_eq!(signed_lt(a, b), (a as $signed) < (b as $signed), "{a} < {b}");
+                    }
+                }
+
+                #[test]
+                fn test_multiplier_with_flags() {
+                    for (a, b) in operands() {
+                        let (value, carry) = a.overflowing_mul(b);
+                        let overflow = (a as $signed).overflowing_mul(b as $signed).1;
+                        let expected = Flagged { value, carry, overflow };
+                        assert_eq!(multiplier_with_flags(a, b), expected, "{a} * {b}");
+                        assert_eq!(multiplier(a, b), value, "{a} * {b}");
+                    }
+                }
+            }
+        )*};
+    }
+
+    width_tests! {
+        width_8: u8, i8;
+        width_16: u16, i16;
+        width_32: u32, i32;
+        width_64: u64, i64;
+        width_128: u128, i128;
     }
 }
@@ -0,0 +1,316 @@
+//! Prenex quantified Boolean formulas: a quantifier prefix over a CNF matrix
+
+use crate::{
+    BooleanTree, Cnf,
+    cnf::{Clause, Literal, variable},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quantifier {
+    Forall,
+    Exists,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Qbf {
+    /// outermost block first, variables absent from the prefix are existential and outermost
+    pub prefix: Vec<(Quantifier, Vec<usize>)>,
+    pub matrix: Cnf,
+}
+
+fn push_variable(prefix: &mut Vec<(Quantifier, Vec<usize>)>, quantifier: Quantifier, v: usize) {
+    match prefix.last_mut() {
+        Some((last, block)) if *last == quantifier => block.push(v),
+        _ => prefix.push((quantifier, vec![v])),
+    }
+}
+
+impl Qbf {
+    /// The formula must be in prenex form: quantifiers on top of a quantifier-free matrix.
+    /// The matrix goes through the Tseitin transformation, its auxiliary variables
+    /// are existential and innermost.
+    pub fn from_tree(tree: &BooleanTree) -> Result<Self, &'static str> {
+        let mut quantified = vec![];
+        let mut matrix = tree;
+        while let BooleanTree::Forall(c, node) | BooleanTree::Exists(c, node) = matrix {
+            let quantifier = if matches!(matrix, BooleanTree::Forall(..)) {
+                Quantifier::Forall
+            } else {
+                Quantifier::Exists
+            };
+            quantified.push((quantifier, *c));
+            matrix = node;
+        }
+        if matrix.is_quantified() {
+            return Err("formula is not in prenex form");
+        }
+        for (i, (_, c)) in quantified.iter().enumerate() {
+            if quantified[..i].iter().any(|(_, other)| other == c) {
+                return Err("variable quantified twice");
+            }
+        }
+
+        let (cnf, variables) = Cnf::from_tree(matrix);
+        let mut prefix = vec![];
+        for (quantifier, c) in quantified {
+            if let Ok(i) = variables.binary_search(&c) {
+                push_variable(&mut prefix, quantifier, i + 1);
+            }
+        }
+        for v in variables.len() + 1..=cnf.num_variables {
+            push_variable(&mut prefix, Quantifier::Exists, v);
+        }
+        Ok(Qbf {
+            prefix,
+            matrix: cnf,
+        })
+    }
+
+    pub fn from_qdimacs(qdimacs: &str) -> Result<Self, &'static str> {
+        let mut prefix = vec![];
+        let mut dimacs = String::new();
+        let mut in_matrix = false;
+        for line in qdimacs.lines().map(str::trim) {
+            let quantifier = match line.split_whitespace().next() {
+                Some("a") => Quantifier::Forall,
+                Some("e") => Quantifier::Exists,
+                Some(token) => {
+                    if !token.starts_with('c') && !token.starts_with('p') {
+                        in_matrix = true;
+                    }
+                    dimacs += line;
+                    dimacs += "\n";
+                    continue;
+                }
+                None => continue,
+            };
+            if in_matrix {
+                return Err("quantifier line after clauses");
+            }
+            let mut terminated = false;
+            for token in line.split_whitespace().skip(1) {
+                if terminated {
+                    return Err("variable after end of quantifier line");
+                }
+                match token
+                    .parse::<usize>()
+                    .map_err(|_| "invalid quantified variable")?
+                {
+                    0 => terminated = true,
+                    v => push_variable(&mut prefix, quantifier, v),
+                }
+            }
+            if !terminated {
+                return Err("unterminated quantifier line");
+            }
+        }
+
+        let matrix = Cnf::from_dimacs(&dimacs)?;
+        let mut quantified = vec![false; matrix.num_variables + 1];
+        for &v in prefix.iter().flat_map(|(_, block)| block) {
+            if v > matrix.num_variables {
+                return Err("quantified variable out of range");
+            }
+            if quantified[v] {
+                return Err("variable quantified twice");
+            }
+            quantified[v] = true;
+        }
+        Ok(Qbf { prefix, matrix })
+    }
+
+    pub fn to_qdimacs(&self) -> String {
+        let dimacs = self.matrix.to_dimacs();
+        let (problem_line, clauses) = dimacs.split_once('\n').unwrap();
+        let mut qdimacs = format!("{problem_line}\n");
+        for (quantifier, block) in &self.prefix {
+            qdimacs += match quantifier {
+                Quantifier::Forall => "a",
+                Quantifier::Exists => "e",
+            };
+            for v in block {
+                qdimacs += &format!(" {v}");
+            }
+            qdimacs += " 0\n";
+        }
+        qdimacs + clauses
+    }
+
+    /// Recursive QDPLL: universal reduction, existential unit propagation,
+    /// then branching on an outermost variable of the remaining clauses
+    pub fn solve(&self) -> bool {
+        fn assign(clauses: &[Clause], literal: Literal) -> Vec<Clause> {
+            clauses
+                .iter()
+                .filter(|clause| !clause.contains(&literal))
+                .map(|clause| {
+                    clause
+                        .iter()
+                        .copied()
+                        .filter(|&lit| lit != -literal)
+                        .collect()
+                })
+                .collect()
+        }
+
+        fn qdpll(mut clauses: Vec<Clause>, depths: &[usize], quantifiers: &[Quantifier]) -> bool {
+            let is_existential = |lit: Literal| quantifiers[variable(lit)] == Quantifier::Exists;
+            loop {
+                for clause in &mut clauses {
+                    let max_depth = clause
+                        .iter()
+                        .filter(|&&lit| is_existential(lit))
+                        .map(|&lit| depths[variable(lit)])
+                        .max();
+                    clause.retain(|&lit| {
+                        is_existential(lit) || max_depth.is_some_and(|d| depths[variable(lit)] < d)
+                    });
+                }
+                if clauses.iter().any(Vec::is_empty) {
+                    return false;
+                }
+                if clauses.is_empty() {
+                    return true;
+                }
+                // after universal reduction, unit clauses are existential
+                match clauses.iter().find(|clause| clause.len() == 1) {
+                    Some(unit) => clauses = assign(&clauses, unit[0]),
+                    None => break,
+                }
+            }
+
+            let v = clauses
+                .iter()
+                .flatten()
+                .map(|&lit| variable(lit))
+                .min_by_key(|&v| depths[v])
+                .unwrap() as Literal;
+            let branch = |lit: Literal| qdpll(assign(&clauses, lit), depths, quantifiers);
+            match quantifiers[v as usize] {
+                Quantifier::Exists => branch(v) || branch(-v),
+                Quantifier::Forall => branch(v) && branch(-v),
+            }
+        }
+
+        let n = self.matrix.num_variables;
+        let mut depths = vec![0; n + 1];
+        let mut quantifiers = vec![Quantifier::Exists; n + 1];
+        for (i, (quantifier, block)) in self.prefix.iter().enumerate() {
+            for &v in block {
+                depths[v] = i + 1;
+                quantifiers[v] = *quantifier;
+            }
+        }
+        let clauses = self
+            .matrix
+            .clauses
+            .iter()
+            .filter(|clause| !clause.iter().any(|lit| clause.contains(&-lit)))
+            .map(|clause| {
+                let mut clause = clause.clone();
+                clause.sort_unstable();
+                clause.dedup();
+                clause
+            })
+            .collect();
+        qdpll(clauses, &depths, &quantifiers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::Rng};
+
+    fn random_tree(rng: &mut impl Rng, depth: usize) -> BooleanTree {
+        if depth == 0 || rng.random_ratio(1, 4) {
+            return BooleanTree::Variable(rng.random_range('A'..='D'));
+        }
+        let node1 = Box::new(random_tree(rng, depth - 1));
+        let node2 = Box::new(random_tree(rng, depth - 1));
+        match rng.random_range(0..7) {
+            0 => BooleanTree::Not(node1),
+            1 => BooleanTree::Or(node1, node2),
+            2 => BooleanTree::And(node1, node2),
+            3 => BooleanTree::Xor(node1, node2),
+            4 => BooleanTree::Implication(node1, node2),
+            5 => BooleanTree::Equivalence(node1, node2),
+            _ => BooleanTree::Ite(node1, node2, Box::new(random_tree(rng, depth - 1))),
+        }
+    }
+
+    #[test]
+    fn test_from_tree() {
+        let mut rng = rand::rng();
+        for _ in 0..200 {
+            let mut tree = random_tree(&mut rng, 5);
+            let mut variables = vec!['A', 'B', 'C', 'D'];
+            while let Some(c) = variables.pop() {
+                tree = if rng.random() {
+                    BooleanTree::Forall(c, Box::new(tree))
+                } else {
+                    BooleanTree::Exists(c, Box::new(tree))
+                };
+            }
+            let qbf = Qbf::from_tree(&tree).unwrap();
+            assert_eq!(qbf.solve(), tree.evaluate(), "{}", tree.to_infix());
+        }
+    }
+
+    #[test]
+    fn test_from_tree_errors() {
+        let not_prenex = BooleanTree::from_infix("∀A. A & ∃B. B").unwrap();
+        assert!(Qbf::from_tree(&not_prenex).is_err());
+        let not_prenex = BooleanTree::from_infix("∀A. (∃B. B) & A").unwrap();
+        assert!(Qbf::from_tree(&not_prenex).is_err());
+        let twice = BooleanTree::from_infix("∀A. ∃A. A").unwrap();
+        assert!(Qbf::from_tree(&twice).is_err());
+    }
+
+    #[test]
+    fn test_free_variables() {
+        // free variables are existential
+        let tree = BooleanTree::from_infix("∀A. A | B").unwrap();
+        assert!(Qbf::from_tree(&tree).unwrap().solve());
+        let tree = BooleanTree::from_infix("∀A. A & B").unwrap();
+        assert!(!Qbf::from_tree(&tree).unwrap().solve());
+        let tree = BooleanTree::from_infix("∀A. A ^ B ^ !A").unwrap();
+        assert!(Qbf::from_tree(&tree).unwrap().solve());
+    }
+
+    #[test]
+    fn test_qdimacs() {
+        // ∀x ∃y. x = y is true, ∃y ∀x. x = y is false
+        let true_qbf = "c example\np cnf 2 2\na 1 0\ne 2 0\n-1 2 0\n1 -2 0\n";
+        let false_qbf = "p cnf 2 2\ne 2 0\na 1 0\n-1 2 0\n1 -2 0\n";
+        let qbf = Qbf::from_qdimacs(true_qbf).unwrap();
+        assert_eq!(
+            qbf.prefix,
+            vec![(Quantifier::Forall, vec![1]), (Quantifier::Exists, vec![2])]
+        );
+        assert!(qbf.solve());
+        assert!(!Qbf::from_qdimacs(false_qbf).unwrap().solve());
+        assert_eq!(Qbf::from_qdimacs(&qbf.to_qdimacs()).unwrap(), qbf);
+    }
+
+    #[test]
+    fn test_qdimacs_errors() {
+        assert!(Qbf::from_qdimacs("p cnf 2 1\na 1\n1 2 0\n").is_err());
+        assert!(Qbf::from_qdimacs("p cnf 2 1\na 3 0\n1 2 0\n").is_err());
+        assert!(Qbf::from_qdimacs("p cnf 2 1\na 1 0\ne 1 0\n1 2 0\n").is_err());
+        assert!(Qbf::from_qdimacs("p cnf 2 1\n1 2 0\na 1 0\n").is_err());
+        assert!(Qbf::from_qdimacs("p cnf 2 1\na x 0\n1 2 0\n").is_err());
+    }
+
+    #[test]
+    fn test_universal_reduction() {
+        // ∃x ∀y. (x | y) & (!x | y) is false because y can be false
+        let qbf = Qbf::from_qdimacs("p cnf 2 2\ne 1 0\na 2 0\n1 2 0\n-1 2 0\n").unwrap();
+        assert!(!qbf.solve());
+        // ∀y ∃x. (x | y) & (!x | !y) is true
+        let qbf = Qbf::from_qdimacs("p cnf 2 2\na 2 0\ne 1 0\n1 2 0\n-1 -2 0\n").unwrap();
+        assert!(qbf.solve());
+        // tautologies are not reduced to empty clauses
+        let qbf = Qbf::from_qdimacs("p cnf 1 1\na 1 0\n1 -1 0\n").unwrap();
+        assert!(qbf.solve());
+    }
+}
@@ -0,0 +1,366 @@
+//! Binary relations on a finite universe, one bitset row per element
+
+use {
+    crate::{BitSet, DenseSet},
+    itertools::Itertools,
+};
+
+/// Set of pairs of elements of `universe`, `rows[i]` holding `j` when
+/// `universe[i]` is related to `universe[j]`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Relation<T> {
+    /// sorted, without duplicates
+    universe: Vec<T>,
+    rows: Vec<BitSet>,
+}
+
+impl<T: Ord + Clone> Relation<T> {
+    fn empty(universe: Vec<T>) -> Self {
+        let universe = universe.into_iter().sorted().dedup().collect_vec();
+        let rows = vec![BitSet::new(universe.len()); universe.len()];
+        Self { universe, rows }
+    }
+
+    pub fn new(
+        universe: impl IntoIterator<Item = T>,
+        pairs: impl IntoIterator<Item = (T, T)>,
+    ) -> Result<Self, &'static str> {
+        let mut relation = Self::empty(universe.into_iter().collect());
+        for (a, b) in pairs {
+            let (Some(i), Some(j)) = (relation.index(&a), relation.index(&b)) else {
+                return Err("pair out of the universe");
+            };
+            relation.rows[i].insert(j);
+        }
+        Ok(relation)
+    }
+
+    pub fn from_fn(
+        universe: impl IntoIterator<Item = T>,
+        related: impl Fn(&T, &T) -> bool,
+    ) -> Self {
+        let mut relation = Self::empty(universe.into_iter().collect());
+        for (i, a) in relation.universe.iter().enumerate() {
+            for (j, b) in relation.universe.iter().enumerate() {
+                if related(a, b) {
+                    relation.rows[i].insert(j);
+                }
+            }
+        }
+        relation
+    }
+
+    pub fn identity(universe: impl IntoIterator<Item = T>) -> Self {
+        Self::from_fn(universe, |a, b| a == b)
+    }
+
+    fn index(&self, x: &T) -> Option<usize> {
+        self.universe.binary_search(x).ok()
+    }
+
+    /// elements of the universe, sorted
+    pub fn universe(&self) -> &[T] {
+        &self.universe
+    }
+
+    pub fn contains(&self, a: &T, b: &T) -> bool {
+        match (self.index(a), self.index(b)) {
+            (Some(i), Some(j)) => self.rows[i].contains(j),
+            _ => false,
+        }
+    }
+
+    /// number of pairs
+    pub fn len(&self) -> usize {
+        self.rows.iter().map(BitSet::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// pairs in lexicographic order
+    pub fn pairs(&self) -> Vec<(T, T)> {
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.indices()
+                    .into_iter()
+                    .map(move |j| (self.universe[i].clone(), self.universe[j].clone()))
+            })
+            .collect()
+    }
+
+    /// elements related to `a`
+    pub fn image(&self, a: &T) -> Vec<T> {
+        self.index(a).map_or(vec![], |i| {
+            self.rows[i]
+                .indices()
+                .into_iter()
+                .map(|j| self.universe[j].clone())
+                .collect()
+        })
+    }
+
+    fn with_rows(&self, rows: Vec<BitSet>) -> Self {
+        Self {
+            universe: self.universe.clone(),
+            rows,
+        }
+    }
+
+    fn assert_same_universe(&self, other: &Self) {
+        assert!(
+            self.universe == other.universe,
+            "relations on different universes"
+        );
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.assert_same_universe(other);
+        self.with_rows(
+            self.rows
+                .iter()
+                .zip(&other.rows)
+                .map(|(a, b)| a.union(b))
+                .collect(),
+        )
+    }
+
+    /// `a` is related to `c` when `a` is related to some `b` by `self` and `b` to `c` by `other`
+    pub fn compose(&self, other: &Self) -> Self {
+        self.assert_same_universe(other);
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut composed = BitSet::new(self.universe.len());
+                for b in row.indices() {
+                    composed.union_with(&other.rows[b]);
+                }
+                composed
+            })
+            .collect();
+        self.with_rows(rows)
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut rows = vec![BitSet::new(self.universe.len()); self.universe.len()];
+        for (i, row) in self.rows.iter().enumerate() {
+            for j in row.indices() {
+                rows[j].insert(i);
+            }
+        }
+        self.with_rows(rows)
+    }
+
+    // =====================================================
+    // ===================== CLOSURES ======================
+    // =====================================================
+
+    pub fn reflexive_closure(&self) -> Self {
+        self.union(&Self::identity(self.universe.iter().cloned()))
+    }
+
+    pub fn symmetric_closure(&self) -> Self {
+        self.union(&self.inverse())
+    }
+
+    /// Warshall's algorithm, a whole row at a time: once every path through the
+    /// first `k` elements is known, the elements reaching `k` reach what `k` reaches
+    pub fn transitive_closure(&self) -> Self {
+        let mut rows = self.rows.clone();
+        for k in 0..rows.len() {
+            let through = rows[k].clone();
+            for row in rows.iter_mut() {
+                if row.contains(k) {
+                    row.union_with(&through);
+                }
+            }
+        }
+        self.with_rows(rows)
+    }
+
+    /// smallest equivalence relation containing the relation
+    pub fn equivalence_closure(&self) -> Self {
+        self.reflexive_closure()
+            .symmetric_closure()
+            .transitive_closure()
+    }
+
+    // =====================================================
+    // ===================== PROPERTIES ====================
+    // =====================================================
+
+    pub fn is_reflexive(&self) -> bool {
+        self.rows.iter().enumerate().all(|(i, row)| row.contains(i))
+    }
+
+    pub fn is_symmetric(&self) -> bool {
+        *self == self.inverse()
+    }
+
+    pub fn is_antisymmetric(&self) -> bool {
+        let inverse = self.inverse();
+        self.rows
+            .iter()
+            .zip(&inverse.rows)
+            .enumerate()
+            .all(|(i, (row, column))| row.intersection(column).indices().iter().all(|&j| j == i))
+    }
+
+    pub fn is_transitive(&self) -> bool {
+        self.compose(self)
+            .rows
+            .iter()
+            .zip(&self.rows)
+            .all(|(composed, row)| composed.intersection(row) == *composed)
+    }
+
+    pub fn is_partial_order(&self) -> bool {
+        self.is_reflexive() && self.is_antisymmetric() && self.is_transitive()
+    }
+
+    pub fn is_equivalence(&self) -> bool {
+        self.is_reflexive() && self.is_symmetric() && self.is_transitive()
+    }
+
+    /// Equivalence classes, each one sorted, in the order of their smallest element
+    pub fn quotient(&self) -> Result<Vec<Vec<T>>, &'static str> {
+        if !self.is_equivalence() {
+            return Err("not an equivalence relation");
+        }
+        let mut classified = BitSet::new(self.universe.len());
+        let mut classes = vec![];
+        for (i, row) in self.rows.iter().enumerate() {
+            if !classified.contains(i) {
+                classified.union_with(row);
+                classes.push(
+                    row.indices()
+                        .into_iter()
+                        .map(|j| self.universe[j].clone())
+                        .collect(),
+                );
+            }
+        }
+        Ok(classes)
+    }
+
+    /// Covering pairs of a partial order: `a < b` with nothing strictly in between
+    pub fn hasse_diagram(&self) -> Result<Vec<(T, T)>, &'static str> {
+        if !self.is_partial_order() {
+            return Err("not a partial order");
+        }
+        let strict = self
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut row = row.clone();
+                row.remove(i);
+                row
+            })
+            .collect_vec();
+        let covers = strict
+            .iter()
+            .map(|row| {
+                // elements above an element above
+                let mut above = BitSet::new(self.universe.len());
+                for c in row.indices() {
+                    above.union_with(&strict[c]);
+                }
+                row.intersection(&above.complement())
+            })
+            .collect();
+        Ok(self.with_rows(covers).pairs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::powerset};
+
+    #[test]
+    fn test_relation() {
+        let relation = Relation::new(1..=4, [(1, 2), (2, 3), (3, 3), (4, 1)]).unwrap();
+        assert_eq!(relation.len(), 4);
+        assert!(
+            relation.contains(&1, &2) && !relation.contains(&2, &1) && !relation.contains(&5, &1)
+        );
+        assert_eq!(relation.image(&2), [3]);
+        assert_eq!(relation.inverse().pairs(), [(1, 4), (2, 1), (3, 2), (3, 3)]);
+        assert_eq!(
+            relation.compose(&relation).pairs(),
+            [(1, 3), (2, 3), (3, 3), (4, 2)]
+        );
+        assert_eq!(
+            relation.transitive_closure().pairs(),
+            [(1, 2), (1, 3), (2, 3), (3, 3), (4, 1), (4, 2), (4, 3)]
+        );
+        assert!(relation.transitive_closure().is_transitive());
+        assert!(!relation.is_transitive());
+        assert!(relation.reflexive_closure().is_reflexive());
+        assert!(relation.symmetric_closure().is_symmetric());
+        assert_eq!(
+            Relation::new(1..=2, [(1, 3)]),
+            Err("pair out of the universe")
+        );
+
+        // Warshall against repeated composition
+        let step = Relation::from_fn(0..12, |a, b| (a * 5 + 3) % 12 == *b || a / 3 == b + 1);
+        let mut closure = step.clone();
+        loop {
+            let next = closure.union(&closure.compose(&step));
+            if next == closure {
+                break;
+            }
+            closure = next;
+        }
+        assert_eq!(step.transitive_closure(), closure);
+    }
+
+    #[test]
+    fn test_quotient() {
+        let congruence = Relation::from_fn(0..10, |a, b| a % 3 == b % 3);
+        assert!(congruence.is_equivalence());
+        assert_eq!(
+            congruence.quotient(),
+            Ok(vec![vec![0, 3, 6, 9], vec![1, 4, 7], vec![2, 5, 8]])
+        );
+        let links = Relation::new("abcdef".chars(), [('a', 'c'), ('d', 'e'), ('e', 'a')]).unwrap();
+        assert_eq!(links.quotient(), Err("not an equivalence relation"));
+        assert_eq!(
+            links.equivalence_closure().quotient(),
+            Ok(vec![vec!['a', 'c', 'd', 'e'], vec!['b'], vec!['f']])
+        );
+    }
+
+    #[test]
+    fn test_hasse_diagram() {
+        let divides = Relation::from_fn(1..=12, |a, b| b % a == 0);
+        assert!(divides.is_partial_order());
+        assert!(!divides.is_equivalence());
+        assert_eq!(
+            divides.hasse_diagram().unwrap()[..6],
+            [(1, 2), (1, 3), (1, 5), (1, 7), (1, 11), (2, 4)]
+        );
+        assert_eq!(
+            Relation::from_fn(0..4, |a, b| a != b).hasse_diagram(),
+            Err("not a partial order")
+        );
+
+        // the subset lattice: each cover adds a single element
+        let subsets = Relation::from_fn(powerset(vec![1, 2, 3, 4]), |a, b| {
+            a.iter().all(|x| b.contains(x))
+        });
+        assert!(subsets.is_partial_order());
+        let covers = subsets.hasse_diagram().unwrap();
+        assert_eq!(covers.len(), 4 << 3);
+        assert!(
+            covers
+                .iter()
+                .all(|(a, b)| { b.len() == a.len() + 1 && a.iter().all(|x| b.contains(x)) })
+        );
+    }
+}
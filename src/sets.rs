@@ -1,33 +1,67 @@
 use {
+    crate::{BitSet, BooleanTree, Cnf, DenseSet, GraySubsets, RevolvingDoor, RoaringSet},
     itertools::Itertools,
     std::{
-        collections::{HashMap, HashSet},
-        sync::LazyLock,
+        collections::{BTreeSet, HashMap, HashSet},
+        hash::Hash,
     },
 };
 
 // &HashSet would make more sense as input but gotta respect the subject
 pub fn powerset(set: Vec<i32>) -> Vec<Vec<i32>> {
-    fn _powerset(set: &Vec<i32>, i: usize, current: &mut Vec<i32>, output: &mut Vec<Vec<i32>>) {
+    powerset_generic(set)
+}
+
+/// Subsets in the order of `powerset`, the elements in the order of `set`
+pub fn powerset_generic<T: Clone, I: IntoIterator<Item = T>>(set: I) -> Vec<Vec<T>> {
+    fn _powerset<T: Clone>(set: &[T], i: usize, current: &mut Vec<T>, output: &mut Vec<Vec<T>>) {
         if i == set.len() {
             output.push(current.clone());
             return;
         }
         _powerset(set, i + 1, current, output);
-        current.push(set[i]);
+        current.push(set[i].clone());
         _powerset(set, i + 1, current, output);
         current.pop();
     }
 
+    let set = set.into_iter().collect_vec();
     let mut output = vec![];
     _powerset(&set, 0, &mut vec![], &mut output);
     output
 }
 
-type SetOperation = fn(&HashSet<i32>, HashSet<i32>, HashSet<i32>) -> HashSet<i32>;
+pub fn powerset_of_set<T: Ord + Clone>(set: &BTreeSet<T>) -> Vec<BTreeSet<T>> {
+    powerset_generic(set.iter().cloned())
+        .into_iter()
+        .map(BTreeSet::from_iter)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowersetOrder {
+    /// the order of `powerset`: counting in binary, the first element being the highest bit
+    Binary,
+    /// each subset differs from the previous one by a single element
+    Gray,
+    /// sorted by their sequences of elements, a subset coming right before its extensions
+    Lexicographic,
+}
 
-static SET_OPERATIONS: LazyLock<HashMap<char, SetOperation>> = LazyLock::new(|| {
-    fn union_(_: &HashSet<i32>, mut a: HashSet<i32>, mut b: HashSet<i32>) -> HashSet<i32> {
+pub fn powerset_in_order(set: Vec<i32>, order: PowersetOrder) -> Vec<Vec<i32>> {
+    match order {
+        PowersetOrder::Binary => powerset(set),
+        PowersetOrder::Gray => GraySubsets::new(set.len())
+            .map(|(indices, _)| indices.into_iter().map(|i| set[i]).collect())
+            .collect(),
+        PowersetOrder::Lexicographic => Powerset::new(set, order).collect(),
+    }
+}
+
+type SetOperation<T> = fn(&HashSet<T>, HashSet<T>, HashSet<T>) -> HashSet<T>;
+
+fn set_operation<T: Eq + Hash + Clone>(c: char) -> Option<SetOperation<T>> {
+    fn union_<T: Eq + Hash>(_: &HashSet<T>, mut a: HashSet<T>, mut b: HashSet<T>) -> HashSet<T> {
         if a.len() > b.len() {
             a.extend(b);
             a
@@ -37,7 +71,11 @@ static SET_OPERATIONS: LazyLock<HashMap<char, SetOperation>> = LazyLock::new(||
         }
     }
 
-    fn intersection(_: &HashSet<i32>, mut a: HashSet<i32>, mut b: HashSet<i32>) -> HashSet<i32> {
+    fn intersection<T: Eq + Hash>(
+        _: &HashSet<T>,
+        mut a: HashSet<T>,
+        mut b: HashSet<T>,
+    ) -> HashSet<T> {
         if a.len() < b.len() {
             a.retain(|x| b.contains(x));
             a
@@ -47,11 +85,11 @@ static SET_OPERATIONS: LazyLock<HashMap<char, SetOperation>> = LazyLock::new(||
         }
     }
 
-    fn symmetric_difference(
-        _: &HashSet<i32>,
-        mut a: HashSet<i32>,
-        mut b: HashSet<i32>,
-    ) -> HashSet<i32> {
+    fn symmetric_difference<T: Eq + Hash>(
+        _: &HashSet<T>,
+        mut a: HashSet<T>,
+        mut b: HashSet<T>,
+    ) -> HashSet<T> {
         if a.len() < b.len() {
             [a, b] = [b, a];
         }
@@ -65,82 +103,877 @@ static SET_OPERATIONS: LazyLock<HashMap<char, SetOperation>> = LazyLock::new(||
         a
     }
 
-    fn equivalence(universe: &HashSet<i32>, a: HashSet<i32>, b: HashSet<i32>) -> HashSet<i32> {
+    fn equivalence<T: Eq + Hash + Clone>(
+        universe: &HashSet<T>,
+        a: HashSet<T>,
+        b: HashSet<T>,
+    ) -> HashSet<T> {
         universe
             .iter()
-            .filter(|x| a.contains(&x) == b.contains(&x))
-            .copied()
-            .collect::<HashSet<i32>>()
+            .filter(|x| a.contains(x) == b.contains(x))
+            .cloned()
+            .collect::<HashSet<T>>()
     }
 
-    fn material_condition(
-        universe: &HashSet<i32>,
-        a: HashSet<i32>,
-        b: HashSet<i32>,
-    ) -> HashSet<i32> {
+    fn material_condition<T: Eq + Hash + Clone>(
+        universe: &HashSet<T>,
+        a: HashSet<T>,
+        b: HashSet<T>,
+    ) -> HashSet<T> {
         universe
             .iter()
-            .filter(|x| !a.contains(&x) || b.contains(&x))
-            .copied()
-            .collect::<HashSet<i32>>()
+            .filter(|x| !a.contains(x) || b.contains(x))
+            .cloned()
+            .collect::<HashSet<T>>()
     }
 
-    HashMap::from([
-        ('|', union_ as SetOperation),
-        ('&', intersection as SetOperation),
-        ('^', symmetric_difference as SetOperation),
-        ('=', equivalence as SetOperation),
-        ('>', material_condition as SetOperation),
-    ])
-});
+    match c {
+        '|' => Some(union_),
+        '&' => Some(intersection),
+        '^' => Some(symmetric_difference),
+        '=' => Some(equivalence),
+        '>' => Some(material_condition),
+        _ => None,
+    }
+}
 
-pub fn eval_set(formula: &str, sets: Vec<Vec<i32>>) -> Vec<i32> {
-    let universe: HashSet<i32> =
-        HashSet::from_iter(sets.iter().flat_map(|set| set.iter().copied()));
-    let sets: Vec<HashSet<i32>> = sets
-        .into_iter()
-        .map(|v| HashSet::from_iter(v.iter().copied()))
-        .collect_vec();
+/// Complements, `=` and `>` are taken in `universe`
+fn try_evaluate<T: Eq + Hash + Clone>(
+    formula: &str,
+    sets: &[HashSet<T>],
+    universe: &HashSet<T>,
+) -> Result<HashSet<T>, &'static str> {
+    let steps = compile_pointwise(formula, "|&^=>", sets.len())?;
+    Ok(eval_pointwise(
+        &steps,
+        |i| sets[i].clone(),
+        |set| universe.difference(&set).cloned().collect(),
+        |c, a, b| set_operation(c).unwrap()(universe, a, b),
+    ))
+}
 
-    let mut stack = vec![];
+/// Step of a formula applied element by element, to multiplicities or degrees
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PointwiseStep {
+    Set(usize),
+    Not,
+    Binary(char),
+}
+
+/// Checks a formula once for every evaluator, `binary` holding the accepted operators
+pub(crate) fn compile_pointwise(
+    formula: &str,
+    binary: &str,
+    num_sets: usize,
+) -> Result<Vec<PointwiseStep>, &'static str> {
+    let mut steps = vec![];
+    let mut depth = 0;
     for c in formula.chars() {
         if c.is_ascii_uppercase() {
             let i = c as usize - 'A' as usize;
-            if i >= sets.len() {
-                panic!(
-                    "got set {c} (index {i}) but only {} set(s) available",
-                    sets.len()
-                );
+            if i >= num_sets {
+                return Err("formula refers to a set that was not given");
             }
-            stack.push(sets[i].clone());
+            steps.push(PointwiseStep::Set(i));
+            depth += 1;
         } else if c == '!' {
-            if stack.is_empty() {
-                panic!("no operand for set negation");
-            }
-            let set = stack.pop().unwrap();
-            stack.push(universe.difference(&set).copied().collect());
-        } else if let Some(set_operation) = SET_OPERATIONS.get(&c) {
-            if stack.len() < 2 {
-                panic!("not enough operands for set operation");
-            }
-            let a = stack.pop().unwrap();
-            let b = stack.pop().unwrap();
-            stack.push(set_operation(&universe, b, a));
+            if depth == 0 {
+                return Err("no operand for set negation");
+            }
+            steps.push(PointwiseStep::Not);
+        } else if binary.contains(c) {
+            if depth < 2 {
+                return Err("not enough operands for set operation");
+            }
+            steps.push(PointwiseStep::Binary(c));
+            depth -= 1;
         } else {
-            panic!("invalid character: {}", c);
+            return Err("invalid character");
+        }
+    }
+
+    match depth {
+        0 => Err("empty formula"),
+        1 => Ok(steps),
+        _ => Err("not enough operators"),
+    }
+}
+
+/// Value of compiled steps for one element, or one whole set when the operations act on
+/// sets, `binary` taking the left operand first
+pub(crate) fn eval_pointwise<V>(
+    steps: &[PointwiseStep],
+    set: impl Fn(usize) -> V,
+    not: impl Fn(V) -> V,
+    binary: impl Fn(char, V, V) -> V,
+) -> V {
+    let mut stack = vec![];
+    for &step in steps {
+        match step {
+            PointwiseStep::Set(i) => stack.push(set(i)),
+            PointwiseStep::Not => {
+                let a = stack.pop().unwrap();
+                stack.push(not(a));
+            }
+            PointwiseStep::Binary(c) => {
+                let right = stack.pop().unwrap();
+                let left = stack.pop().unwrap();
+                stack.push(binary(c, left, right));
+            }
+        }
+    }
+    stack.pop().unwrap()
+}
+
+/// The universe is the union of the sets: `A!` is empty when `A` is the only set
+fn evaluate<T: Eq + Hash + Clone>(formula: &str, sets: &[HashSet<T>]) -> HashSet<T> {
+    let universe: HashSet<T> = sets.iter().flatten().cloned().collect();
+    SetFormula::new(formula)
+        .and_then(|formula| formula.evaluate(sets, &universe))
+        .unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn eval_set(formula: &str, sets: Vec<Vec<i32>>) -> Vec<i32> {
+    eval_set_generic(formula, sets)
+}
+
+/// Sorted elements of the result, duplicates in the sets are ignored
+pub fn eval_set_generic<T, S, I>(formula: &str, sets: I) -> Vec<T>
+where
+    T: Eq + Hash + Ord + Clone,
+    S: IntoIterator<Item = T>,
+    I: IntoIterator<Item = S>,
+{
+    let sets = sets
+        .into_iter()
+        .map(HashSet::from_iter)
+        .collect::<Vec<HashSet<T>>>();
+    evaluate(formula, &sets)
+        .into_iter()
+        .sorted_unstable()
+        .collect()
+}
+
+/// Evaluates the formula with an explicit universe, of which every set must be a subset
+pub fn eval_set_in_universe<T: Eq + Hash + Clone>(
+    formula: &str,
+    sets: &[HashSet<T>],
+    universe: &HashSet<T>,
+) -> Result<HashSet<T>, &'static str> {
+    if !sets.iter().all(|set| set.is_subset(universe)) {
+        return Err("set is not a subset of the universe");
+    }
+    SetFormula::new(formula)?.evaluate(sets, universe)
+}
+
+/// Sorted elements of the result of `eval_set_in_universe`
+pub fn eval_set_with_universe<T, S, I, U>(
+    formula: &str,
+    sets: I,
+    universe: U,
+) -> Result<Vec<T>, &'static str>
+where
+    T: Eq + Hash + Ord + Clone,
+    S: IntoIterator<Item = T>,
+    I: IntoIterator<Item = S>,
+    U: IntoIterator<Item = T>,
+{
+    let sets = sets
+        .into_iter()
+        .map(HashSet::from_iter)
+        .collect::<Vec<HashSet<T>>>();
+    let universe = HashSet::from_iter(universe);
+    let result = eval_set_in_universe(formula, &sets, &universe)?;
+    Ok(result.into_iter().sorted_unstable().collect())
+}
+
+/// Applies the operators one after the other, each one building a new set
+pub fn eval_hash_sets<T: Eq + Hash + Clone>(formula: &str, sets: &[HashSet<T>]) -> HashSet<T> {
+    let universe: HashSet<T> = sets.iter().flatten().cloned().collect();
+    try_evaluate(formula, sets, &universe).unwrap_or_else(|error| panic!("{error}"))
+}
+
+pub fn eval_btree_sets<T: Eq + Hash + Ord + Clone>(
+    formula: &str,
+    sets: &[BTreeSet<T>],
+) -> BTreeSet<T> {
+    let sets = sets
+        .iter()
+        .map(|set| set.iter().cloned().collect())
+        .collect_vec();
+    evaluate(formula, &sets).into_iter().collect()
+}
+
+// =====================================================
+// ================== DENSE UNIVERSES ==================
+// =====================================================
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DenseBackend {
+    BitSet,
+    Roaring,
+}
+
+/// Sets of indices in `0..capacity`, the universe
+fn evaluate_dense<D: DenseSet>(formula: &str, capacity: usize, sets: &[Vec<usize>]) -> Vec<usize> {
+    let sets = sets
+        .iter()
+        .map(|set| D::from_indices(capacity, set.iter().copied()))
+        .collect_vec();
+    let steps =
+        compile_pointwise(formula, "|&^=>", sets.len()).unwrap_or_else(|error| panic!("{error}"));
+    eval_pointwise(
+        &steps,
+        |i| sets[i].clone(),
+        |set| set.complement(),
+        |c, a, b| match c {
+            '|' => a.union(&b),
+            '&' => a.intersection(&b),
+            '^' => a.symmetric_difference(&b),
+            '=' => a.equivalence(&b),
+            _ => a.material_condition(&b),
+        },
+    )
+    .indices()
+}
+
+/// Same result as `eval_set_generic`, computed on the indices of the elements in the
+/// sorted universe so that the operations run on whole words of the chosen backend
+pub fn eval_set_dense<T, S, I>(formula: &str, sets: I, backend: DenseBackend) -> Vec<T>
+where
+    T: Ord + Clone,
+    S: IntoIterator<Item = T>,
+    I: IntoIterator<Item = S>,
+{
+    let sets = sets
+        .into_iter()
+        .map(|set| set.into_iter().collect_vec())
+        .collect_vec();
+    let universe = sets
+        .iter()
+        .flatten()
+        .cloned()
+        .sorted_unstable()
+        .dedup()
+        .collect_vec();
+    let indices = sets
+        .iter()
+        .map(|set| {
+            set.iter()
+                .map(|x| universe.binary_search(x).unwrap())
+                .collect_vec()
+        })
+        .collect_vec();
+    let result = match backend {
+        DenseBackend::BitSet => evaluate_dense::<BitSet>(formula, universe.len(), &indices),
+        DenseBackend::Roaring => evaluate_dense::<RoaringSet>(formula, universe.len(), &indices),
+    };
+    result.into_iter().map(|i| universe[i].clone()).collect()
+}
+
+// =====================================================
+// ================= COMPILED FORMULAS =================
+// =====================================================
+
+/// Set formula parsed into a `BooleanTree` over the membership of an element in each set.
+/// The elements of a Venn region, those in exactly the same sets, are all in the result
+/// or all out of it, so the formula is evaluated once per region found in the universe.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SetFormula {
+    tree: BooleanTree,
+    variables: Vec<char>,
+}
+
+impl SetFormula {
+    pub fn new(formula: &str) -> Result<Self, &'static str> {
+        if !formula
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || "!|&^=>".contains(c))
+        {
+            return Err("invalid character");
+        }
+        let mut tree = BooleanTree::new(formula, true).map_err(|error| match error {
+            "no operand for binary not" => "no operand for set negation",
+            "not enough operands for binary operation" => "not enough operands for set operation",
+            error => error,
+        })?;
+        // taken before normalizing, which may cancel letters as in `AB^B^`
+        let variables = tree.get_variables();
+        tree.normalize();
+        let formula = Self { tree, variables };
+        // formulas like `AA!&` cover no region or all of them: their tree is a constant,
+        // their variables are kept so that the sets they refer to are still required
+        if formula.variables.len() <= 16 {
+            let covered = formula.regions().len();
+            if covered == 0 || covered == 1 << formula.variables.len() {
+                return Ok(Self {
+                    tree: BooleanTree::Value(covered != 0),
+                    ..formula
+                });
+            }
+        }
+        Ok(formula)
+    }
+
+    /// normalized formula
+    pub fn tree(&self) -> &BooleanTree {
+        &self.tree
+    }
+
+    /// sets the formula refers to, in alphabetical order
+    pub fn variables(&self) -> &[char] {
+        &self.variables
+    }
+
+    /// whether the elements in exactly the sets of `region` are in the result,
+    /// bit `i` standing for the `i`-th variable
+    pub fn covers(&self, region: u32) -> bool {
+        let values = self
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(bit, &c)| (c, region >> bit & 1 == 1))
+            .collect();
+        self.tree.evaluate_with_variables(&values)
+    }
+
+    /// every region covered by the formula, out of `2^variables`
+    pub fn regions(&self) -> Vec<u32> {
+        (0..1 << self.variables.len())
+            .filter(|&region| self.covers(region))
+            .collect()
+    }
+
+    /// disjunction of the covered regions, each one the conjunction of
+    /// the sets that contain it and the complements of the others
+    pub fn dnf(&self) -> BooleanTree {
+        let minterm = |region: u32| {
+            let literals = self.variables.iter().enumerate().map(|(bit, &c)| {
+                let variable = BooleanTree::Variable(c);
+                if region >> bit & 1 == 1 {
+                    variable
+                } else {
+                    BooleanTree::Not(Box::new(variable))
+                }
+            });
+            BooleanTree::AndN(literals.collect())
+        };
+        BooleanTree::OrN(self.regions().into_iter().map(minterm).collect())
+    }
+
+    /// Elements of `universe` in the result, found in a single pass
+    pub fn evaluate<T: Eq + Hash + Clone>(
+        &self,
+        sets: &[HashSet<T>],
+        universe: &HashSet<T>,
+    ) -> Result<HashSet<T>, &'static str> {
+        let sets = self
+            .variables
+            .iter()
+            .map(|&c| sets.get(c as usize - 'A' as usize))
+            .collect::<Option<Vec<_>>>()
+            .ok_or("formula refers to a set that was not given")?;
+        let mut covered = HashMap::new();
+        let mut result = HashSet::new();
+        for x in universe {
+            let region = sets.iter().enumerate().fold(0, |region, (bit, set)| {
+                region | (set.contains(x) as u32) << bit
+            });
+            if *covered.entry(region).or_insert_with(|| self.covers(region)) {
+                result.insert(x.clone());
+            }
         }
+        Ok(result)
+    }
+}
+
+// =====================================================
+// ================== SET RELATIONS ====================
+// =====================================================
+
+/// Relation between the results of two set formulas
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetRelation {
+    /// `F ⊆ G`
+    Subset,
+    /// `F = G`
+    Equal,
+    /// `F ∩ G = ∅`
+    Disjoint,
+}
+
+impl SetFormula {
+    /// Decides whether `relation` holds between `self` and `other` whatever the sets,
+    /// complements being taken in any universe. Otherwise returns a counterexample region:
+    /// an element in exactly these sets, among the ones of the formulas, refutes it.
+    pub fn counterexample(&self, relation: SetRelation, other: &SetFormula) -> Option<Vec<char>> {
+        let (f, g) = (Box::new(self.tree.clone()), Box::new(other.tree.clone()));
+        // satisfied by the membership of the elements refuting the relation
+        let refutation = match relation {
+            SetRelation::Subset => BooleanTree::And(f, Box::new(BooleanTree::Not(g))),
+            SetRelation::Equal => BooleanTree::Xor(f, g),
+            SetRelation::Disjoint => BooleanTree::And(f, g),
+        };
+        let (cnf, variables) = Cnf::from_tree(&refutation);
+        let model = cnf.solve()?;
+        Some(
+            variables
+                .into_iter()
+                .zip(model)
+                .filter_map(|(c, member)| member.then_some(c))
+                .collect(),
+        )
     }
 
-    match stack.len() {
-        0 => panic!("empty formula"),
-        1 => stack.pop().unwrap().into_iter().sorted_unstable().collect(),
-        _ => panic!("not enough operators"),
+    pub fn holds(&self, relation: SetRelation, other: &SetFormula) -> bool {
+        self.counterexample(relation, other).is_none()
     }
 }
 
+/// `SetFormula::counterexample` on two formulas in reverse Polish notation
+pub fn set_relation_counterexample(
+    f: &str,
+    relation: SetRelation,
+    g: &str,
+) -> Result<Option<Vec<char>>, &'static str> {
+    Ok(SetFormula::new(f)?.counterexample(relation, &SetFormula::new(g)?))
+}
+
+// =====================================================
+// =================== VENN REGIONS ====================
+// =====================================================
+
+/// Sizes of the 2^n Venn regions of n sets, the region `r` holding the elements in
+/// exactly the sets `i` for which bit `i` of `r` is set. The universe is the union of
+/// the sets, as in `eval_set`, so region 0 is always empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VennCounts {
+    counts: Vec<usize>,
+    /// by mask, see `intersections`
+    intersections: Vec<usize>,
+}
+
+impl VennCounts {
+    pub fn new<T, S, I>(sets: I) -> Self
+    where
+        T: Eq + Hash,
+        S: IntoIterator<Item = T>,
+        I: IntoIterator<Item = S>,
+    {
+        let mut regions: HashMap<T, u32> = HashMap::new();
+        let mut n = 0;
+        for (i, set) in sets.into_iter().enumerate() {
+            assert!(i < 26, "at most 26 sets, from A to Z");
+            for x in set {
+                *regions.entry(x).or_default() |= 1 << i;
+            }
+            n = i + 1;
+        }
+        let mut counts = vec![0; 1 << n];
+        for region in regions.into_values() {
+            counts[region as usize] += 1;
+        }
+        // the sum of the regions including each mask
+        let mut intersections = counts.clone();
+        for i in 0..n {
+            for mask in 0..intersections.len() {
+                if mask >> i & 1 == 0 {
+                    intersections[mask] += intersections[mask | 1 << i];
+                }
+            }
+        }
+        Self {
+            counts,
+            intersections,
+        }
+    }
+
+    pub fn num_sets(&self) -> usize {
+        self.counts.len().trailing_zeros() as usize
+    }
+
+    /// size of each region, by region
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+
+    pub fn region(&self, region: u32) -> usize {
+        self.counts[region as usize]
+    }
+
+    /// Size of the result of the formula, sum of the sizes of the regions it covers
+    pub fn count(&self, formula: &SetFormula) -> Result<usize, &'static str> {
+        let bits = formula
+            .variables()
+            .iter()
+            .map(|&c| c as usize - 'A' as usize)
+            .collect_vec();
+        if bits.iter().any(|&bit| bit >= self.num_sets()) {
+            return Err("formula refers to a set that was not given");
+        }
+        let covered = (0..1 << bits.len())
+            .map(|region| formula.covers(region))
+            .collect_vec();
+        Ok((0..self.counts.len())
+            .filter(|&region| {
+                // the region restricted to the sets of the formula
+                let projected = bits.iter().enumerate().fold(0, |projected, (k, &bit)| {
+                    projected | (region >> bit & 1) << k
+                });
+                covered[projected]
+            })
+            .map(|region| self.counts[region])
+            .sum())
+    }
+
+    /// `count` of a formula in reverse Polish notation
+    pub fn count_formula(&self, formula: &str) -> Result<usize, &'static str> {
+        self.count(&SetFormula::new(formula)?)
+    }
+
+    /// Size of the intersection of the sets of each mask, the whole universe for mask 0
+    pub fn intersections(&self) -> &[usize] {
+        &self.intersections
+    }
+
+    /// Size of the union of the sets of `mask`, from the regions meeting one of them
+    pub fn union(&self, mask: u32) -> usize {
+        (0..self.counts.len())
+            .filter(|&region| region as u32 & mask != 0)
+            .map(|region| self.counts[region])
+            .sum()
+    }
+
+    /// `union` by the inclusion–exclusion principle: the sizes of the intersections
+    /// of an odd number of the sets minus those of an even number of them. Bits of
+    /// `mask` past the last set are ignored, as in `union`.
+    pub fn union_by_inclusion_exclusion(&self, mask: u32) -> usize {
+        let mask = mask & (self.counts.len() as u32 - 1);
+        let mut total: i64 = 0;
+        // nonempty submasks of mask
+        let mut sub = mask;
+        while sub != 0 {
+            let size = self.intersections[sub as usize] as i64;
+            total += if sub.count_ones() % 2 == 1 {
+                size
+            } else {
+                -size
+            };
+            sub = (sub - 1) & mask;
+        }
+        total as usize
+    }
+}
+
+// =====================================================
+// ================== LAZY POWERSETS ===================
+// =====================================================
+
+// Subsets of `0..n` are masks with bit `i` set when element `i` is in the subset
+
+/// Position of the subset `mask` of `0..n` in `order`
+pub fn subset_rank(mask: u64, n: usize, order: PowersetOrder) -> u64 {
+    assert!(n < 64, "at most 63 elements");
+    assert!(mask >> n == 0, "mask out of the {n} elements");
+    match order {
+        // the first element is the highest bit
+        PowersetOrder::Binary => reverse_bits(mask, n),
+        PowersetOrder::Gray => {
+            let mut rank = mask;
+            for shift in [1, 2, 4, 8, 16, 32] {
+                rank ^= rank >> shift;
+            }
+            rank
+        }
+        // each element `e` after the previous one `s - 1` skips the 2^(n - 1 - j) subsets
+        // starting with the current prefix and `j`, for `j` from `s` to `e - 1`
+        PowersetOrder::Lexicographic => {
+            let mut rank = 0;
+            let mut s = 0;
+            for e in (0..n).filter(|e| mask >> e & 1 == 1) {
+                rank += 1 + ((1 << (n - s)) - (1 << (n - e)));
+                s = e + 1;
+            }
+            rank
+        }
+    }
+}
+
+/// Subset of `0..n` at position `rank` in `order`, inverse of `subset_rank`
+pub fn subset_unrank(rank: u64, n: usize, order: PowersetOrder) -> u64 {
+    assert!(n < 64, "at most 63 elements");
+    assert!(rank >> n == 0, "rank out of the 2^{n} subsets");
+    match order {
+        PowersetOrder::Binary => reverse_bits(rank, n),
+        PowersetOrder::Gray => rank ^ rank >> 1,
+        PowersetOrder::Lexicographic => {
+            let mut mask = 0;
+            let mut rank = rank;
+            let mut s = 0;
+            // rank among the subsets extending the current prefix, the prefix being the first
+            while rank > 0 {
+                rank -= 1;
+                for e in s..n {
+                    let extensions = 1 << (n - 1 - e);
+                    if rank < extensions {
+                        mask |= 1 << e;
+                        s = e + 1;
+                        break;
+                    }
+                    rank -= extensions;
+                }
+            }
+            mask
+        }
+    }
+}
+
+/// the `n` low bits of `bits` in reverse order
+fn reverse_bits(bits: u64, n: usize) -> u64 {
+    match n {
+        0 => 0,
+        _ => bits.reverse_bits() >> (64 - n),
+    }
+}
+
+fn select<T: Clone>(set: &[T], mask: u64) -> Vec<T> {
+    (0..set.len())
+        .filter(|i| mask >> i & 1 == 1)
+        .map(|i| set[i].clone())
+        .collect()
+}
+
+/// Subsets built one at a time, from both ends or at any rank.
+/// `Powerset::new(set, PowersetOrder::Binary)` yields the subsets of `powerset(set)`.
+#[derive(Clone, Debug)]
+pub struct Powerset<T> {
+    set: Vec<T>,
+    order: PowersetOrder,
+    /// ranks of the subsets not yielded yet
+    front: u64,
+    back: u64,
+}
+
+impl<T: Clone> Powerset<T> {
+    pub fn new<I: IntoIterator<Item = T>>(set: I, order: PowersetOrder) -> Self {
+        let set = set.into_iter().collect_vec();
+        assert!(set.len() < 64, "at most 63 elements");
+        Self {
+            back: 1 << set.len(),
+            set,
+            order,
+            front: 0,
+        }
+    }
+
+    pub fn nth_subset(&self, rank: u64) -> Option<Vec<T>> {
+        (rank >> self.set.len() == 0)
+            .then(|| select(&self.set, subset_unrank(rank, self.set.len(), self.order)))
+    }
+
+    /// Rank of the subset made of the elements at `indices` in the set
+    pub fn rank(&self, indices: &[usize]) -> u64 {
+        let mask = indices.iter().fold(0, |mask, &i| {
+            assert!(i < self.set.len(), "index {i} out of the set");
+            mask | 1 << i
+        });
+        subset_rank(mask, self.set.len(), self.order)
+    }
+}
+
+impl<T: Clone> Iterator for Powerset<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (self.front < self.back).then(|| {
+            self.front += 1;
+            select(
+                &self.set,
+                subset_unrank(self.front - 1, self.set.len(), self.order),
+            )
+        })
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n as u64).min(self.back);
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.back - self.front) as usize;
+        (len, Some(len))
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for Powerset<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        (self.front < self.back).then(|| {
+            self.back -= 1;
+            select(
+                &self.set,
+                subset_unrank(self.back, self.set.len(), self.order),
+            )
+        })
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for Powerset<T> {}
+
+#[derive(Clone, Debug)]
+enum KSubsetsState {
+    /// rank of the next subset, found with Gosper's hack
+    Binary(Option<u64>),
+    /// indices of the next subset
+    Lexicographic(Option<Vec<usize>>),
+    Gray(RevolvingDoor),
+}
+
+/// Subsets of size `k`, in the order they have in the powerset in `order`
+#[derive(Clone, Debug)]
+pub struct KSubsets<T> {
+    set: Vec<T>,
+    state: KSubsetsState,
+}
+
+impl<T: Clone> KSubsets<T> {
+    pub fn new<I: IntoIterator<Item = T>>(set: I, k: usize, order: PowersetOrder) -> Self {
+        let set = set.into_iter().collect_vec();
+        let n = set.len();
+        let state = match order {
+            PowersetOrder::Binary => {
+                assert!(n < 64, "at most 63 elements");
+                KSubsetsState::Binary((k <= n).then(|| (1 << k) - 1))
+            }
+            PowersetOrder::Lexicographic => {
+                KSubsetsState::Lexicographic((k <= n).then(|| (0..k).collect()))
+            }
+            PowersetOrder::Gray if k > n => KSubsetsState::Lexicographic(None),
+            PowersetOrder::Gray => KSubsetsState::Gray(RevolvingDoor::new(n, k)),
+        };
+        Self { set, state }
+    }
+}
+
+impl<T: Clone> Iterator for KSubsets<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.set.len();
+        match &mut self.state {
+            KSubsetsState::Binary(next) => {
+                let rank = next.take()?;
+                // next higher number with as many bits set
+                if rank != 0 {
+                    let lowest = rank & rank.wrapping_neg();
+                    let ripple = rank + lowest;
+                    let successor = (((ripple ^ rank) >> 2) / lowest) | ripple;
+                    *next = (successor >> n == 0).then_some(successor);
+                }
+                Some(select(&self.set, reverse_bits(rank, n)))
+            }
+            KSubsetsState::Lexicographic(next) => {
+                let indices = next.take()?;
+                let subset = indices.iter().map(|&i| self.set[i].clone()).collect();
+                let k = indices.len();
+                // rightmost index that can still move right
+                if let Some(i) = (0..k).rev().find(|&i| indices[i] < n - k + i) {
+                    let mut successor = indices;
+                    successor[i] += 1;
+                    for j in i + 1..k {
+                        successor[j] = successor[j - 1] + 1;
+                    }
+                    *next = Some(successor);
+                }
+                Some(subset)
+            }
+            KSubsetsState::Gray(door) => door
+                .next()
+                .map(|(indices, _)| indices.into_iter().map(|i| self.set[i].clone()).collect()),
+        }
+    }
+}
+
+/// Subsets in lexicographic order for which `keep` holds, `keep` being monotone: when it
+/// fails for a subset, it fails for its supersets, as a bound on a sum of nonnegative
+/// weights does. The supersets of a rejected subset are never built.
+#[derive(Clone, Debug)]
+pub struct PrunedPowerset<T, F> {
+    set: Vec<T>,
+    keep: F,
+    /// indices and elements of the last subset yielded
+    indices: Vec<usize>,
+    subset: Vec<T>,
+    started: bool,
+    done: bool,
+}
+
+impl<T: Clone, F: FnMut(&[T]) -> bool> PrunedPowerset<T, F> {
+    pub fn new<I: IntoIterator<Item = T>>(set: I, keep: F) -> Self {
+        Self {
+            set: set.into_iter().collect(),
+            keep,
+            indices: vec![],
+            subset: vec![],
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl<T: Clone, F: FnMut(&[T]) -> bool> Iterator for PrunedPowerset<T, F> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if !self.started {
+            self.started = true;
+            self.done = !(self.keep)(&[]);
+            return (!self.done).then(Vec::new);
+        }
+        // extend the last subset, otherwise extend its prefixes with later elements
+        let mut start = self.indices.last().map_or(0, |&i| i + 1);
+        loop {
+            for e in start..self.set.len() {
+                self.subset.push(self.set[e].clone());
+                if (self.keep)(&self.subset) {
+                    self.indices.push(e);
+                    return Some(self.subset.clone());
+                }
+                self.subset.pop();
+            }
+            match self.indices.pop() {
+                Some(last) => {
+                    self.subset.pop();
+                    start = last + 1;
+                }
+                None => {
+                    self.done = true;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Shared by the tests of the evaluators that extend `eval_set`
+#[cfg(test)]
+pub(crate) mod fixtures {
+    /// one element in each nonempty region of three sets
+    pub(crate) fn region_sets() -> Vec<Vec<i32>> {
+        vec![vec![1, 3, 5, 7], vec![2, 3, 6, 7], vec![4, 5, 6, 7]]
+    }
+
+    /// every operator on three sets, with complements
+    pub(crate) const FORMULAS: [&str; 8] = [
+        "AB&C|", "AB|C&!", "AB^C=", "AB>C!&", "A!B&C>", "AB=C^!", "AA!|", "AA!&B|",
+    ];
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, rand::Rng};
 
     #[test]
     fn test_powerset() {
@@ -161,6 +994,384 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_powerset_in_order() {
+        assert_eq!(
+            powerset_in_order(vec![1, 2, 3], PowersetOrder::Binary),
+            powerset(vec![1, 2, 3])
+        );
+        assert_eq!(
+            powerset_in_order(vec![1, 2, 3], PowersetOrder::Gray),
+            vec![
+                vec![],
+                vec![1],
+                vec![1, 2],
+                vec![2],
+                vec![2, 3],
+                vec![1, 2, 3],
+                vec![1, 3],
+                vec![3],
+            ]
+        );
+        assert_eq!(powerset_in_order(vec![], PowersetOrder::Gray), vec![vec![]]);
+    }
+
+    #[test]
+    fn test_lazy_powerset() {
+        let orders = [
+            PowersetOrder::Binary,
+            PowersetOrder::Gray,
+            PowersetOrder::Lexicographic,
+        ];
+        assert_eq!(
+            Powerset::new([1, 2, 3], PowersetOrder::Lexicographic).collect_vec(),
+            vec![
+                vec![],
+                vec![1],
+                vec![1, 2],
+                vec![1, 2, 3],
+                vec![1, 3],
+                vec![2],
+                vec![2, 3],
+                vec![3],
+            ]
+        );
+        for n in 0..8 {
+            let set = (0..n).map(|i| i * 10).collect_vec();
+            for order in orders {
+                let subsets = Powerset::new(set.clone(), order);
+                assert_eq!(subsets.len(), 1 << n);
+                if order != PowersetOrder::Lexicographic {
+                    assert_eq!(
+                        subsets.clone().collect_vec(),
+                        powerset_in_order(set.clone(), order)
+                    );
+                }
+                assert_eq!(
+                    subsets.clone().rev().collect_vec(),
+                    subsets
+                        .clone()
+                        .collect_vec()
+                        .into_iter()
+                        .rev()
+                        .collect_vec()
+                );
+                for (rank, subset) in subsets.clone().enumerate() {
+                    assert_eq!(subsets.nth_subset(rank as u64).as_ref(), Some(&subset));
+                    let indices = subset.iter().map(|x| (x / 10) as usize).collect_vec();
+                    assert_eq!(subsets.rank(&indices), rank as u64);
+                    assert_eq!(subsets.clone().nth(rank), Some(subset));
+                }
+                assert_eq!(subsets.nth_subset(1 << n), None);
+
+                for k in 0..=n + 1 {
+                    assert_eq!(
+                        KSubsets::new(set.clone(), k as usize, order).collect_vec(),
+                        subsets
+                            .clone()
+                            .filter(|subset| subset.len() == k as usize)
+                            .collect_vec(),
+                        "{order:?} {n} {k}"
+                    );
+                }
+            }
+        }
+
+        // no need to hold 2^60 subsets
+        let mut subsets = Powerset::new(0..60, PowersetOrder::Binary);
+        assert_eq!(subsets.nth(1 << 59), Some(vec![0]));
+        assert_eq!(subsets.next_back(), Some((0..60).collect()));
+        assert_eq!(subsets.len(), (1 << 59) - 2);
+    }
+
+    #[test]
+    fn test_pruned_powerset() {
+        let weights = [5, 3, 8, 1, 4];
+        let within = |budget: i32| move |subset: &[i32]| subset.iter().sum::<i32>() <= budget;
+        for budget in [-1, 0, 4, 9, 21, 100] {
+            assert_eq!(
+                PrunedPowerset::new(weights, within(budget)).collect_vec(),
+                Powerset::new(weights, PowersetOrder::Lexicographic)
+                    .filter(|subset| within(budget)(subset))
+                    .collect_vec()
+            );
+        }
+        // subsets of 40 elements summing to at most 3
+        let mut calls = 0;
+        let small = PrunedPowerset::new(1..=40, |subset: &[i32]| {
+            calls += 1;
+            subset.iter().sum::<i32>() <= 3
+        })
+        .collect_vec();
+        assert_eq!(small, vec![vec![], vec![1], vec![1, 2], vec![2], vec![3]]);
+        assert!(calls < 200);
+    }
+
+    #[test]
+    fn test_generic_powerset() {
+        assert_eq!(
+            powerset_generic(["a", "b"]),
+            vec![vec![], vec!["b"], vec!["a"], vec!["a", "b"]]
+        );
+        let set = BTreeSet::from([(1, 'x'), (0, 'y')]);
+        let subsets = powerset_of_set(&set);
+        assert_eq!(subsets.len(), 4);
+        assert!(subsets.contains(&set));
+        assert!(subsets.contains(&BTreeSet::new()));
+        assert!(subsets.iter().all(|subset| subset.is_subset(&set)));
+    }
+
+    #[test]
+    fn test_generic_eval_set() {
+        let sets = [vec!["apple", "pear"], vec!["pear", "plum", "pear"]];
+        assert_eq!(eval_set_generic("AB&", sets.clone()), ["pear"]);
+        assert_eq!(eval_set_generic("AB^", sets.clone()), ["apple", "plum"]);
+        assert_eq!(eval_set_generic("A!", sets), ["plum"]);
+
+        let ids = [
+            HashSet::from([(1, 2), (3, 4)]),
+            HashSet::from([(3, 4), (5, 6)]),
+        ];
+        assert_eq!(eval_hash_sets("AB|B!&", &ids), HashSet::from([(1, 2)]));
+        let names = [
+            BTreeSet::from(["ada".to_string(), "alan".to_string()]),
+            BTreeSet::from(["alan".to_string()]),
+        ];
+        assert_eq!(
+            eval_btree_sets("AB>", &names),
+            BTreeSet::from(["alan".to_string()])
+        );
+        // same results as the i32 version
+        let sets = vec![vec![1, 2], vec![2, 3], vec![3, 4]];
+        for formula in ["ABC&|", "ABC^=", "A!B>C|"] {
+            let btree_sets = sets
+                .iter()
+                .map(|set| set.iter().copied().collect())
+                .collect_vec();
+            assert_eq!(
+                eval_btree_sets(formula, &btree_sets)
+                    .into_iter()
+                    .collect_vec(),
+                eval_set(formula, sets.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_set_with_universe() {
+        let universe = 0..6;
+        let with =
+            |formula, sets: Vec<Vec<i32>>| eval_set_with_universe(formula, sets, universe.clone());
+        assert_eq!(with("A!", vec![vec![0, 1, 2]]), Ok(vec![3, 4, 5]));
+        assert_eq!(with("A!!", vec![vec![0, 1, 2]]), Ok(vec![0, 1, 2]));
+        assert_eq!(
+            with("AB=", vec![vec![0, 1, 2], vec![0, 3, 4]]),
+            Ok(vec![0, 5])
+        );
+        assert_eq!(
+            with("AB>", vec![vec![0, 1, 2], vec![0, 3, 4]]),
+            Ok(vec![0, 3, 4, 5])
+        );
+        // the same formulas with the union as universe
+        assert_eq!(eval_set("A!", vec![vec![0, 1, 2]]), []);
+        assert_eq!(eval_set("AB=", vec![vec![0, 1, 2], vec![0, 3, 4]]), [0]);
+
+        assert_eq!(
+            with("A", vec![vec![0, 6]]),
+            Err("set is not a subset of the universe")
+        );
+        assert_eq!(
+            with("AB|", vec![vec![0]]),
+            Err("formula refers to a set that was not given")
+        );
+        // constant formulas still need their sets
+        assert_eq!(
+            with("BB!&", vec![vec![0]]),
+            Err("formula refers to a set that was not given")
+        );
+        assert_eq!(with("AA!|", vec![vec![0]]), Ok(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(with("", vec![]), Err("empty formula"));
+        assert_eq!(
+            with("A|", vec![vec![]]),
+            Err("not enough operands for set operation")
+        );
+        assert_eq!(with("!", vec![]), Err("no operand for set negation"));
+        assert_eq!(with("AA", vec![vec![]]), Err("not enough operators"));
+        assert_eq!(with("a", vec![]), Err("invalid character"));
+
+        let universe = HashSet::from(["x", "y", "z"]);
+        let sets = [HashSet::from(["x"]), HashSet::from(["y"])];
+        assert_eq!(
+            eval_set_in_universe("AB|!", &sets, &universe),
+            Ok(HashSet::from(["z"]))
+        );
+    }
+
+    #[test]
+    fn test_set_formula() {
+        let formula = SetFormula::new("AB&C|").unwrap();
+        assert_eq!(formula.variables(), ['A', 'B', 'C']);
+        assert_eq!(formula.regions(), [0b011, 0b100, 0b101, 0b110, 0b111]);
+        assert_eq!(
+            SetFormula::new("AA!&").unwrap().tree(),
+            &BooleanTree::Value(false)
+        );
+        assert_eq!(
+            SetFormula::new("AB>BA>|").unwrap().tree(),
+            &BooleanTree::Value(true)
+        );
+        assert_eq!(SetFormula::new("01&"), Err("invalid character"));
+        assert_eq!(SetFormula::new("A↑"), Err("invalid character"));
+
+        // same results as the operators applied one by one
+        let mut rng = rand::rng();
+        let formulas = ["AB&C|", "AB>C=!", "ABC^^D>", "A!B!|C&", "AB=C>D^A|"];
+        for _ in 0..50 {
+            let sets = (0..4)
+                .map(|_| (0..20).filter(|_| rng.random()).collect::<HashSet<i32>>())
+                .collect_vec();
+            for formula in formulas {
+                let compiled = SetFormula::new(formula).unwrap();
+                assert_eq!(evaluate(formula, &sets), eval_hash_sets(formula, &sets));
+                let dnf = SetFormula::new(&compiled.dnf().to_formula()).unwrap();
+                let universe = (0..25).collect();
+                assert_eq!(
+                    dnf.evaluate(&sets, &universe),
+                    compiled.evaluate(&sets, &universe),
+                    "{formula}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_eval_set_dense() {
+        let backends = [DenseBackend::BitSet, DenseBackend::Roaring];
+        let sets = [vec!["apple", "pear"], vec!["pear", "plum", "pear"]];
+        for backend in backends {
+            assert_eq!(eval_set_dense("AB&", sets.clone(), backend), ["pear"]);
+            assert_eq!(eval_set_dense("A!", sets.clone(), backend), ["plum"]);
+        }
+
+        // same results as the HashSet path, across several roaring chunks
+        let mut rng = rand::rng();
+        let formulas = ["AB&C|", "AB>C=!", "ABC^^", "A!B!|C&", "AB=C>A^"];
+        for density in [0.5, 0.001] {
+            let sets = (0..3)
+                .map(|_| {
+                    (0..140_000)
+                        .filter(|_| rng.random_bool(density))
+                        .collect_vec()
+                })
+                .collect_vec();
+            for formula in formulas {
+                let expected = eval_set(formula, sets.clone());
+                for backend in backends {
+                    assert_eq!(
+                        eval_set_dense(formula, sets.clone(), backend),
+                        expected,
+                        "{formula}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_relations() {
+        let check = |f, relation, g| set_relation_counterexample(f, relation, g).unwrap();
+        assert_eq!(check("AB&", SetRelation::Subset, "A"), None);
+        assert_eq!(check("A", SetRelation::Subset, "AB&"), Some(vec!['A']));
+        assert_eq!(check("AB|!", SetRelation::Equal, "A!B!&"), None);
+        assert_eq!(check("AB>", SetRelation::Equal, "A!B|"), None);
+        assert_eq!(check("A", SetRelation::Equal, "AA&"), None);
+        assert_eq!(check("AB^", SetRelation::Disjoint, "AB&"), None);
+        assert_eq!(check("A!", SetRelation::Disjoint, "B!"), Some(vec![]));
+        assert_eq!(check("AA!&", SetRelation::Subset, "B"), None);
+        assert_eq!(check("B", SetRelation::Subset, "AA!|"), None);
+        assert_eq!(check("A", SetRelation::Disjoint, "B"), Some(vec!['A', 'B']));
+        assert_eq!(
+            set_relation_counterexample("A&", SetRelation::Subset, "A"),
+            Err("not enough operands for set operation")
+        );
+
+        // the counterexample regions do refute the relation
+        let formulas = ["AB&C|", "AB>C=!", "ABC^^", "A!B!|C&", "AC=", "B", "ABC&&"];
+        let relations = [
+            SetRelation::Subset,
+            SetRelation::Equal,
+            SetRelation::Disjoint,
+        ];
+        for (f, g) in formulas.into_iter().cartesian_product(formulas) {
+            let (f, g) = (SetFormula::new(f).unwrap(), SetFormula::new(g).unwrap());
+            for relation in relations {
+                let refutes = |region: u32| {
+                    let member = |formula: &SetFormula| {
+                        let values = formula
+                            .variables()
+                            .iter()
+                            .map(|&c| (c, region >> (c as u32 - 'A' as u32) & 1 == 1))
+                            .collect();
+                        formula.tree().evaluate_with_variables(&values)
+                    };
+                    match relation {
+                        SetRelation::Subset => member(&f) && !member(&g),
+                        SetRelation::Equal => member(&f) != member(&g),
+                        SetRelation::Disjoint => member(&f) && member(&g),
+                    }
+                };
+                match f.counterexample(relation, &g) {
+                    None => assert!((0..8).all(|region| !refutes(region))),
+                    Some(sets) => assert!(refutes(
+                        sets.iter().map(|&c| 1 << (c as u32 - 'A' as u32)).sum()
+                    )),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_venn_counts() {
+        let venn = VennCounts::new([vec![1, 2, 3, 4], vec![3, 4, 5], vec![4, 6, 6]]);
+        assert_eq!(venn.num_sets(), 3);
+        assert_eq!(venn.counts(), [0, 2, 1, 1, 1, 0, 0, 1]);
+        assert_eq!(venn.intersections(), [6, 4, 3, 2, 2, 1, 1, 1]);
+        assert_eq!(venn.count_formula("AB&C!&"), Ok(1));
+        assert_eq!(
+            venn.count_formula("D"),
+            Err("formula refers to a set that was not given")
+        );
+        assert_eq!(VennCounts::new(Vec::<Vec<i32>>::new()).counts(), [0]);
+        assert_eq!(venn.union_by_inclusion_exclusion(0b1000), 0);
+        assert_eq!(
+            venn.union_by_inclusion_exclusion(u32::MAX),
+            venn.union(0b111)
+        );
+        assert_eq!(
+            venn.union_by_inclusion_exclusion(0b1011),
+            venn.union(0b1011)
+        );
+
+        // same sizes as the evaluated formulas and the direct unions
+        let mut rng = rand::rng();
+        for _ in 0..20 {
+            let sets = (0..5)
+                .map(|_| (0..40).filter(|_| rng.random_bool(0.3)).collect_vec())
+                .collect_vec();
+            let venn = VennCounts::new(sets.clone());
+            assert_eq!(venn.counts().iter().sum::<usize>(), venn.union(0b11111));
+            for formula in ["AB&C|", "AB>C=!", "ABC^^D>", "A!B!|C&", "AE=C>D^A|"] {
+                assert_eq!(
+                    venn.count_formula(formula),
+                    Ok(eval_set(formula, sets.clone()).len())
+                );
+            }
+            for mask in 0..32 {
+                assert_eq!(venn.union_by_inclusion_exclusion(mask), venn.union(mask));
+            }
+        }
+    }
+
     #[test]
     fn test_eval_one_set() {
         assert_eq!(eval_set("A", vec![vec![0, 1, 2]]), vec![0, 1, 2]);
@@ -243,4 +1454,31 @@ mod tests {
     fn test_eval_set_not_enough_sets() {
         eval_set("ABC&&", vec![vec![1, 2, 3], vec![3, 4, 5]]);
     }
+
+    #[test]
+    #[should_panic(expected = "formula refers to a set that was not given")]
+    fn test_eval_set_constant_formula_without_its_set() {
+        eval_set("BB!|", vec![vec![1]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "formula refers to a set that was not given")]
+    fn test_eval_set_cancelled_set_not_given() {
+        eval_set("AB^B^", vec![vec![1]]);
+    }
+
+    #[test]
+    fn test_cancelled_sets_are_required() {
+        let error = "formula refers to a set that was not given";
+        assert_eq!(
+            eval_set_with_universe("AB^B^", vec![vec![1]], 0..3),
+            Err(error)
+        );
+        assert_eq!(
+            VennCounts::new([vec![1]]).count_formula("AB^B^"),
+            Err(error)
+        );
+        assert_eq!(SetFormula::new("AB^B^").unwrap().variables(), ['A', 'B']);
+        assert_eq!(eval_set("AB^B^", vec![vec![1], vec![2]]), [1]);
+    }
 }
@@ -0,0 +1,455 @@
+//! Implementation of https://en.wikipedia.org/wiki/Conflict-driven_clause_learning
+//! with two watched literals, MiniSat-style assumptions, activity-based decisions
+//! and phase saving
+
+use crate::{
+    Cnf,
+    cnf::{Literal, variable},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolverResult {
+    /// `model[v - 1]` is the value of variable `v`
+    Satisfiable(Vec<bool>),
+    /// subset of the assumptions that cannot hold together (empty if the clauses alone conflict)
+    Unsatisfiable(Vec<Literal>),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Literal>>,
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Literal>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    inconsistent: bool,
+    /// bumped for the variables of each learnt clause, decides the next branching variable
+    activity: Vec<f64>,
+    bump: f64,
+    /// last value of each variable, reused when branching on it again
+    phases: Vec<bool>,
+}
+
+fn watch_index(literal: Literal) -> usize {
+    2 * variable(literal) + (literal < 0) as usize
+}
+
+fn value(values: &[Option<bool>], literal: Literal) -> Option<bool> {
+    values[variable(literal)].map(|b| b == (literal > 0))
+}
+
+impl Solver {
+    pub fn new(cnf: &Cnf) -> Self {
+        let mut solver = Self::default();
+        solver.reserve_variables(cnf.num_variables);
+        for clause in &cnf.clauses {
+            solver.add_clause(clause);
+        }
+        solver
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.values.len().saturating_sub(1)
+    }
+
+    pub fn reserve_variables(&mut self, num_variables: usize) {
+        if num_variables > self.num_variables() {
+            self.values.resize(num_variables + 1, None);
+            self.levels.resize(num_variables + 1, 0);
+            self.reasons.resize(num_variables + 1, None);
+            self.activity.resize(num_variables + 1, 0.0);
+            self.phases.resize(num_variables + 1, false);
+            self.watches.resize(2 * num_variables + 2, vec![]);
+        }
+    }
+
+    pub fn new_variable(&mut self) -> Literal {
+        let v = self.num_variables() + 1;
+        self.reserve_variables(v);
+        v as Literal
+    }
+
+    pub fn add_clause(&mut self, clause: &[Literal]) {
+        assert!(!clause.contains(&0), "0 is not a valid literal");
+        self.cancel_until(0);
+        if self.inconsistent {
+            return;
+        }
+        if let Some(max) = clause.iter().map(|&lit| variable(lit)).max() {
+            self.reserve_variables(max);
+        }
+
+        let mut simplified: Vec<Literal> = vec![];
+        for &lit in clause {
+            if value(&self.values, lit) == Some(true) || simplified.contains(&-lit) {
+                return;
+            }
+            if value(&self.values, lit).is_none() && !simplified.contains(&lit) {
+                simplified.push(lit);
+            }
+        }
+        match simplified.len() {
+            0 => self.inconsistent = true,
+            1 => {
+                self.enqueue(simplified[0], None);
+                if self.propagate().is_some() {
+                    self.inconsistent = true;
+                }
+            }
+            _ => {
+                self.attach(simplified);
+            }
+        }
+    }
+
+    fn attach(&mut self, clause: Vec<Literal>) -> usize {
+        let index = self.clauses.len();
+        self.watches[watch_index(clause[0])].push(index);
+        self.watches[watch_index(clause[1])].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn enqueue(&mut self, literal: Literal, reason: Option<usize>) {
+        let v = variable(literal);
+        self.values[v] = Some(literal > 0);
+        self.levels[v] = self.decision_level();
+        self.reasons[v] = reason;
+        self.trail.push(literal);
+    }
+
+    fn decide(&mut self, literal: Literal) {
+        self.trail_limits.push(self.trail.len());
+        self.enqueue(literal, None);
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let limit = self.trail_limits[level];
+        for &lit in &self.trail[limit..] {
+            self.values[variable(lit)] = None;
+            self.phases[variable(lit)] = lit > 0;
+        }
+        self.trail.truncate(limit);
+        self.trail_limits.truncate(level);
+        self.propagated = limit;
+    }
+
+    /// returns the index of a conflicting clause, if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_literal = -self.trail[self.propagated];
+            self.propagated += 1;
+            let mut watchers = std::mem::take(&mut self.watches[watch_index(false_literal)]);
+            let mut i = 0;
+            while i < watchers.len() {
+                let clause_index = watchers[i];
+                let clause = &mut self.clauses[clause_index];
+                if clause[0] == false_literal {
+                    clause.swap(0, 1);
+                }
+                if value(&self.values, clause[0]) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                if let Some(k) =
+                    (2..clause.len()).find(|&k| value(&self.values, clause[k]) != Some(false))
+                {
+                    clause.swap(1, k);
+                    self.watches[watch_index(clause[1])].push(clause_index);
+                    watchers.swap_remove(i);
+                    continue;
+                }
+                if value(&self.values, clause[0]) == Some(false) {
+                    self.watches[watch_index(false_literal)] = watchers;
+                    self.propagated = self.trail.len();
+                    return Some(clause_index);
+                }
+                let unit = clause[0];
+                self.enqueue(unit, Some(clause_index));
+                i += 1;
+            }
+            self.watches[watch_index(false_literal)] = watchers;
+        }
+        None
+    }
+
+    /// first unique implication point, returns the learnt clause and the backjump level
+    fn analyze(&self, conflict: usize) -> (Vec<Literal>, usize) {
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![0];
+        let mut counter = 0;
+        let mut clause_index = conflict;
+        let mut p = 0;
+        let mut index = self.trail.len();
+        loop {
+            for &q in &self.clauses[clause_index] {
+                let v = variable(q);
+                if q == p || seen[v] || self.levels[v] == 0 {
+                    continue;
+                }
+                seen[v] = true;
+                if self.levels[v] == self.decision_level() {
+                    counter += 1;
+                } else {
+                    learnt.push(q);
+                }
+            }
+            loop {
+                index -= 1;
+                if seen[variable(self.trail[index])] {
+                    break;
+                }
+            }
+            p = self.trail[index];
+            seen[variable(p)] = false;
+            counter -= 1;
+            if counter == 0 {
+                break;
+            }
+            clause_index = self.reasons[variable(p)].unwrap();
+        }
+        learnt[0] = -p;
+
+        let mut backjump_level = 0;
+        for i in 1..learnt.len() {
+            let level = self.levels[variable(learnt[i])];
+            if level > backjump_level {
+                backjump_level = level;
+                learnt.swap(1, i);
+            }
+        }
+        (learnt, backjump_level)
+    }
+
+    fn bump_activity(&mut self, learnt: &[Literal]) {
+        if self.bump == 0.0 {
+            self.bump = 1.0;
+        }
+        for &lit in learnt {
+            self.activity[variable(lit)] += self.bump;
+        }
+        // decaying every activity is the same as growing the bump
+        self.bump /= 0.95;
+        if self.bump > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.bump *= 1e-100;
+        }
+    }
+
+    /// unassigned variable with the highest activity, with its saved phase
+    fn pick_branching_literal(&self) -> Option<Literal> {
+        let mut best: Option<usize> = None;
+        for v in 1..self.values.len() {
+            if self.values[v].is_none() && best.is_none_or(|b| self.activity[v] > self.activity[b])
+            {
+                best = Some(v);
+            }
+        }
+        best.map(|v| {
+            if self.phases[v] {
+                v as Literal
+            } else {
+                -(v as Literal)
+            }
+        })
+    }
+
+    /// assumptions responsible for `failed` being false
+    fn analyze_final(&self, failed: Literal) -> Vec<Literal> {
+        let mut core = vec![failed];
+        if self.levels[variable(failed)] == 0 {
+            return core;
+        }
+        let mut seen = vec![false; self.values.len()];
+        seen[variable(failed)] = true;
+        for &lit in self.trail[self.trail_limits[0]..].iter().rev() {
+            let v = variable(lit);
+            if !seen[v] {
+                continue;
+            }
+            match self.reasons[v] {
+                None => core.push(lit),
+                Some(clause_index) => {
+                    for &q in &self.clauses[clause_index] {
+                        if variable(q) != v && self.levels[variable(q)] > 0 {
+                            seen[variable(q)] = true;
+                        }
+                    }
+                }
+            }
+            seen[v] = false;
+        }
+        core
+    }
+
+    pub fn solve(&mut self, assumptions: &[Literal]) -> SolverResult {
+        if let Some(max) = assumptions.iter().map(|&lit| variable(lit)).max() {
+            self.reserve_variables(max);
+        }
+        self.cancel_until(0);
+        if self.inconsistent {
+            return SolverResult::Unsatisfiable(vec![]);
+        }
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    self.inconsistent = true;
+                    return SolverResult::Unsatisfiable(vec![]);
+                }
+                let (learnt, backjump_level) = self.analyze(conflict);
+                self.bump_activity(&learnt);
+                self.cancel_until(backjump_level);
+                let asserting = learnt[0];
+                if learnt.len() == 1 {
+                    self.enqueue(asserting, None);
+                } else {
+                    let clause_index = self.attach(learnt);
+                    self.enqueue(asserting, Some(clause_index));
+                }
+            } else if self.decision_level() < assumptions.len() {
+                let assumption = assumptions[self.decision_level()];
+                match value(&self.values, assumption) {
+                    Some(true) => self.trail_limits.push(self.trail.len()),
+                    Some(false) => {
+                        let core = self.analyze_final(assumption);
+                        self.cancel_until(0);
+                        return SolverResult::Unsatisfiable(core);
+                    }
+                    None => self.decide(assumption),
+                }
+            } else if let Some(lit) = self.pick_branching_literal() {
+                self.decide(lit);
+            } else {
+                let model = self.values[1..].iter().map(|b| b.unwrap()).collect();
+                self.cancel_until(0);
+                return SolverResult::Satisfiable(model);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::cnf::literal_value, rand::Rng, std::collections::HashSet};
+
+    fn brute_force(cnf: &Cnf) -> bool {
+        (0..1u32 << cnf.num_variables).any(|mask| {
+            let model = (0..cnf.num_variables)
+                .map(|i| mask >> i & 1 == 1)
+                .collect::<Vec<_>>();
+            cnf.evaluate(&model)
+        })
+    }
+
+    fn pigeonhole(holes: usize) -> Cnf {
+        let pigeons = holes + 1;
+        let var = |p: usize, h: usize| (p * holes + h + 1) as Literal;
+        let mut cnf = Cnf::new();
+        for p in 0..pigeons {
+            cnf.add_clause((0..holes).map(|h| var(p, h)));
+        }
+        for h in 0..holes {
+            for p1 in 0..pigeons {
+                for p2 in p1 + 1..pigeons {
+                    cnf.add_clause([-var(p1, h), -var(p2, h)]);
+                }
+            }
+        }
+        cnf
+    }
+
+    #[test]
+    fn test_pigeonhole() {
+        for holes in 1..=5 {
+            assert_eq!(pigeonhole(holes).solve(), None);
+        }
+    }
+
+    #[test]
+    fn test_random_3sat() {
+        let mut rng = rand::rng();
+        for _ in 0..200 {
+            let mut cnf = Cnf::new();
+            cnf.num_variables = 8;
+            for _ in 0..rng.random_range(1..50) {
+                cnf.add_clause((0..3).map(|_| {
+                    let v = rng.random_range(1..=8);
+                    if rng.random() { v } else { -v }
+                }));
+            }
+            match cnf.solve() {
+                Some(model) => assert!(cnf.evaluate(&model)),
+                None => assert!(!brute_force(&cnf)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_branching_heuristic() {
+        let mut cnf = Cnf::new();
+        cnf.num_variables = 3;
+        let mut solver = Solver::new(&cnf);
+        // without activity, the first variable, negated
+        assert_eq!(solver.pick_branching_literal(), Some(-1));
+        solver.bump_activity(&[-2]);
+        solver.bump_activity(&[3, -2]);
+        assert_eq!(solver.pick_branching_literal(), Some(-2));
+
+        // the value of a variable is kept when it is unassigned
+        solver.decide(2);
+        assert_eq!(solver.pick_branching_literal(), Some(-3));
+        solver.decide(3);
+        solver.cancel_until(0);
+        assert_eq!(solver.pick_branching_literal(), Some(2));
+
+        // rescaling keeps the order of the activities
+        solver.bump = 9.9e99;
+        solver.bump_activity(&[1]);
+        assert!(solver.bump < 2.0 && solver.activity.iter().all(|a| a.is_finite()));
+        assert!(solver.activity[1] > solver.activity[2]);
+        assert!(solver.activity[2] > solver.activity[3]);
+        assert_eq!(solver.pick_branching_literal(), Some(-1));
+    }
+
+    #[test]
+    fn test_assumptions() {
+        let mut cnf = Cnf::new();
+        cnf.num_variables = 4;
+        cnf.add_clause([-1, 2]);
+        cnf.add_clause([-2, 3]);
+        let mut solver = Solver::new(&cnf);
+
+        let SolverResult::Satisfiable(model) = solver.solve(&[1]) else {
+            panic!("expected a model");
+        };
+        assert!(literal_value(&model, 3));
+
+        let SolverResult::Unsatisfiable(core) = solver.solve(&[4, 1, -3]) else {
+            panic!("expected a core");
+        };
+        assert_eq!(core.into_iter().collect::<HashSet<_>>(), [1, -3].into());
+
+        assert!(matches!(
+            solver.solve(&[-1, -3]),
+            SolverResult::Satisfiable(_)
+        ));
+        solver.add_clause(&[3]);
+        assert_eq!(solver.solve(&[-3]), SolverResult::Unsatisfiable(vec![-3]));
+        solver.add_clause(&[-2]);
+        solver.add_clause(&[1]);
+        assert_eq!(solver.solve(&[]), SolverResult::Unsatisfiable(vec![]));
+    }
+}
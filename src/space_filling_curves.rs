@@ -1,4 +1,4 @@
-/// Implementation of https://en.wikipedia.org/wiki/Z-order_curve
+//! Implementation of https://en.wikipedia.org/wiki/Z-order_curve
 
 /// Interleaves a 16-bit number with zeros
 /// http://graphics.stanford.edu/%7Eseander/bithacks.html#InterleaveBMN